@@ -0,0 +1,163 @@
+#ifndef MYPTHREADS_H
+#define MYPTHREADS_H
+
+/* Generado con cbindgen desde src/ffi.rs; no editar a mano. */
+
+#include <stddef.h>
+#include <stdint.h>
+#define MY_PTHREAD_MUTEX_INITIALIZER { NULL }
+#define MY_PTHREAD_COND_INITIALIZER { NULL }
+
+#define MY_SCHED_RR 0
+
+#define MY_SCHED_LOTTERY 1
+
+#define MY_SCHED_REALTIME 2
+
+#define MY_PTHREAD_MUTEX_NORMAL 0
+
+#define MY_PTHREAD_MUTEX_ERRORCHECK 1
+
+#define MY_PTHREAD_MUTEX_RECURSIVE 2
+
+#define MY_EPERM 1
+
+#define MY_ESRCH 3
+
+#define MY_EBUSY 16
+
+#define MY_EINVAL 22
+
+#define MY_EDEADLK 35
+
+/**
+ * Identificador de hilo (el ThreadId del runtime). El hilo principal es 0.
+ */
+typedef uint32_t my_pthread_t;
+
+/**
+ * Parametros de planificacion; NULL en `my_pthread_create` equivale a round robin
+ */
+typedef struct my_pthread_attr_t {
+  /**
+   * MY_SCHED_RR, MY_SCHED_LOTTERY o MY_SCHED_REALTIME
+   */
+  int policy;
+  /**
+   * solo para MY_SCHED_LOTTERY
+   */
+  uint32_t tickets;
+  /**
+   * solo para MY_SCHED_REALTIME, en ms del reloj del runtime
+   */
+  uint64_t deadline;
+} my_pthread_attr_t;
+
+/**
+ * Atributos de mutex; NULL en `my_pthread_mutex_init` equivale a errorcheck
+ */
+typedef struct my_pthread_mutexattr_t {
+  /**
+   * MY_PTHREAD_MUTEX_NORMAL, MY_PTHREAD_MUTEX_ERRORCHECK o MY_PTHREAD_MUTEX_RECURSIVE
+   */
+  int kind;
+} my_pthread_mutexattr_t;
+
+/**
+ * Mutex errorcheck; `MY_PTHREAD_MUTEX_INITIALIZER` lo deja listo sin `my_pthread_mutex_init`
+ */
+typedef struct my_pthread_mutex_t {
+  void *inner;
+} my_pthread_mutex_t;
+
+/**
+ * Variable de condicion; `MY_PTHREAD_COND_INITIALIZER` la deja lista
+ */
+typedef struct my_pthread_cond_t {
+  void *inner;
+} my_pthread_cond_t;
+
+/**
+ * Inicializa el runtime global; llamar una vez antes de crear hilos
+ */
+void my_pthread_runtime_init(void);
+
+/**
+ * Siembra el scheduler para que los sorteos se repitan
+ */
+int my_pthread_runtime_seed(uint64_t seed);
+
+/**
+ * Corre el scheduler hasta que no haya hilos listos o pasen `max_cycles`.
+ * Retorna cuantos hilos siguen vivos.
+ */
+size_t my_pthread_runtime_run(size_t max_cycles);
+
+int my_pthread_create(my_pthread_t *thread,
+                      const struct my_pthread_attr_t *attr,
+                      void *(*start)(void *arg),
+                      void *arg);
+
+/**
+ * Termina el hilo actual con `retval`. Desde el hilo principal corre los
+ * hilos restantes y termina el proceso.
+ */
+void my_pthread_exit(void *retval) __attribute__((noreturn));
+
+my_pthread_t my_pthread_self(void);
+
+/**
+ * Cede el procesador; desde el hilo principal corre un ciclo del scheduler
+ */
+int my_pthread_yield(void);
+
+/**
+ * Espera a que `thread` termine y guarda su valor de retorno en `retval` (si no es NULL)
+ */
+int my_pthread_join(my_pthread_t thread, void **retval);
+
+int my_pthread_detach(my_pthread_t thread);
+
+/**
+ * Cambia el planificador de un hilo
+ */
+int my_pthread_chsched(my_pthread_t thread, const struct my_pthread_attr_t *attr);
+
+int my_pthread_mutexattr_init(struct my_pthread_mutexattr_t *attr);
+
+int my_pthread_mutexattr_settype(struct my_pthread_mutexattr_t *attr, int kind);
+
+int my_pthread_mutexattr_gettype(const struct my_pthread_mutexattr_t *attr, int *kind);
+
+/**
+ * `attr` NULL crea un mutex errorcheck
+ */
+int my_pthread_mutex_init(struct my_pthread_mutex_t *mutex,
+                          const struct my_pthread_mutexattr_t *attr);
+
+int my_pthread_mutex_lock(struct my_pthread_mutex_t *mutex);
+
+int my_pthread_mutex_trylock(struct my_pthread_mutex_t *mutex);
+
+int my_pthread_mutex_unlock(struct my_pthread_mutex_t *mutex);
+
+int my_pthread_mutex_destroy(struct my_pthread_mutex_t *mutex);
+
+/**
+ * `attr` se ignora
+ */
+int my_pthread_cond_init(struct my_pthread_cond_t *cond, const void *attr);
+
+/**
+ * Libera `mutex`, espera un signal/broadcast y lo vuelve a tomar.
+ * Solo desde un hilo mypthreads (EPERM desde el principal o sin ser dueño del mutex).
+ */
+int my_pthread_cond_wait(struct my_pthread_cond_t *cond, struct my_pthread_mutex_t *mutex);
+
+int my_pthread_cond_signal(struct my_pthread_cond_t *cond);
+
+int my_pthread_cond_broadcast(struct my_pthread_cond_t *cond);
+
+int my_pthread_cond_destroy(struct my_pthread_cond_t *cond);
+
+#endif  /* MYPTHREADS_H */
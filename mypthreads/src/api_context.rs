@@ -1,11 +1,16 @@
-use crate::thread::ThreadId;
+use crate::error::MyThreadError;
+use crate::thread::{MyThread, ThreadId};
 use crate::signals::ThreadSignal;
-use crate::channels::{ThreadChannels, JoinHandle, SimpleMutex};
+use crate::channels::{HandoffRequest, ThreadChannels, JoinHandle, SimpleMutex};
+use crate::reactor::Interest;
+use crate::remote::{RemoteMessage, RemoteQueue};
+use crate::tls::{self, ThreadLocals};
 
 // Thread-local storage para que cada hilo sepa su tid y tenga acceso a los canales
 thread_local! {
     static CURRENT_TID: std::cell::RefCell<Option<ThreadId>> = std::cell::RefCell::new(None);
     static CHANNELS: std::cell::RefCell<Option<ThreadChannels>> = std::cell::RefCell::new(None);
+    static CURRENT_THREAD: std::cell::Cell<Option<*mut MyThread>> = const { std::cell::Cell::new(None) };
 }
 
 /// inicializa el contexto del hilo actual
@@ -14,6 +19,43 @@ pub fn init_thread_context(tid: ThreadId, channels: ThreadChannels) {
     CHANNELS.with(|c| *c.borrow_mut() = Some(channels));
 }
 
+/// contexto que estaba activo antes de despertar a un hilo verde
+pub(crate) struct SavedContext {
+    thread: Option<*mut MyThread>,
+    tid: Option<ThreadId>,
+    channels: Option<ThreadChannels>,
+    locals: ThreadLocals,
+}
+
+/// instala tid, canales y valores locales del hilo que va a correr
+pub(crate) fn enter_thread(
+    thread: *mut MyThread,
+    tid: ThreadId,
+    channels: ThreadChannels,
+    locals: ThreadLocals,
+) -> SavedContext {
+    SavedContext {
+        thread: CURRENT_THREAD.with(|t| t.replace(Some(thread))),
+        tid: CURRENT_TID.with(|t| t.borrow_mut().replace(tid)),
+        channels: CHANNELS.with(|c| c.borrow_mut().replace(channels)),
+        locals: tls::swap_active(locals),
+    }
+}
+
+/// restaura el contexto previo y devuelve los valores locales del hilo que sale
+pub(crate) fn leave_thread(saved: SavedContext) -> ThreadLocals {
+    CURRENT_THREAD.with(|t| t.set(saved.thread));
+    CURRENT_TID.with(|t| *t.borrow_mut() = saved.tid);
+    CHANNELS.with(|c| *c.borrow_mut() = saved.channels);
+    tls::swap_active(saved.locals)
+}
+
+/// true mientras el hilo del sistema corre el paso de un hilo verde, es
+/// decir, cuando la llamada viene del despachador que lo esta corriendo
+pub(crate) fn in_dispatch() -> bool {
+    CURRENT_THREAD.with(|t| t.get().is_some())
+}
+
 /// obtiene el tid del hilo actual
 pub fn current_tid() -> ThreadId {
     CURRENT_TID.with(|t| {
@@ -28,6 +70,100 @@ fn channels() -> ThreadChannels {
     })
 }
 
+/// Suspende el hilo actual en medio de un paso: el runtime procesa `signal`
+/// como si el paso hubiera terminado, y esta funcion retorna (con los tiquetes
+/// del nuevo despacho) cuando el hilo vuelve a correr. Con `Exit` no retorna.
+///
+/// Permite escribir hilos "bloqueantes" (p. ej. los de la capa C) en lugar de
+/// maquinas de estados que devuelven una señal por paso.
+pub fn ctx_suspend(signal: ThreadSignal) -> u32 {
+    let thread = CURRENT_THREAD
+        .with(|t| t.get())
+        .expect("ctx_suspend llamado fuera de un hilo mypthreads");
+    if matches!(signal, ThreadSignal::Exit) {
+        tls::run_destructors();
+    }
+    unsafe { crate::thread::park(thread, signal.into()) }
+}
+
+/// Pide al runtime que despierte a `tid` cuando termine el paso actual
+/// (si para entonces sigue bloqueado). Fuera de un hilo no hace nada.
+pub fn ctx_wake(tid: ThreadId) {
+    CHANNELS.with(|c| {
+        if let Some(channels) = c.borrow().as_ref() {
+            channels.request_wake(tid);
+        }
+    });
+}
+
+/// Pide al runtime que entregue un mutex al terminar el paso actual, igual
+/// que con la señal `MutexUnlock`. Fuera de un hilo no hace nada.
+pub(crate) fn ctx_hand_off(handoff: HandoffRequest) {
+    CHANNELS.with(|c| {
+        if let Some(channels) = c.borrow().as_ref() {
+            channels.request_handoff(handoff);
+        }
+    });
+}
+
+/// cuenta un mutex que el hilo actual tomo sin esperar
+pub(crate) fn ctx_mutex_acquired() {
+    if let Some(thread) = CURRENT_THREAD.with(|t| t.get()) {
+        unsafe { (*thread).stats.mutex_acquisitions += 1 };
+    }
+}
+
+/// Espera dentro de un paso a que `fd` este listo: el hilo se anota en el
+/// reactor del runtime y se suspende hasta que el scheduler lo despierte.
+/// Puede volver antes (p. ej. `runtime_unblock_all`), asi que hay que reintentar.
+pub fn ctx_wait_fd(fd: std::os::fd::RawFd, interest: Interest) -> std::io::Result<()> {
+    let tid = current_tid();
+    let channels = channels();
+    channels.reactor().register(fd, interest, tid)?;
+    ctx_suspend(ThreadSignal::Block);
+    channels.reactor().remove(fd, tid);
+    Ok(())
+}
+
+/// Saca el siguiente mensaje que le mandaron al hilo con `RuntimeHandle::send`;
+/// si no hay, el hilo se suspende hasta que llegue uno.
+pub fn ctx_remote_recv() -> RemoteMessage {
+    let tid = current_tid();
+    let remote = channels().remote().clone();
+    loop {
+        if let Some(message) = remote.recv(tid, true) {
+            return message;
+        }
+        ctx_suspend(ThreadSignal::Block);
+    }
+}
+
+/// como `ctx_remote_recv` pero sin esperar
+pub fn ctx_remote_try_recv() -> Option<RemoteMessage> {
+    channels().remote().recv(current_tid(), false)
+}
+
+/// cola remota del runtime que corre al hilo actual (None fuera de un hilo)
+pub(crate) fn current_remote() -> Option<std::sync::Arc<RemoteQueue>> {
+    CHANNELS.with(|c| c.borrow().as_ref().map(|channels| channels.remote().clone()))
+}
+
+/// Saca el error que dejo el runtime al procesar la ultima señal del hilo
+/// (ver `my_thread_last_error`). Fuera de un hilo es None.
+pub fn ctx_last_error() -> Option<MyThreadError> {
+    CURRENT_THREAD
+        .with(|t| t.get())
+        .and_then(|thread| unsafe { (*thread).last_error.take() })
+}
+
+/// Avisa al watchdog que el paso actual hizo trabajo util, para que un hilo
+/// que se bloquea en cada ronda no cuente como livelock. Fuera de un hilo no hace nada.
+pub fn ctx_progress() {
+    if let Some(thread) = CURRENT_THREAD.with(|t| t.get()) {
+        unsafe { (*thread).watch.progressed = true };
+    }
+}
+
 /// el hilo cede el control (yield)
 pub fn ctx_yield() -> ThreadSignal {
     let tid = current_tid();
@@ -61,11 +197,11 @@ pub fn ctx_join(join_handle: &JoinHandle) -> ThreadSignal {
 /// intenta adquirir un mutex
 pub fn ctx_mutex_lock(mutex: &SimpleMutex) -> ThreadSignal {
     let tid = current_tid();
-    if mutex.lock(tid) {
+    if mutex.lock(tid) == Ok(true) {
         // debe bloquearse
         ctx_block()
     } else {
-        // adquirió el lock
+        // adquirió el lock (o ya era el dueño de un errorcheck)
         ThreadSignal::Continue
     }
 }
@@ -73,9 +209,7 @@ pub fn ctx_mutex_lock(mutex: &SimpleMutex) -> ThreadSignal {
 /// libera un mutex
 pub fn ctx_mutex_unlock(mutex: &SimpleMutex) -> ThreadSignal {
     let tid = current_tid();
-    if let Some(next_tid) = mutex.unlock(tid) {
-        let _ = next_tid;
-    }
+    let _ = mutex.unlock(tid);
     ThreadSignal::Continue
 }
 
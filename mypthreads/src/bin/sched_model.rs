@@ -0,0 +1,44 @@
+use mypthreads::TaskSet;
+use std::env;
+use std::process;
+
+// uso: sched_model <tareas.txt> [--seed N] [--horizon MS] [--json]
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut path = None;
+    let mut seed = 0;
+    let mut horizon_ms = 1000;
+    let mut json = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--seed" => seed = number(iter.next(), "--seed"),
+            "--horizon" => horizon_ms = number(iter.next(), "--horizon"),
+            "--json" => json = true,
+            other => path = Some(other.to_string()),
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("uso: sched_model <tareas.txt> [--seed N] [--horizon MS] [--json]");
+        process::exit(2);
+    };
+
+    let tasks = TaskSet::load(&path).unwrap_or_else(|e| {
+        eprintln!("no se pudo leer {}: {}", path, e);
+        process::exit(1);
+    });
+    let report = tasks.simulate(seed, horizon_ms);
+    if json {
+        print!("{}", report.to_json());
+    } else {
+        print!("{}", report.to_text());
+    }
+}
+
+fn number(value: Option<&String>, flag: &str) -> u64 {
+    value.and_then(|v| v.parse().ok()).unwrap_or_else(|| {
+        eprintln!("{} necesita un numero", flag);
+        process::exit(2);
+    })
+}
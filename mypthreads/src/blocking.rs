@@ -0,0 +1,43 @@
+//! pool de hilos del sistema para trabajo que bloquea (syscalls, stdout, sleep...)
+//! sin frenar al runtime de hilos verdes
+
+use once_cell::sync::Lazy;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// hilos del sistema que atienden las tareas bloqueantes
+pub const BLOCKING_POOL_THREADS: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Cola de tareas compartida por los hilos del pool. Los hilos se crean la
+/// primera vez que alguien manda una tarea y viven lo que dure el proceso.
+static POOL: Lazy<Mutex<Sender<Job>>> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    for i in 0..BLOCKING_POOL_THREADS {
+        let receiver = receiver.clone();
+        thread::Builder::new()
+            .name(format!("mypthreads-blocking-{}", i))
+            .spawn(move || worker(&receiver))
+            .expect("no se pudo crear el hilo del pool bloqueante");
+    }
+    Mutex::new(sender)
+});
+
+fn worker(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // el lock solo dura lo que tarda en sacar la siguiente tarea
+        let job = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+/// encola `job` para que la corra algun hilo del pool
+pub(crate) fn submit(job: Job) {
+    let _ = POOL.lock().unwrap_or_else(|e| e.into_inner()).send(job);
+}
@@ -1,13 +1,16 @@
 //! canales de comunicacion entre hilos y runtime
+use crate::error::{MyResult, MyThreadError};
+use crate::reactor::Reactor;
+use crate::remote::RemoteQueue;
 use crate::shared;
 use crate::thread::ThreadId;
 use crate::sync::{Shared};
 use std::cell::UnsafeCell;
 use std::collections::{HashMap, VecDeque};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::sync::atomic::{AtomicU32, Ordering};
 
-const UNLOCKED: u32 = u32::MAX;
+pub const UNLOCKED: u32 = u32::MAX;
 
 /// canales de comunicacion del runtime
 #[derive(Clone)]
@@ -23,6 +26,25 @@ pub struct ThreadChannels {
 
     /// datos compartidos entre hilos
     pub shared_data: Shared<HashMap<String, SharedData>>,
+
+    /// hilos bloqueados que otro hilo pidio despertar (ver `ctx_wake`)
+    wake_requests: Arc<Mutex<Vec<ThreadId>>>,
+
+    /// mutex que un hilo libero con alguien esperando (ver `my_mutex_unlock`)
+    handoff_requests: Arc<Mutex<Vec<HandoffRequest>>>,
+
+    /// hilos esperando E/S (ver `ctx_wait_fd`)
+    reactor: Arc<Mutex<Reactor>>,
+
+    /// pedidos y mensajes de otros hilos del sistema (ver `RuntimeHandle`)
+    remote: Arc<RemoteQueue>,
+}
+
+/// mutex que `from` libero y le toca a `to`; `waiters` siguen en la cola
+pub(crate) struct HandoffRequest {
+    pub from: ThreadId,
+    pub to: ThreadId,
+    pub waiters: Vec<ThreadId>,
 }
 
 /// datos que se pueden compartir entre hilos
@@ -41,6 +63,10 @@ impl ThreadChannels {
             blocked_queue: shared(VecDeque::new()),
             terminated_queue: shared(VecDeque::new()),
             shared_data: shared(HashMap::new()),
+            wake_requests: Arc::new(Mutex::new(Vec::new())),
+            handoff_requests: Arc::new(Mutex::new(Vec::new())),
+            reactor: Arc::new(Mutex::new(Reactor::new())),
+            remote: Arc::new(RemoteQueue::new()),
         }
     }
 
@@ -48,7 +74,7 @@ impl ThreadChannels {
     pub fn report_yield(&self, tid: ThreadId) {
         if let Some(mut q) = self.yield_queue.try_enter() {
             q.push_back(tid);
-            self.yield_queue.request_unlock();
+            let _ = self.yield_queue.request_unlock();
         }
     }
 
@@ -56,7 +82,7 @@ impl ThreadChannels {
     pub fn report_block(&self, tid: ThreadId) {
         if let Some(mut q) = self.blocked_queue.try_enter() {
             q.push_back(tid);
-            self.blocked_queue.request_unlock();
+            let _ = self.blocked_queue.request_unlock();
         }
     }
 
@@ -64,15 +90,42 @@ impl ThreadChannels {
     pub fn report_exit(&self, tid: ThreadId) {
         if let Some(mut q) = self.terminated_queue.try_enter() {
             q.push_back(tid);
-            self.terminated_queue.request_unlock();
+            let _ = self.terminated_queue.request_unlock();
         }
     }
 
+    /// pide despertar a un hilo bloqueado al terminar el paso actual
+    pub fn request_wake(&self, tid: ThreadId) {
+        self.wake_requests.lock().unwrap_or_else(|e| e.into_inner()).push(tid);
+    }
+
+    pub(crate) fn take_wake_requests(&self) -> Vec<ThreadId> {
+        std::mem::take(&mut *self.wake_requests.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// pide al runtime entregar un mutex al terminar el paso actual
+    pub(crate) fn request_handoff(&self, handoff: HandoffRequest) {
+        self.handoff_requests.lock().unwrap_or_else(|e| e.into_inner()).push(handoff);
+    }
+
+    pub(crate) fn take_handoff_requests(&self) -> Vec<HandoffRequest> {
+        std::mem::take(&mut *self.handoff_requests.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// reactor compartido por el runtime y sus hilos
+    pub(crate) fn reactor(&self) -> MutexGuard<'_, Reactor> {
+        self.reactor.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn remote(&self) -> &Arc<RemoteQueue> {
+        &self.remote
+    }
+
     /// guardar dato compartido
     pub fn store(&self, key: String, data: SharedData) {
         if let Some(mut map) = self.shared_data.try_enter() {
             map.insert(key, data);
-            self.shared_data.request_unlock();
+            let _ = self.shared_data.request_unlock();
         }
     }
 
@@ -80,7 +133,7 @@ impl ThreadChannels {
     pub fn get(&self, key: &str) -> Option<SharedData> {
         if let Some(map) = self.shared_data.try_enter() {
             let result = map.get(key).cloned();
-            self.shared_data.request_unlock();
+            let _ = self.shared_data.request_unlock();
             result
         } else {
             None
@@ -104,14 +157,14 @@ impl JoinHandle {
     pub fn mark_terminated(&self) {
         if let Some(mut flag) = self.terminated.try_enter() {
             *flag = true;
-            self.terminated.request_unlock();
+            let _ = self.terminated.request_unlock();
         }
     }
 
     pub fn is_terminated(&self) -> bool {
         if let Some(flag) = self.terminated.try_enter() {
             let result = *flag;
-            self.terminated.request_unlock();
+            let _ = self.terminated.request_unlock();
             result
         } else {
             false
@@ -119,6 +172,20 @@ impl JoinHandle {
     }
 }
 
+/// Comportamiento de un mutex cuando el dueño lo vuelve a tomar, como los
+/// tipos de pthreads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MutexKind {
+    /// `PTHREAD_MUTEX_NORMAL`: volver a tomarlo deja al dueño esperando para siempre
+    Normal,
+    /// `PTHREAD_MUTEX_ERRORCHECK`: volver a tomarlo es un error (EDEADLK)
+    #[default]
+    ErrorCheck,
+    /// `PTHREAD_MUTEX_RECURSIVE`: el dueño lo puede tomar varias veces y lo
+    /// libera cuando hace la misma cantidad de unlocks
+    Recursive,
+}
+
 /// mutex simple para sincronizacion entre hilos
 #[derive(Clone)]
 pub struct SimpleMutex {
@@ -128,6 +195,10 @@ pub struct SimpleMutex {
 
     /// Cola de hilos esperando por el mutex.
     pub wait_queue: Arc<UnsafeCell<VecDeque<ThreadId>>>,
+
+    pub kind: MutexKind,
+    /// locks extra del dueño de un mutex recursivo
+    depth: Arc<AtomicU32>,
 }
 
 unsafe impl Send for SimpleMutex {}
@@ -135,54 +206,78 @@ unsafe impl Sync for SimpleMutex {}
 
 impl SimpleMutex {
     pub fn new() -> Self {
+        Self::with_kind(MutexKind::default())
+    }
+
+    pub fn with_kind(kind: MutexKind) -> Self {
         Self {
             owner: Arc::new(AtomicU32::new(UNLOCKED)),
             wait_queue: Arc::new(UnsafeCell::new(VecDeque::new())),
+            kind,
+            depth: Arc::new(AtomicU32::new(0)),
         }
     }
 
+    /// Toma el lock si está libre (o si es recursivo y `tid` ya es el dueño)
     pub fn try_lock(&self, tid: ThreadId) -> bool {
-        self.owner
+        let acquired = self.owner
             .compare_exchange(
                 UNLOCKED,
                 tid,
                 Ordering::Acquire,
                 Ordering::Relaxed,
             )
-            .is_ok()
+            .is_ok();
+        if !acquired && self.kind == MutexKind::Recursive && self.owner.load(Ordering::Relaxed) == tid {
+            self.depth.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        acquired
     }
 
-    pub fn lock(&self, tid: ThreadId) -> bool {
+    /// Toma el lock o encola a `tid`; retorna true si debe bloquearse.
+    /// Error `Deadlock` si el dueño vuelve a tomar un mutex errorcheck.
+    pub fn lock(&self, tid: ThreadId) -> MyResult<bool> {
         if self.try_lock(tid) {
-            false
-        } else {
-            let queue = unsafe { &mut *self.wait_queue.get() };
-            queue.push_back(tid);
-            true
+            return Ok(false);
         }
+        if self.kind == MutexKind::ErrorCheck && self.owner.load(Ordering::Relaxed) == tid {
+            return Err(MyThreadError::Deadlock);
+        }
+        let queue = unsafe { &mut *self.wait_queue.get() };
+        queue.push_back(tid);
+        Ok(true)
     }
 
-    pub fn unlock(&self, tid: ThreadId) -> Option<ThreadId> {
-        let current_owner = self.owner.load(Ordering::Relaxed);
-        if current_owner != tid {
-            panic!(
-                "hilo {:?} intenta liberar mutex que no posee (dueño actual: {:?})",
-                tid, current_owner
-            );
+    /// cuantas veces mas tiene que liberarlo el dueño antes de soltarlo
+    pub fn recursion_depth(&self) -> u32 {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// Libera el lock y se lo pasa al primero en la cola (lo retorna).
+    /// Error `NotOwner` si `tid` no es el dueño.
+    pub fn unlock(&self, tid: ThreadId) -> MyResult<Option<ThreadId>> {
+        if self.owner.load(Ordering::Relaxed) != tid {
+            return Err(MyThreadError::NotOwner);
+        }
+        if self.depth.load(Ordering::Relaxed) > 0 {
+            self.depth.fetch_sub(1, Ordering::Relaxed);
+            return Ok(None);
         }
 
         let queue = unsafe { &mut *self.wait_queue.get() };
 
         if let Some(next_tid) = queue.pop_front() {
             self.owner.store(next_tid, Ordering::Release);
-            Some(next_tid)
+            Ok(Some(next_tid))
         } else {
             self.owner.store(UNLOCKED, Ordering::Release);
-            None
+            Ok(None)
         }
     }
 
     pub fn force_unlock(&self) {
+        self.depth.store(0, Ordering::Relaxed);
         let queue = unsafe { &mut *self.wait_queue.get() };
         if let Some(next_tid) = queue.pop_front() {
             self.owner.store(next_tid, Ordering::Release);
@@ -3,15 +3,25 @@ use context::stack::ProtectedFixedSizeStack;
 
 const STACK_SIZE: usize = 8192; // 8kb por hilo
 
+/// con esto se pinta la pila nueva; lo que siga intacto nunca se uso
+const STACK_PAINT: u8 = 0xA5;
+
 pub struct ThreadContext {
     pub context: Option<Context>, 
-    _stack: Box<ProtectedFixedSizeStack>,
+    stack: Box<ProtectedFixedSizeStack>,
 }
 
 impl ThreadContext {
     pub fn new(entry: extern "C" fn(Transfer) -> !) -> Self {
-        let stack = ProtectedFixedSizeStack::new(STACK_SIZE)
+        Self::with_stack_size(entry, STACK_SIZE)
+    }
+
+    /// como `new` pero con una pila de `size` bytes
+    pub fn with_stack_size(entry: extern "C" fn(Transfer) -> !, size: usize) -> Self {
+        let stack = ProtectedFixedSizeStack::new(size)
             .expect("no se pudo crear la pila");
+        // se pinta antes de que Context::new arme el primer marco en el tope
+        unsafe { std::ptr::write_bytes(stack.bottom() as *mut u8, STACK_PAINT, stack.len()) };
         
         let context = unsafe {
             Context::new(&stack, entry)
@@ -19,7 +29,7 @@ impl ThreadContext {
         
         Self {
             context: Some(context),
-            _stack: Box::new(stack),
+            stack: Box::new(stack),
         }
     }
 
@@ -36,13 +46,27 @@ impl ThreadContext {
         transfer.data
     }
 
+    /// tamaño real de la pila (puede ser mas que lo pedido, se redondea a paginas)
+    pub fn stack_size(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Bytes de pila que se llegaron a usar. La pila crece hacia abajo: se
+    /// cuenta lo pintado que sigue intacto desde el fondo y el resto se uso.
+    /// Es aproximado: un byte escrito con el mismo valor del patron no se ve.
+    pub fn stack_high_water(&self) -> usize {
+        let bytes = unsafe { std::slice::from_raw_parts(self.stack.bottom() as *const u8, self.stack.len()) };
+        let untouched = bytes.iter().take_while(|&&b| b == STACK_PAINT).count();
+        bytes.len() - untouched
+    }
+
     pub fn new_runtime() -> Self {
         let stack = ProtectedFixedSizeStack::new(STACK_SIZE)
             .expect("no se pudo crear la pila del runtime");
         
         Self {
             context: None,
-            _stack: Box::new(stack),
+            stack: Box::new(stack),
         }
     }
 }
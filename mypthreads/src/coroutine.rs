@@ -0,0 +1,147 @@
+//! corrutinas y generadores sobre `ThreadContext`, sin pasar por el scheduler
+//!
+//! `resume` corre el cuerpo en su propia pila hasta el siguiente `suspend` (o
+//! hasta que termina) y vuelve al que llamo. Los valores viajan tipados en
+//! lugar de empacados en un `usize` como los `TransferMessage` de los hilos.
+
+use crate::context_wrapper::ThreadContext;
+use context::{Context, Transfer};
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+/// Lo que devuelve `resume`: un valor intermedio o el resultado final
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoroutineState<Y, T> {
+    Yielded(Y),
+    Complete(T),
+}
+
+/// pila por defecto; mas grande que la de un hilo porque un panico del
+/// cuerpo se desenrolla en ella
+pub const COROUTINE_STACK_SIZE: usize = 64 * 1024;
+
+type Body<Y, R, T> = Box<dyn FnOnce(&mut Yielder<Y, R>, R) -> T>;
+
+/// valores que se pasan en cada cambio de contexto
+struct Channel<Y, R> {
+    input: Option<R>,
+    yielded: Option<Y>,
+    /// a donde volver en el proximo suspend
+    caller: Option<Context>,
+}
+
+/// vive en el heap para que la corrutina lo encuentre aunque se mueva el `Coroutine`
+struct Frame<Y, R, T> {
+    channel: Channel<Y, R>,
+    body: Option<Body<Y, R, T>>,
+    result: Option<thread::Result<T>>,
+}
+
+/// Corrutina con su propia pila: recibe valores `R`, entrega valores `Y` y
+/// termina con un `T`. Si se suelta sin terminar, lo que viva en su pila no
+/// se libera.
+pub struct Coroutine<Y, R = (), T = ()> {
+    context: ThreadContext,
+    frame: Box<Frame<Y, R, T>>,
+    done: bool,
+}
+
+/// Lo que usa el cuerpo de la corrutina para ceder el control
+pub struct Yielder<Y, R> {
+    channel: *mut Channel<Y, R>,
+}
+
+impl<Y, R> Yielder<Y, R> {
+    /// entrega `value` al que llamo `resume` y espera el siguiente valor
+    pub fn suspend(&mut self, value: Y) -> R {
+        let channel = unsafe { &mut *self.channel };
+        channel.yielded = Some(value);
+        let caller = channel.caller.take().expect("la corrutina no sabe a donde volver");
+        let transfer = unsafe { caller.resume(0) };
+
+        let channel = unsafe { &mut *self.channel };
+        channel.caller = Some(transfer.context);
+        channel.input.take().expect("resume sin valor")
+    }
+}
+
+impl<Y, R, T> Coroutine<Y, R, T> {
+    /// Crea la corrutina sin correrla; el primer `resume` le pasa su primer valor.
+    pub fn new(body: impl FnOnce(&mut Yielder<Y, R>, R) -> T + 'static) -> Self {
+        Self::with_stack_size(COROUTINE_STACK_SIZE, body)
+    }
+
+    /// como `new` pero con una pila de `size` bytes
+    pub fn with_stack_size(size: usize, body: impl FnOnce(&mut Yielder<Y, R>, R) -> T + 'static) -> Self {
+        Self {
+            context: ThreadContext::with_stack_size(coroutine_entry::<Y, R, T>, size),
+            frame: Box::new(Frame {
+                channel: Channel {
+                    input: None,
+                    yielded: None,
+                    caller: None,
+                },
+                body: Some(Box::new(body)),
+                result: None,
+            }),
+            done: false,
+        }
+    }
+
+    /// true cuando el cuerpo ya retorno
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Corre la corrutina hasta el siguiente `suspend` o hasta que termine.
+    /// Un panico del cuerpo se vuelve a lanzar aqui. Entra en panico si ya termino.
+    pub fn resume(&mut self, input: R) -> CoroutineState<Y, T> {
+        assert!(!self.done, "la corrutina ya termino");
+        self.frame.channel.input = Some(input);
+        let frame = &mut *self.frame as *mut Frame<Y, R, T>;
+        unsafe { self.context.resume_with_data(frame as usize) };
+
+        if let Some(value) = self.frame.channel.yielded.take() {
+            return CoroutineState::Yielded(value);
+        }
+        self.done = true;
+        match self.frame.result.take().expect("la corrutina volvio sin resultado") {
+            Ok(result) => CoroutineState::Complete(result),
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+/// Un generador es una corrutina que no recibe nada: se recorre como iterador
+impl<Y, T> Iterator for Coroutine<Y, (), T> {
+    type Item = Y;
+
+    fn next(&mut self) -> Option<Y> {
+        if self.done {
+            return None;
+        }
+        match self.resume(()) {
+            CoroutineState::Yielded(value) => Some(value),
+            CoroutineState::Complete(_) => None,
+        }
+    }
+}
+
+/// Corre en la pila de la corrutina. El panico no puede cruzar el cambio de
+/// contexto, asi que se guarda y se relanza en `resume`.
+extern "C" fn coroutine_entry<Y, R, T>(transfer: Transfer) -> ! {
+    let frame = unsafe { &mut *(transfer.data as *mut Frame<Y, R, T>) };
+    frame.channel.caller = Some(transfer.context);
+    let body = frame.body.take().expect("la corrutina ya arranco");
+    let input = frame.channel.input.take().expect("resume sin valor");
+
+    let mut yielder = Yielder {
+        channel: &mut frame.channel,
+    };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| body(&mut yielder, input)));
+    frame.result = Some(result);
+
+    let caller = frame.channel.caller.take().expect("la corrutina no sabe a donde volver");
+    unsafe { caller.resume(0) };
+    unreachable!("se reanudo una corrutina terminada");
+}
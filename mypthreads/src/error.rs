@@ -0,0 +1,70 @@
+//! errores de la API de mypthreads, con el codigo errno equivalente de pthreads
+
+use crate::thread::ThreadId;
+use std::fmt;
+
+// codigos errno de Linux
+pub const EPERM: i32 = 1;
+pub const ESRCH: i32 = 3;
+pub const EBUSY: i32 = 16;
+pub const EINVAL: i32 = 22;
+pub const EDEADLK: i32 = 35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MyThreadError {
+    /// EINVAL: falta llamar a `runtime_init()`
+    NotInitialized,
+    /// EINVAL: parametro invalido (grupo, moneda o llave inexistente, tiquetes en 0...)
+    InvalidArgument(&'static str),
+    /// ESRCH: no hay un hilo con ese id (o ya termino, segun la operacion)
+    NoSuchThread(ThreadId),
+    /// EDEADLK: la operacion esperaria por el mismo hilo que la pide
+    Deadlock,
+    /// EPERM: el hilo no es dueño del recurso (p. ej. liberar un mutex ajeno)
+    NotOwner,
+    /// EPERM: solo se puede llamar desde un hilo mypthreads
+    NotInThread,
+    /// EBUSY: el recurso esta tomado
+    Busy,
+    /// fallo una llamada de E/S; guarda su errno (EAGAIN si el principal no puede esperar)
+    Io(i32),
+    /// EIO: la tarea de `my_spawn_blocking` entro en panico
+    Panicked,
+}
+
+pub type MyResult<T> = Result<T, MyThreadError>;
+
+impl MyThreadError {
+    /// codigo errno equivalente, el que devolveria pthreads
+    pub fn errno(&self) -> i32 {
+        match self {
+            MyThreadError::NotInitialized | MyThreadError::InvalidArgument(_) => EINVAL,
+            MyThreadError::NoSuchThread(_) => ESRCH,
+            MyThreadError::Deadlock => EDEADLK,
+            MyThreadError::NotOwner | MyThreadError::NotInThread => EPERM,
+            MyThreadError::Busy => EBUSY,
+            MyThreadError::Io(errno) => *errno,
+            MyThreadError::Panicked => libc::EIO,
+        }
+    }
+}
+
+impl fmt::Display for MyThreadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MyThreadError::NotInitialized => {
+                write!(f, "runtime no inicializado: llamá a runtime_init() antes de usar la API")
+            }
+            MyThreadError::InvalidArgument(what) => write!(f, "argumento invalido: {}", what),
+            MyThreadError::NoSuchThread(tid) => write!(f, "no existe el hilo {}", tid),
+            MyThreadError::Deadlock => write!(f, "la operacion causaria un deadlock"),
+            MyThreadError::NotOwner => write!(f, "el hilo no es dueño del recurso"),
+            MyThreadError::NotInThread => write!(f, "solo se puede llamar desde un hilo mypthreads"),
+            MyThreadError::Busy => write!(f, "recurso ocupado"),
+            MyThreadError::Io(errno) => write!(f, "error de E/S: {}", std::io::Error::from_raw_os_error(*errno)),
+            MyThreadError::Panicked => write!(f, "la tarea bloqueante entro en panico"),
+        }
+    }
+}
+
+impl std::error::Error for MyThreadError {}
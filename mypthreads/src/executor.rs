@@ -0,0 +1,273 @@
+//! puente async/await: un `Future` corre dentro de un hilo verde y cada
+//! `Poll::Pending` bloquea solo a ese hilo hasta que su waker lo despierte.
+//! La politica (sorteo, tiempo real...) la sigue poniendo el scheduler.
+
+use crate::api_context;
+use crate::mypthreads_api::{is_runtime_owner, with_runtime};
+use crate::reactor::IO_POLL_TIMEOUT_MS;
+use crate::remote::RemoteQueue;
+use crate::signals::ThreadSignal;
+use crate::thread::ThreadId;
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::Thread;
+use std::time::Duration;
+
+/// a quien hay que despertar cuando el future avisa
+enum WakeTarget {
+    /// hilo verde: el despertar pasa por la cola remota, se puede pedir desde cualquier hilo
+    Green { tid: ThreadId, remote: Arc<RemoteQueue> },
+    /// el hilo principal (o cualquier hilo del sistema) espera con park
+    Os(Thread),
+}
+
+struct ThreadWaker {
+    target: WakeTarget,
+    /// el hilo esta suspendido esperando a este waker
+    parked: AtomicBool,
+    /// alguien llamo a wake desde el ultimo poll
+    notified: AtomicBool,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.notified.store(true, Ordering::SeqCst);
+        match &self.target {
+            // solo se despierta si de verdad esta esperando al future, para no
+            // sacar al hilo de otra espera (p. ej. un mutex)
+            WakeTarget::Green { tid, remote } => {
+                if self.parked.swap(false, Ordering::SeqCst) {
+                    remote.request_wake(*tid);
+                }
+            }
+            WakeTarget::Os(thread) => thread.unpark(),
+        }
+    }
+}
+
+/// Corre `future` hasta que termine. En un hilo verde, cada `Pending` lo
+/// suspende y el runtime sigue con los demas; fuera de uno se usa el hilo
+/// del sistema. Si es el que creo el runtime global y quedan hilos vivos, se
+/// lo va corriendo; cualquier otro hilo del sistema solo espera su wake.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let target = match (api_context::try_current_tid(), api_context::current_remote()) {
+        (Some(tid), Some(remote)) => WakeTarget::Green { tid, remote },
+        _ => WakeTarget::Os(std::thread::current()),
+    };
+    let green = matches!(target, WakeTarget::Green { .. });
+    let state = Arc::new(ThreadWaker {
+        target,
+        parked: AtomicBool::new(false),
+        notified: AtomicBool::new(false),
+    });
+    let waker = Waker::from(state.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        state.notified.store(false, Ordering::SeqCst);
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        if green {
+            state.parked.store(true, Ordering::SeqCst);
+            // el wake llego durante el poll: si nadie encolo el despertar se
+            // vuelve a intentar; si ya se encolo, se espera a que llegue
+            if state.notified.swap(false, Ordering::SeqCst) && state.parked.swap(false, Ordering::SeqCst) {
+                continue;
+            }
+            api_context::ctx_suspend(ThreadSignal::Block);
+            state.parked.store(false, Ordering::SeqCst);
+        } else if !state.notified.load(Ordering::SeqCst) {
+            let ran = is_runtime_owner()
+                && with_runtime(|rt| {
+                    let alive = !rt.ready.is_empty() || !rt.blocked.is_empty();
+                    if alive {
+                        rt.run_once();
+                    }
+                    alive
+                })
+                .unwrap_or(false);
+            if !ran {
+                std::thread::park_timeout(Duration::from_millis(IO_POLL_TIMEOUT_MS as u64));
+            }
+        }
+    }
+}
+
+/// Mutex para futures: `lock().await` no bloquea al hilo verde, solo a la tarea
+pub struct AsyncMutex<T> {
+    state: Mutex<AsyncMutexState>,
+    data: UnsafeCell<T>,
+}
+
+#[derive(Default)]
+struct AsyncMutexState {
+    locked: bool,
+    waiters: VecDeque<Waker>,
+}
+
+unsafe impl<T: Send> Send for AsyncMutex<T> {}
+unsafe impl<T: Send> Sync for AsyncMutex<T> {}
+
+impl<T> AsyncMutex<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            state: Mutex::new(AsyncMutexState::default()),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, AsyncMutexState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// espera hasta tomar el mutex
+    pub async fn lock(&self) -> AsyncMutexGuard<'_, T> {
+        std::future::poll_fn(|cx| {
+            let mut state = self.state();
+            if state.locked {
+                if !state.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                    state.waiters.push_back(cx.waker().clone());
+                }
+                Poll::Pending
+            } else {
+                state.locked = true;
+                Poll::Ready(AsyncMutexGuard { mutex: self })
+            }
+        })
+        .await
+    }
+
+    /// toma el mutex solo si esta libre
+    pub fn try_lock(&self) -> Option<AsyncMutexGuard<'_, T>> {
+        let mut state = self.state();
+        if state.locked {
+            return None;
+        }
+        state.locked = true;
+        Some(AsyncMutexGuard { mutex: self })
+    }
+}
+
+pub struct AsyncMutexGuard<'a, T> {
+    mutex: &'a AsyncMutex<T>,
+}
+
+impl<T> Deref for AsyncMutexGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for AsyncMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for AsyncMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        let mut state = self.mutex.state();
+        state.locked = false;
+        // el despertado vuelve a intentar; si otro se le adelanta se anota de nuevo
+        if let Some(waker) = state.waiters.pop_front() {
+            waker.wake();
+        }
+    }
+}
+
+/// Canal sin limite entre tareas async (varios emisores, un receptor)
+pub fn async_channel<T>() -> (AsyncSender<T>, AsyncReceiver<T>) {
+    let shared = Arc::new(Mutex::new(ChannelState {
+        queue: VecDeque::new(),
+        senders: 1,
+        receiver: None,
+    }));
+    (AsyncSender { shared: shared.clone() }, AsyncReceiver { shared })
+}
+
+struct ChannelState<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receiver: Option<Waker>,
+}
+
+type SharedChannel<T> = Arc<Mutex<ChannelState<T>>>;
+
+fn channel_state<T>(shared: &SharedChannel<T>) -> MutexGuard<'_, ChannelState<T>> {
+    shared.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub struct AsyncSender<T> {
+    shared: SharedChannel<T>,
+}
+
+impl<T> AsyncSender<T> {
+    /// encola `value` y despierta al receptor; nunca espera
+    pub fn send(&self, value: T) {
+        let mut state = channel_state(&self.shared);
+        state.queue.push_back(value);
+        if let Some(waker) = state.receiver.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Clone for AsyncSender<T> {
+    fn clone(&self) -> Self {
+        channel_state(&self.shared).senders += 1;
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for AsyncSender<T> {
+    fn drop(&mut self) {
+        let mut state = channel_state(&self.shared);
+        state.senders -= 1;
+        // sin emisores el receptor tiene que enterarse de que se cerro
+        if state.senders == 0 {
+            if let Some(waker) = state.receiver.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+pub struct AsyncReceiver<T> {
+    shared: SharedChannel<T>,
+}
+
+impl<T> AsyncReceiver<T> {
+    /// espera el siguiente valor; None cuando ya no quedan emisores
+    pub async fn recv(&mut self) -> Option<T> {
+        std::future::poll_fn(|cx| {
+            let mut state = channel_state(&self.shared);
+            match state.queue.pop_front() {
+                Some(value) => Poll::Ready(Some(value)),
+                None if state.senders == 0 => Poll::Ready(None),
+                None => {
+                    state.receiver = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// saca un valor si hay alguno listo
+    pub fn try_recv(&mut self) -> Option<T> {
+        channel_state(&self.shared).queue.pop_front()
+    }
+}
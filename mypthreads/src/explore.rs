@@ -0,0 +1,221 @@
+//! explorador de intercalaciones para probar programas mypthreads
+//!
+//! Cada corrida arma un runtime nuevo con `setup`, y en cada despacho el
+//! explorador (no el scheduler) elige que hilo listo corre. Al terminar la
+//! corrida se llama al verificador que devolvio `setup`; si falla, o si quedan
+//! hilos bloqueados sin ninguno listo, se reporta el cronograma que lo provoco.
+//!
+//! Estrategias:
+//! - `Exhaustive`: recorre en profundidad todas las intercalaciones, hasta `max_runs`.
+//! - `Pct`: prioridades aleatorias con `depth - 1` puntos de cambio por corrida
+//!   (Probabilistic Concurrency Testing). Como en el articulo, los puntos de
+//!   cambio se sortean entre los pasos que midieron las corridas anteriores.
+
+use crate::replay::SchedRecording;
+use crate::runtime::ThreadRuntimeV2;
+use crate::thread::ThreadId;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Como elegir el siguiente hilo en cada corrida
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExploreStrategy {
+    Exhaustive { max_runs: usize },
+    Pct { runs: usize, depth: usize, seed: u64 },
+}
+
+/// Cronograma que rompio una verificacion
+#[derive(Debug, Clone)]
+pub struct ExploreFailure {
+    /// numero de corrida (desde 1)
+    pub run: usize,
+    pub message: String,
+    /// hilos despachados, en orden
+    pub schedule: Vec<ThreadId>,
+    pub names: HashMap<ThreadId, String>,
+    /// decisiones completas, se pueden reproducir con `ThreadRuntimeV2::start_replay`
+    pub recording: SchedRecording,
+}
+
+impl fmt::Display for ExploreFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "fallo en la corrida {}: {}", self.run, self.message)?;
+        write!(f, "cronograma:")?;
+        for (step, tid) in self.schedule.iter().enumerate() {
+            let name = self.names.get(tid).map_or("?", String::as_str);
+            write!(f, "\n  {:>3}. hilo {} ({})", step + 1, tid, name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Resultado de una exploracion
+#[derive(Debug, Clone)]
+pub struct ExploreReport {
+    pub runs: usize,
+    /// la busqueda exhaustiva cubrio todas las intercalaciones
+    pub complete: bool,
+    pub failure: Option<ExploreFailure>,
+}
+
+pub struct Explorer {
+    strategy: ExploreStrategy,
+    max_steps: usize,
+}
+
+impl Explorer {
+    pub fn exhaustive(max_runs: usize) -> Self {
+        Self {
+            strategy: ExploreStrategy::Exhaustive { max_runs },
+            max_steps: 1000,
+        }
+    }
+
+    pub fn pct(runs: usize, depth: usize, seed: u64) -> Self {
+        Self {
+            strategy: ExploreStrategy::Pct { runs, depth, seed },
+            max_steps: 1000,
+        }
+    }
+
+    /// despachos maximos por corrida (una corrida que llega al limite se verifica igual)
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// `setup` crea los hilos en el runtime y devuelve el verificador de la corrida
+    pub fn run<S, C>(&self, mut setup: S) -> ExploreReport
+    where
+        S: FnMut(&mut ThreadRuntimeV2) -> C,
+        C: FnOnce(&ThreadRuntimeV2) -> Result<(), String>,
+    {
+        match self.strategy {
+            ExploreStrategy::Exhaustive { max_runs } => self.run_exhaustive(max_runs, &mut setup),
+            ExploreStrategy::Pct { runs, depth, seed } => self.run_pct(runs, depth, seed, &mut setup),
+        }
+    }
+
+    fn run_exhaustive<S, C>(&self, max_runs: usize, setup: &mut S) -> ExploreReport
+    where
+        S: FnMut(&mut ThreadRuntimeV2) -> C,
+        C: FnOnce(&ThreadRuntimeV2) -> Result<(), String>,
+    {
+        // por profundidad: hilos listos en ese punto y cual se eligio
+        let mut stack: Vec<(Vec<ThreadId>, usize)> = Vec::new();
+        let mut runs = 0;
+
+        while runs < max_runs {
+            runs += 1;
+            let failure = self.run_one(runs, setup, |depth, ready| {
+                if depth == stack.len() {
+                    stack.push((ready.to_vec(), 0));
+                }
+                let (choices, idx) = &stack[depth];
+                // si el programa no es determinista las opciones pueden cambiar
+                choices.get(*idx).copied().filter(|t| ready.contains(t)).unwrap_or(ready[0])
+            });
+            if failure.is_some() {
+                return ExploreReport { runs, complete: false, failure };
+            }
+
+            // siguiente intercalacion: avanzar la ultima eleccion con alternativas
+            while let Some((choices, idx)) = stack.last_mut() {
+                if *idx + 1 < choices.len() {
+                    *idx += 1;
+                    break;
+                }
+                stack.pop();
+            }
+            if stack.is_empty() {
+                return ExploreReport { runs, complete: true, failure: None };
+            }
+        }
+        ExploreReport { runs, complete: false, failure: None }
+    }
+
+    fn run_pct<S, C>(&self, runs: usize, depth: usize, seed: u64, setup: &mut S) -> ExploreReport
+    where
+        S: FnMut(&mut ThreadRuntimeV2) -> C,
+        C: FnOnce(&ThreadRuntimeV2) -> Result<(), String>,
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let depth = depth.max(1);
+
+        // pasos de la corrida mas larga hasta ahora; la primera solo mide
+        let mut measured_steps: Option<usize> = None;
+
+        for run in 1..=runs {
+            let change_points: Vec<usize> = match measured_steps {
+                Some(steps) => (0..depth - 1).map(|_| rng.random_range(0..steps.max(1))).collect(),
+                None => Vec::new(),
+            };
+            // prioridades iniciales por encima de las de los puntos de cambio
+            let mut priorities: HashMap<ThreadId, u64> = HashMap::new();
+            let mut last: Option<ThreadId> = None;
+            let mut steps = 0;
+
+            let failure = self.run_one(run, setup, |step, ready| {
+                steps = step + 1;
+                if let Some(prev) = last {
+                    if let Some(i) = change_points.iter().position(|&c| c + 1 == step) {
+                        priorities.insert(prev, i as u64);
+                    }
+                }
+                for &tid in ready {
+                    priorities
+                        .entry(tid)
+                        .or_insert_with(|| depth as u64 + rng.random_range(0..u32::MAX as u64));
+                }
+                let tid = *ready.iter().max_by_key(|t| priorities[t]).unwrap();
+                last = Some(tid);
+                tid
+            });
+            measured_steps = Some(measured_steps.unwrap_or(0).max(steps));
+            if failure.is_some() {
+                return ExploreReport { runs: run, complete: false, failure };
+            }
+        }
+        ExploreReport { runs, complete: false, failure: None }
+    }
+
+    /// una corrida completa; `choose(paso, listos)` decide cada despacho
+    fn run_one<S, C>(
+        &self,
+        run: usize,
+        setup: &mut S,
+        mut choose: impl FnMut(usize, &[ThreadId]) -> ThreadId,
+    ) -> Option<ExploreFailure>
+    where
+        S: FnMut(&mut ThreadRuntimeV2) -> C,
+        C: FnOnce(&ThreadRuntimeV2) -> Result<(), String>,
+    {
+        let mut rt = ThreadRuntimeV2::with_seed(0);
+        let check = setup(&mut rt);
+        rt.start_recording();
+
+        let mut schedule = Vec::new();
+        while schedule.len() < self.max_steps && !rt.ready.is_empty() {
+            let ready: Vec<ThreadId> = rt.ready.iter().copied().collect();
+            let tid = choose(schedule.len(), &ready);
+            rt.run_chosen(tid);
+            schedule.push(tid);
+        }
+
+        let result = if rt.ready.is_empty() && !rt.blocked.is_empty() {
+            Err(format!("deadlock: hilos bloqueados {:?}", rt.blocked))
+        } else {
+            check(&rt)
+        };
+        let message = result.err()?;
+        Some(ExploreFailure {
+            run,
+            message,
+            schedule,
+            names: rt.threads.iter().map(|(&tid, t)| (tid, t.name.clone())).collect(),
+            recording: rt.replay.stop_recording().unwrap_or_default(),
+        })
+    }
+}
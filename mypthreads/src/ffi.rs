@@ -0,0 +1,552 @@
+//! capa C al estilo pthreads sobre el runtime global
+//!
+//! El header es `include/mypthreads.h`, generado con
+//! `cbindgen --config cbindgen.toml --crate mypthreads --output include/mypthreads.h`.
+//!
+//! Como en pthreads, las funciones devuelven 0 o un codigo errno. Los hilos C
+//! corren su funcion completa en un solo paso y se suspenden (sin terminar el
+//! paso) en yield, join, mutex_lock y cond_wait. Llamadas desde el hilo
+//! principal, join y mutex_lock corren el scheduler hasta poder continuar.
+#![allow(non_camel_case_types)]
+// los punteros vienen de C: se revisa NULL y el resto es contrato del llamador, como en pthreads
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use crate::api_context::{ctx_mutex_acquired, ctx_suspend, ctx_wake, try_current_tid};
+use crate::channels::{MutexKind, SimpleMutex, UNLOCKED};
+use crate::mypthreads_api::{
+    create_named, hand_off, my_thread_chsched, my_thread_detach, runtime_init, runtime_set_seed, with_runtime,
+    SchedulerParams,
+};
+use crate::signals::ThreadSignal;
+use crate::thread::{ThreadId, ThreadState};
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::{c_int, c_void};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Identificador de hilo (el ThreadId del runtime). El hilo principal es 0.
+pub type my_pthread_t = u32;
+
+pub const MY_SCHED_RR: c_int = 0;
+pub const MY_SCHED_LOTTERY: c_int = 1;
+pub const MY_SCHED_REALTIME: c_int = 2;
+
+pub const MY_PTHREAD_MUTEX_NORMAL: c_int = 0;
+pub const MY_PTHREAD_MUTEX_ERRORCHECK: c_int = 1;
+pub const MY_PTHREAD_MUTEX_RECURSIVE: c_int = 2;
+
+// codigos errno de Linux, los mismos que devuelve pthreads
+pub const MY_EPERM: c_int = 1;
+pub const MY_ESRCH: c_int = 3;
+pub const MY_EBUSY: c_int = 16;
+pub const MY_EINVAL: c_int = 22;
+pub const MY_EDEADLK: c_int = 35;
+
+/// Parametros de planificacion; NULL en `my_pthread_create` equivale a round robin
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct my_pthread_attr_t {
+    /// MY_SCHED_RR, MY_SCHED_LOTTERY o MY_SCHED_REALTIME
+    pub policy: c_int,
+    /// solo para MY_SCHED_LOTTERY
+    pub tickets: u32,
+    /// solo para MY_SCHED_REALTIME, en ms del reloj del runtime
+    pub deadline: u64,
+}
+
+/// Atributos de mutex; NULL en `my_pthread_mutex_init` equivale a errorcheck
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct my_pthread_mutexattr_t {
+    /// MY_PTHREAD_MUTEX_NORMAL, MY_PTHREAD_MUTEX_ERRORCHECK o MY_PTHREAD_MUTEX_RECURSIVE
+    pub kind: c_int,
+}
+
+/// Mutex errorcheck; `MY_PTHREAD_MUTEX_INITIALIZER` lo deja listo sin `my_pthread_mutex_init`
+#[repr(C)]
+pub struct my_pthread_mutex_t {
+    pub inner: *mut c_void,
+}
+
+/// Variable de condicion; `MY_PTHREAD_COND_INITIALIZER` la deja lista
+#[repr(C)]
+pub struct my_pthread_cond_t {
+    pub inner: *mut c_void,
+}
+
+/// tid con el que el hilo principal toma mutexes
+const MAIN_TID: ThreadId = 0;
+
+/// valor de retorno de cada hilo creado desde C, hasta que alguien haga join
+struct CThread {
+    retval: Option<usize>,
+    detached: bool,
+}
+
+static C_THREADS: Mutex<BTreeMap<ThreadId, CThread>> = Mutex::new(BTreeMap::new());
+
+fn c_threads() -> std::sync::MutexGuard<'static, BTreeMap<ThreadId, CThread>> {
+    C_THREADS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+struct CondWaiter {
+    tid: ThreadId,
+    signaled: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct CCond {
+    waiters: Mutex<VecDeque<CondWaiter>>,
+}
+
+fn sched_params(attr: *const my_pthread_attr_t) -> Result<SchedulerParams, c_int> {
+    let Some(attr) = (unsafe { attr.as_ref() }) else {
+        return Ok(SchedulerParams::RoundRobin);
+    };
+    match attr.policy {
+        MY_SCHED_RR => Ok(SchedulerParams::RoundRobin),
+        MY_SCHED_LOTTERY => Ok(SchedulerParams::Lottery { tickets: attr.tickets }),
+        MY_SCHED_REALTIME => Ok(SchedulerParams::RealTime { deadline: attr.deadline }),
+        _ => Err(MY_EINVAL),
+    }
+}
+
+/// guarda el valor de retorno; un hilo detached no lo necesita
+fn finish(tid: ThreadId, retval: *mut c_void) {
+    let mut threads = c_threads();
+    if let Some(t) = threads.get_mut(&tid) {
+        if t.detached {
+            threads.remove(&tid);
+        } else {
+            t.retval = Some(retval as usize);
+        }
+    }
+}
+
+/// un ciclo del scheduler desde el hilo principal; false si no habia nada que correr
+fn main_step() -> bool {
+    with_runtime(|rt| {
+        if rt.ready.is_empty() {
+            return false;
+        }
+        rt.run_once();
+        true
+    })
+    .unwrap_or(false)
+}
+
+/// despierta a `tid` desde un hilo (al terminar su paso) o desde el principal
+fn wake(tid: ThreadId) {
+    if try_current_tid().is_some() {
+        ctx_wake(tid);
+    } else {
+        let _ = with_runtime(|rt| rt.unblock_thread(tid));
+    }
+}
+
+fn mutex_ref(mutex: *mut my_pthread_mutex_t) -> Result<&'static SimpleMutex, c_int> {
+    let mutex = unsafe { mutex.as_mut() }.ok_or(MY_EINVAL)?;
+    if mutex.inner.is_null() {
+        mutex.inner = Box::into_raw(Box::new(SimpleMutex::new())) as *mut c_void;
+    }
+    Ok(unsafe { &*(mutex.inner as *const SimpleMutex) })
+}
+
+fn cond_ref(cond: *mut my_pthread_cond_t) -> Result<&'static CCond, c_int> {
+    let cond = unsafe { cond.as_mut() }.ok_or(MY_EINVAL)?;
+    if cond.inner.is_null() {
+        cond.inner = Box::into_raw(Box::new(CCond::default())) as *mut c_void;
+    }
+    Ok(unsafe { &*(cond.inner as *const CCond) })
+}
+
+fn lock(mutex: &'static SimpleMutex) -> c_int {
+    let me = try_current_tid().unwrap_or(MAIN_TID);
+    if mutex.try_lock(me) {
+        ctx_mutex_acquired();
+        return 0;
+    }
+    // uno normal deja al hilo esperando para siempre, como en pthreads; el principal no puede
+    if mutex.owner.load(Ordering::Relaxed) == me && (mutex.kind != MutexKind::Normal || try_current_tid().is_none()) {
+        return MY_EDEADLK;
+    }
+    if try_current_tid().is_some() {
+        // el runtime lo encola y nos despierta siendo dueños
+        ctx_suspend(ThreadSignal::MutexLock(mutex as *const SimpleMutex as usize));
+        return 0;
+    }
+    loop {
+        if !main_step() {
+            return MY_EDEADLK;
+        }
+        if mutex.try_lock(me) {
+            return 0;
+        }
+    }
+}
+
+fn unlock(mutex: &'static SimpleMutex) -> c_int {
+    let me = try_current_tid().unwrap_or(MAIN_TID);
+    if mutex.owner.load(Ordering::Relaxed) != me {
+        return MY_EPERM;
+    }
+    if try_current_tid().is_some() {
+        ctx_suspend(ThreadSignal::MutexUnlock(mutex as *const SimpleMutex as usize));
+    } else if let Ok(Some(next)) = mutex.unlock(me) {
+        hand_off(mutex, me, next);
+    }
+    0
+}
+
+// --- RUNTIME ---
+
+/// Inicializa el runtime global; llamar una vez antes de crear hilos
+#[no_mangle]
+pub extern "C" fn my_pthread_runtime_init() {
+    runtime_init();
+}
+
+/// Siembra el scheduler para que los sorteos se repitan
+#[no_mangle]
+pub extern "C" fn my_pthread_runtime_seed(seed: u64) -> c_int {
+    runtime_set_seed(seed).map_or_else(|e| e.errno(), |()| 0)
+}
+
+/// Corre el scheduler hasta que no haya hilos listos o pasen `max_cycles`.
+/// Retorna cuantos hilos siguen vivos.
+#[no_mangle]
+pub extern "C" fn my_pthread_runtime_run(max_cycles: usize) -> usize {
+    for _ in 0..max_cycles {
+        if !main_step() {
+            break;
+        }
+    }
+    with_runtime(|rt| {
+        rt.threads
+            .values()
+            .filter(|t| t.state != ThreadState::Terminated)
+            .count()
+    })
+    .unwrap_or(0)
+}
+
+// --- HILOS ---
+
+#[no_mangle]
+pub extern "C" fn my_pthread_create(
+    thread: *mut my_pthread_t,
+    attr: *const my_pthread_attr_t,
+    start: Option<extern "C" fn(arg: *mut c_void) -> *mut c_void>,
+    arg: *mut c_void,
+) -> c_int {
+    let (Some(out), Some(start)) = (unsafe { thread.as_mut() }, start) else {
+        return MY_EINVAL;
+    };
+    let params = match sched_params(attr) {
+        Ok(params) => params,
+        Err(e) => return e,
+    };
+
+    let arg = arg as usize;
+    let created = create_named(
+        |tid| format!("pthread-{}", tid),
+        params,
+        Box::new(move |tid, _| {
+            let retval = start(arg as *mut c_void);
+            finish(tid, retval);
+            ThreadSignal::Exit
+        }),
+    );
+    let tid = match created {
+        Ok(tid) => tid,
+        Err(e) => return e.errno(),
+    };
+    c_threads().insert(tid, CThread { retval: None, detached: false });
+    *out = tid;
+    0
+}
+
+/// Termina el hilo actual con `retval`. Desde el hilo principal corre los
+/// hilos restantes y termina el proceso.
+#[no_mangle]
+pub extern "C" fn my_pthread_exit(retval: *mut c_void) -> ! {
+    match try_current_tid() {
+        Some(tid) => {
+            finish(tid, retval);
+            ctx_suspend(ThreadSignal::Exit);
+            unreachable!("un hilo terminado no vuelve a correr");
+        }
+        None => {
+            while main_step() {}
+            std::process::exit(0);
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_self() -> my_pthread_t {
+    try_current_tid().unwrap_or(MAIN_TID)
+}
+
+/// Cede el procesador; desde el hilo principal corre un ciclo del scheduler
+#[no_mangle]
+pub extern "C" fn my_pthread_yield() -> c_int {
+    if try_current_tid().is_some() {
+        ctx_suspend(ThreadSignal::Yield);
+    } else {
+        main_step();
+    }
+    0
+}
+
+/// Espera a que `thread` termine y guarda su valor de retorno en `retval` (si no es NULL)
+#[no_mangle]
+pub extern "C" fn my_pthread_join(thread: my_pthread_t, retval: *mut *mut c_void) -> c_int {
+    let me = try_current_tid();
+    if me == Some(thread) {
+        return MY_EDEADLK;
+    }
+    loop {
+        {
+            let mut threads = c_threads();
+            match threads.get(&thread) {
+                None => return MY_ESRCH,
+                Some(t) if t.detached => return MY_EINVAL,
+                Some(CThread { retval: Some(value), .. }) => {
+                    let value = *value;
+                    threads.remove(&thread);
+                    drop(threads);
+                    // ya nadie lo va a esperar: el runtime lo libera al terminar
+                    let _ = my_thread_detach(thread);
+                    if let Some(out) = unsafe { retval.as_mut() } {
+                        *out = value as *mut c_void;
+                    }
+                    return 0;
+                }
+                Some(_) => {}
+            }
+        }
+        if me.is_some() {
+            ctx_suspend(ThreadSignal::Join(thread));
+        } else if !main_step() {
+            return MY_EDEADLK;
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_detach(thread: my_pthread_t) -> c_int {
+    let mut threads = c_threads();
+    let Some(t) = threads.get_mut(&thread) else {
+        return MY_ESRCH;
+    };
+    if t.detached {
+        return MY_EINVAL;
+    }
+    if t.retval.is_some() {
+        threads.remove(&thread);
+    } else {
+        t.detached = true;
+    }
+    drop(threads);
+    my_thread_detach(thread).map_or_else(|e| e.errno(), |()| 0)
+}
+
+/// Cambia el planificador de un hilo
+#[no_mangle]
+pub extern "C" fn my_pthread_chsched(thread: my_pthread_t, attr: *const my_pthread_attr_t) -> c_int {
+    if attr.is_null() {
+        return MY_EINVAL;
+    }
+    let params = match sched_params(attr) {
+        Ok(params) => params,
+        Err(e) => return e,
+    };
+    my_thread_chsched(thread, params).map_or_else(|e| e.errno(), |()| 0)
+}
+
+// --- MUTEX ---
+
+fn mutex_kind(kind: c_int) -> Option<MutexKind> {
+    match kind {
+        MY_PTHREAD_MUTEX_NORMAL => Some(MutexKind::Normal),
+        MY_PTHREAD_MUTEX_ERRORCHECK => Some(MutexKind::ErrorCheck),
+        MY_PTHREAD_MUTEX_RECURSIVE => Some(MutexKind::Recursive),
+        _ => None,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_mutexattr_init(attr: *mut my_pthread_mutexattr_t) -> c_int {
+    let Some(attr) = (unsafe { attr.as_mut() }) else {
+        return MY_EINVAL;
+    };
+    attr.kind = MY_PTHREAD_MUTEX_ERRORCHECK;
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_mutexattr_settype(attr: *mut my_pthread_mutexattr_t, kind: c_int) -> c_int {
+    match (unsafe { attr.as_mut() }, mutex_kind(kind)) {
+        (Some(attr), Some(_)) => {
+            attr.kind = kind;
+            0
+        }
+        _ => MY_EINVAL,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_mutexattr_gettype(attr: *const my_pthread_mutexattr_t, kind: *mut c_int) -> c_int {
+    match (unsafe { attr.as_ref() }, unsafe { kind.as_mut() }) {
+        (Some(attr), Some(out)) => {
+            *out = attr.kind;
+            0
+        }
+        _ => MY_EINVAL,
+    }
+}
+
+/// `attr` NULL crea un mutex errorcheck
+#[no_mangle]
+pub extern "C" fn my_pthread_mutex_init(mutex: *mut my_pthread_mutex_t, attr: *const my_pthread_mutexattr_t) -> c_int {
+    let kind = match unsafe { attr.as_ref() } {
+        None => MutexKind::default(),
+        Some(attr) => match mutex_kind(attr.kind) {
+            Some(kind) => kind,
+            None => return MY_EINVAL,
+        },
+    };
+    let Some(mutex) = (unsafe { mutex.as_mut() }) else {
+        return MY_EINVAL;
+    };
+    mutex.inner = Box::into_raw(Box::new(SimpleMutex::with_kind(kind))) as *mut c_void;
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_mutex_lock(mutex: *mut my_pthread_mutex_t) -> c_int {
+    mutex_ref(mutex).map_or_else(|e| e, lock)
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_mutex_trylock(mutex: *mut my_pthread_mutex_t) -> c_int {
+    let me = try_current_tid().unwrap_or(MAIN_TID);
+    match mutex_ref(mutex) {
+        Ok(m) if m.try_lock(me) => {
+            ctx_mutex_acquired();
+            0
+        }
+        Ok(_) => MY_EBUSY,
+        Err(e) => e,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_mutex_unlock(mutex: *mut my_pthread_mutex_t) -> c_int {
+    mutex_ref(mutex).map_or_else(|e| e, unlock)
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_mutex_destroy(mutex: *mut my_pthread_mutex_t) -> c_int {
+    let Some(mutex) = (unsafe { mutex.as_mut() }) else {
+        return MY_EINVAL;
+    };
+    if mutex.inner.is_null() {
+        return 0;
+    }
+    let inner = unsafe { &*(mutex.inner as *const SimpleMutex) };
+    if inner.owner.load(Ordering::Relaxed) != UNLOCKED {
+        return MY_EBUSY;
+    }
+    drop(unsafe { Box::from_raw(mutex.inner as *mut SimpleMutex) });
+    mutex.inner = std::ptr::null_mut();
+    0
+}
+
+// --- VARIABLES DE CONDICION ---
+
+/// `attr` se ignora
+#[no_mangle]
+pub extern "C" fn my_pthread_cond_init(cond: *mut my_pthread_cond_t, attr: *const c_void) -> c_int {
+    let _ = attr;
+    let Some(cond) = (unsafe { cond.as_mut() }) else {
+        return MY_EINVAL;
+    };
+    cond.inner = Box::into_raw(Box::new(CCond::default())) as *mut c_void;
+    0
+}
+
+/// Libera `mutex`, espera un signal/broadcast y lo vuelve a tomar.
+/// Solo desde un hilo mypthreads (EPERM desde el principal o sin ser dueño del mutex).
+#[no_mangle]
+pub extern "C" fn my_pthread_cond_wait(cond: *mut my_pthread_cond_t, mutex: *mut my_pthread_mutex_t) -> c_int {
+    let Some(me) = try_current_tid() else {
+        return MY_EPERM;
+    };
+    let (cond, mutex) = match (cond_ref(cond), mutex_ref(mutex)) {
+        (Ok(c), Ok(m)) => (c, m),
+        _ => return MY_EINVAL,
+    };
+    if mutex.owner.load(Ordering::Relaxed) != me {
+        return MY_EPERM;
+    }
+
+    let signaled = Arc::new(AtomicBool::new(false));
+    cond.waiters
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push_back(CondWaiter { tid: me, signaled: signaled.clone() });
+
+    unlock(mutex);
+    // el signal puede llegar mientras liberabamos el mutex: entonces no hay que bloquearse
+    while !signaled.load(Ordering::Acquire) {
+        ctx_suspend(ThreadSignal::Block);
+    }
+    lock(mutex)
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_cond_signal(cond: *mut my_pthread_cond_t) -> c_int {
+    let cond = match cond_ref(cond) {
+        Ok(c) => c,
+        Err(e) => return e,
+    };
+    let waiter = cond.waiters.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+    if let Some(w) = waiter {
+        w.signaled.store(true, Ordering::Release);
+        wake(w.tid);
+    }
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_cond_broadcast(cond: *mut my_pthread_cond_t) -> c_int {
+    let cond = match cond_ref(cond) {
+        Ok(c) => c,
+        Err(e) => return e,
+    };
+    let waiters: Vec<CondWaiter> = cond.waiters.lock().unwrap_or_else(|e| e.into_inner()).drain(..).collect();
+    for w in waiters {
+        w.signaled.store(true, Ordering::Release);
+        wake(w.tid);
+    }
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn my_pthread_cond_destroy(cond: *mut my_pthread_cond_t) -> c_int {
+    let Some(cond) = (unsafe { cond.as_mut() }) else {
+        return MY_EINVAL;
+    };
+    if cond.inner.is_null() {
+        return 0;
+    }
+    let inner = unsafe { &*(cond.inner as *const CCond) };
+    if !inner.waiters.lock().unwrap_or_else(|e| e.into_inner()).is_empty() {
+        return MY_EBUSY;
+    }
+    drop(unsafe { Box::from_raw(cond.inner as *mut CCond) });
+    cond.inner = std::ptr::null_mut();
+    0
+}
@@ -0,0 +1,78 @@
+//! grupos de hilos para operar sobre una clase completa a la vez
+//!
+//! Cada hilo pertenece a lo sumo a un grupo (`MyThread::group`). El registro
+//! guarda los nombres; los miembros vivos se obtienen recorriendo los hilos,
+//! asi nunca queda desincronizado con el runtime. De los miembros que el
+//! runtime ya libero (`reap`) solo quedan sus estadisticas sumadas, para que
+//! un grupo que recibe hilos sin parar no crezca con cada uno.
+
+use crate::stats::{StatsSummary, ThreadStats};
+use std::collections::BTreeMap;
+
+pub type GroupId = u32;
+
+/// Un grupo de hilos con nombre
+#[derive(Debug, Clone)]
+pub struct ThreadGroup {
+    pub id: GroupId,
+    pub name: String,
+    /// estadisticas sumadas de los miembros ya liberados (`threads` los cuenta)
+    pub exited: StatsSummary,
+}
+
+/// Registro de grupos del runtime
+#[derive(Debug)]
+pub struct GroupRegistry {
+    next_id: GroupId,
+    groups: BTreeMap<GroupId, ThreadGroup>,
+}
+
+impl Default for GroupRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GroupRegistry {
+    pub fn new() -> Self {
+        Self {
+            next_id: 1,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    /// crea un grupo vacio
+    pub fn create(&mut self, name: impl Into<String>) -> GroupId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.groups.insert(
+            id,
+            ThreadGroup {
+                id,
+                name: name.into(),
+                exited: StatsSummary::default(),
+            },
+        );
+        id
+    }
+
+    pub fn get(&self, id: GroupId) -> Option<&ThreadGroup> {
+        self.groups.get(&id)
+    }
+
+    pub fn contains(&self, id: GroupId) -> bool {
+        self.groups.contains_key(&id)
+    }
+
+    /// guarda lo que deja un miembro que el runtime libera
+    pub(crate) fn record_exit(&mut self, id: GroupId, stats: &ThreadStats) {
+        if let Some(group) = self.groups.get_mut(&id) {
+            group.exited.add(stats);
+        }
+    }
+
+    /// todos los grupos en orden de creacion
+    pub fn iter(&self) -> impl Iterator<Item = &ThreadGroup> {
+        self.groups.values()
+    }
+}
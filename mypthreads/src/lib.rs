@@ -9,13 +9,48 @@ pub mod context_wrapper;
 pub mod mypthreads_api;
 pub mod sched;
 pub mod sync;
+pub mod lottery;
+pub mod trace;
+pub mod stats;
+pub mod snapshot;
+pub mod observer;
+pub mod log;
+pub mod tls;
+pub mod replay;
+pub mod explore;
+pub mod ffi;
+pub mod group;
+pub mod error;
+pub mod reactor;
+pub mod blocking;
+pub mod remote;
+pub mod executor;
+pub mod coroutine;
+pub mod watchdog;
+pub mod model;
 
 // Tipos públicos de la biblioteca
 pub use runtime::ThreadRuntimeV2;
 pub use thread::{MyThread, ContextThreadEntry, ThreadId, ThreadState, SchedulerType};
-pub use channels::{ThreadChannels, JoinHandle, SimpleMutex, SharedData};
+pub use channels::{ThreadChannels, JoinHandle, MutexKind, SimpleMutex, SharedData};
 pub use api_context::*; 
 pub use signals::ThreadSignal; 
 pub use context_wrapper::ThreadContext;
+pub use coroutine::{Coroutine, CoroutineState, Yielder};
 pub use thread_data::{TransferMessage, ThreadResponse}; 
-pub use sync::{Shared, shared};
\ No newline at end of file
+pub use sync::{MyRwLock, RwPreference, Shared, SharedRw, shared, shared_rw};
+pub use lottery::{CurrencyId, TicketCurrency, LotteryLedger};
+pub use group::{GroupId, GroupRegistry, ThreadGroup};
+pub use error::{MyResult, MyThreadError};
+pub use reactor::{Interest, Reactor};
+pub use remote::{RemoteMessage, RuntimeHandle};
+pub use executor::{async_channel, AsyncMutex, AsyncMutexGuard, AsyncReceiver, AsyncSender};
+pub use trace::{SchedTracer, TraceEvent, TraceEventKind};
+pub use stats::{BlockReason, StackUsage, ThreadStats, StatsSummary};
+pub use snapshot::{RuntimeSnapshot, ThreadSnapshot, WaitReason};
+pub use observer::{ObserverId, RuntimeEvent, RuntimeObserver};
+pub use tls::{KeyDestructor, MyKey};
+pub use replay::{Divergence, SchedDecision, SchedRecording, SchedReplay};
+pub use explore::{ExploreFailure, ExploreReport, ExploreStrategy, Explorer};
+pub use watchdog::{Watchdog, WatchdogFlag};
+pub use model::{GanttSlice, JobReport, ModelReport, TaskSet, TaskSpec, TaskSummary};
\ No newline at end of file
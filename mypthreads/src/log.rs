@@ -0,0 +1,189 @@
+//! fachada de logging con niveles y targets
+//!
+//! Apagado por defecto. Se habilita con `set_level` (todos los targets) o
+//! `set_target_level` (uno solo, p. ej. "sched") y la salida va a stderr, a un
+//! archivo (`log_to_file`) o a un buffer circular en memoria (`log_to_ring`).
+
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+
+/// Nivel de un mensaje, de mas a menos grave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Un mensaje guardado en el buffer circular
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub level: Level,
+    pub target: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} {}] {}", self.level, self.target, self.message)
+    }
+}
+
+enum Sink {
+    Stderr,
+    File(LineWriter<File>),
+    Ring {
+        records: VecDeque<LogRecord>,
+        capacity: usize,
+    },
+}
+
+struct LogConfig {
+    level: Option<Level>,
+    targets: HashMap<&'static str, Option<Level>>,
+    sink: Sink,
+}
+
+impl LogConfig {
+    fn level_for(&self, target: &str) -> Option<Level> {
+        self.targets.get(target).copied().unwrap_or(self.level)
+    }
+
+    /// nivel mas detallado habilitado en cualquier target
+    fn max_level(&self) -> u8 {
+        self.targets
+            .values()
+            .chain(std::iter::once(&self.level))
+            .map(|l| l.map_or(0, |l| l as u8))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+static CONFIG: Lazy<Mutex<LogConfig>> = Lazy::new(|| {
+    Mutex::new(LogConfig {
+        level: None,
+        targets: HashMap::new(),
+        sink: Sink::Stderr,
+    })
+});
+
+// copia de `max_level` para descartar mensajes sin tomar el lock
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+fn config() -> std::sync::MutexGuard<'static, LogConfig> {
+    CONFIG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Nivel para todos los targets sin nivel propio (None = apagado)
+pub fn set_level(level: Option<Level>) {
+    let mut cfg = config();
+    cfg.level = level;
+    MAX_LEVEL.store(cfg.max_level(), Ordering::Relaxed);
+}
+
+/// Nivel de un target especifico, tiene prioridad sobre `set_level`
+pub fn set_target_level(target: &'static str, level: Option<Level>) {
+    let mut cfg = config();
+    cfg.targets.insert(target, level);
+    MAX_LEVEL.store(cfg.max_level(), Ordering::Relaxed);
+}
+
+/// Vuelve a la configuracion inicial: todo apagado y salida a stderr
+pub fn reset() {
+    let mut cfg = config();
+    cfg.level = None;
+    cfg.targets.clear();
+    cfg.sink = Sink::Stderr;
+    MAX_LEVEL.store(0, Ordering::Relaxed);
+}
+
+pub fn log_to_stderr() {
+    config().sink = Sink::Stderr;
+}
+
+/// Escribe los mensajes en `path` (se trunca si existe)
+pub fn log_to_file(path: impl AsRef<Path>) -> io::Result<()> {
+    let file = File::create(path)?;
+    config().sink = Sink::File(LineWriter::new(file));
+    Ok(())
+}
+
+/// Guarda los ultimos `capacity` mensajes en memoria
+pub fn log_to_ring(capacity: usize) {
+    config().sink = Sink::Ring {
+        records: VecDeque::with_capacity(capacity),
+        capacity,
+    };
+}
+
+/// Copia del buffer circular (vacio si la salida no es el buffer)
+pub fn ring_records() -> Vec<LogRecord> {
+    match &config().sink {
+        Sink::Ring { records, .. } => records.iter().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn enabled(level: Level, target: &str) -> bool {
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+    config().level_for(target).is_some_and(|max| level <= max)
+}
+
+/// Registra un mensaje; normalmente se usa `mp_log!`
+pub fn log(level: Level, target: &'static str, args: fmt::Arguments<'_>) {
+    if !enabled(level, target) {
+        return;
+    }
+    let record = LogRecord {
+        level,
+        target,
+        message: args.to_string(),
+    };
+    match &mut config().sink {
+        Sink::Stderr => eprintln!("{}", record),
+        Sink::File(file) => {
+            let _ = writeln!(file, "{}", record);
+        }
+        Sink::Ring { records, capacity } => {
+            if *capacity == 0 {
+                return;
+            }
+            if records.len() == *capacity {
+                records.pop_front();
+            }
+            records.push_back(record);
+        }
+    }
+}
+
+/// `mp_log!(Debug, "sched", "hilo {}", tid)`
+#[macro_export]
+macro_rules! mp_log {
+    ($level:ident, $target:expr, $($arg:tt)*) => {{
+        $crate::log::log($crate::log::Level::$level, $target, format_args!($($arg)*));
+    }};
+}
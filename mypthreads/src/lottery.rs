@@ -0,0 +1,154 @@
+//! monedas de tiquetes para el scheduler de sorteo (estilo Waldspurger)
+//!
+//! Cada moneda esta respaldada por una cantidad de tiquetes base. Los hilos que
+//! tienen tiquetes en una moneda se reparten ese respaldo en proporcion a sus
+//! tiquetes, asi un grupo de hilos comparte un fondo fijo sin importar cuantos sean.
+
+use crate::thread::{MyThread, ThreadId, ThreadState};
+use std::collections::HashMap;
+
+pub type CurrencyId = u32;
+
+/// Una moneda de tiquetes y su respaldo en tiquetes base
+#[derive(Debug, Clone)]
+pub struct TicketCurrency {
+    pub id: CurrencyId,
+    pub name: String,
+    pub funding: u32,
+}
+
+/// Registro de monedas del runtime
+#[derive(Debug)]
+pub struct LotteryLedger {
+    next_id: CurrencyId,
+    currencies: HashMap<CurrencyId, TicketCurrency>,
+}
+
+impl Default for LotteryLedger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LotteryLedger {
+    pub fn new() -> Self {
+        Self {
+            next_id: 1,
+            currencies: HashMap::new(),
+        }
+    }
+
+    /// crea una moneda respaldada por `funding` tiquetes base
+    pub fn create_currency(&mut self, name: impl Into<String>, funding: u32) -> CurrencyId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.currencies.insert(
+            id,
+            TicketCurrency {
+                id,
+                name: name.into(),
+                funding,
+            },
+        );
+        id
+    }
+
+    /// cambia el respaldo de una moneda, retorna false si no existe
+    pub fn fund(&mut self, id: CurrencyId, funding: u32) -> bool {
+        match self.currencies.get_mut(&id) {
+            Some(currency) => {
+                currency.funding = funding;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get(&self, id: CurrencyId) -> Option<&TicketCurrency> {
+        self.currencies.get(&id)
+    }
+
+    /// Recalcula `effective_tickets` de cada hilo en tiquetes base.
+    ///
+    /// El valor propio de un hilo son sus tiquetes mas los de compensacion,
+    /// convertidos por su moneda. Un hilo bloqueado esperando un lock presta
+    /// su valor al dueño del lock (siguiendo la cadena si el dueño tambien espera).
+    ///
+    /// Como en Waldspurger, solo los tiquetes activos reparten el respaldo de
+    /// una moneda: un hilo bloqueado que no presta no diluye a los demas y
+    /// sus tiquetes no valen nada mientras espera.
+    pub fn refresh(&self, threads: &mut HashMap<ThreadId, Box<MyThread>>) {
+        // tiquetes activos emitidos por moneda
+        let mut issued: HashMap<CurrencyId, u64> = HashMap::new();
+        for thread in threads.values() {
+            if let Some(c) = thread.currency {
+                if is_active(thread) {
+                    *issued.entry(c).or_insert(0) += thread.tickets as u64;
+                }
+            }
+        }
+
+        // valor propio de cada hilo en tiquetes base
+        let own: HashMap<ThreadId, u64> = threads
+            .iter()
+            .map(|(&tid, thread)| {
+                let local = thread.tickets as u64 + thread.compensation_tickets as u64;
+                let value = match thread.currency {
+                    None => local,
+                    Some(_) if !is_active(thread) => 0,
+                    Some(c) => {
+                        let funding = self.get(c).map_or(0, |c| c.funding as u64);
+                        let total = issued.get(&c).copied().unwrap_or(0);
+                        (local * funding).checked_div(total).unwrap_or(0)
+                    }
+                };
+                (tid, value)
+            })
+            .collect();
+
+        let mut effective = own.clone();
+
+        // transferencias: el prestamista pierde su valor y lo recibe el dueño final del lock
+        for (&tid, thread) in threads.iter() {
+            if thread.state != ThreadState::Blocked {
+                continue;
+            }
+            let Some(mut holder) = thread.lending_to else {
+                continue;
+            };
+            let mut hops = 0;
+            while let Some(next) = threads
+                .get(&holder)
+                .filter(|t| t.state == ThreadState::Blocked)
+                .and_then(|t| t.lending_to)
+            {
+                hops += 1;
+                if next == tid || hops > threads.len() {
+                    break;
+                }
+                holder = next;
+            }
+            if holder == tid {
+                continue;
+            }
+            let value = own[&tid];
+            *effective.get_mut(&tid).unwrap() -= value;
+            if let Some(v) = effective.get_mut(&holder) {
+                *v += value;
+            }
+        }
+
+        for (tid, thread) in threads.iter_mut() {
+            thread.effective_tickets = effective[tid].min(u32::MAX as u64) as u32;
+        }
+    }
+}
+
+/// compite por el CPU: listo, corriendo, o bloqueado prestandole al dueño de un lock
+fn is_active(thread: &MyThread) -> bool {
+    match thread.state {
+        ThreadState::Terminated => false,
+        ThreadState::Blocked => thread.lending_to.is_some(),
+        _ => true,
+    }
+}
@@ -0,0 +1,562 @@
+//! simulador del scheduler sin hilos: solo el modelo
+//!
+//! Toma un conjunto de tareas declarado (llegada, rafaga, periodo, tiquetes,
+//! deadline) y lo reparte con las mismas politicas de `sched` sobre un reloj
+//! virtual, sin correr closures. Sirve para anticipar como se va a comportar
+//! el scheduler antes de armar un escenario completo.
+//!
+//! Formato del archivo, una tarea por linea (los tiempos en ms y el deadline
+//! relativo a cada llegada):
+//! ```text
+//! # mypthreads task set v1
+//! task radio lottery arrival=0 burst=40 tickets=5
+//! task sensor realtime arrival=0 burst=10 period=50 deadline=20
+//! task log rr arrival=15 burst=30
+//! ```
+
+use crate::runtime::QUANTUM_MS;
+use crate::sched::{self, Schedulable};
+use crate::thread::{SchedulerType, ThreadId};
+use crate::trace::escape_json;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+const HEADER: &str = "# mypthreads task set v1";
+
+/// Una tarea del modelo; con `period_ms` llega de nuevo cada periodo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskSpec {
+    pub name: String,
+    pub sched: SchedulerType,
+    pub arrival_ms: u64,
+    /// tiempo de CPU que necesita cada llegada
+    pub burst_ms: u64,
+    pub period_ms: Option<u64>,
+    pub tickets: u32,
+    /// deadline relativo a cada llegada (solo para tiempo real)
+    pub deadline_ms: Option<u64>,
+}
+
+impl TaskSpec {
+    /// tarea de una sola llegada, con 1 tiquete y sin deadline
+    pub fn new(name: impl Into<String>, sched: SchedulerType, arrival_ms: u64, burst_ms: u64) -> Self {
+        Self {
+            name: name.into(),
+            sched,
+            arrival_ms,
+            burst_ms,
+            period_ms: None,
+            tickets: 1,
+            deadline_ms: None,
+        }
+    }
+}
+
+/// Conjunto de tareas a simular
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskSet {
+    pub tasks: Vec<TaskSpec>,
+}
+
+impl TaskSet {
+    pub fn new(tasks: Vec<TaskSpec>) -> Self {
+        Self { tasks }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::from(HEADER);
+        out.push('\n');
+        for task in &self.tasks {
+            let _ = write!(
+                out,
+                "task {} {} arrival={} burst={}",
+                task.name,
+                sched_name(task.sched),
+                task.arrival_ms,
+                task.burst_ms
+            );
+            if let Some(period) = task.period_ms {
+                let _ = write!(out, " period={}", period);
+            }
+            if task.tickets != 1 {
+                let _ = write!(out, " tickets={}", task.tickets);
+            }
+            if let Some(deadline) = task.deadline_ms {
+                let _ = write!(out, " deadline={}", deadline);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// lee el formato de `to_text`; el error indica la linea invalida
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut set = TaskSet::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |why: &str| format!("linea {}: '{}' no es valida ({})", n + 1, line, why);
+            let mut parts = line.split_whitespace();
+            if parts.next() != Some("task") {
+                return Err(invalid("se esperaba 'task'"));
+            }
+            let name = parts.next().ok_or_else(|| invalid("falta el nombre"))?;
+            if set.tasks.iter().any(|t| t.name == name) {
+                return Err(invalid("ya hay una tarea con ese nombre"));
+            }
+            let sched = match parts.next() {
+                Some("rr") => SchedulerType::RoundRobin,
+                Some("lottery") => SchedulerType::Lottery,
+                Some("realtime") => SchedulerType::RealTime,
+                _ => return Err(invalid("el scheduler es rr, lottery o realtime")),
+            };
+            let mut task = TaskSpec::new(name, sched, 0, 0);
+            for field in parts {
+                let (key, value) = field.split_once('=').ok_or_else(|| invalid("campo sin '='"))?;
+                let value: u64 = value.parse().map_err(|_| invalid("valor no numerico"))?;
+                match key {
+                    "arrival" => task.arrival_ms = value,
+                    "burst" => task.burst_ms = value,
+                    "period" => task.period_ms = Some(value),
+                    "tickets" => task.tickets = u32::try_from(value).map_err(|_| invalid("demasiados tiquetes"))?,
+                    "deadline" => task.deadline_ms = Some(value),
+                    _ => return Err(invalid("campo desconocido")),
+                }
+            }
+            if task.burst_ms == 0 {
+                return Err(invalid("burst tiene que ser mayor que 0"));
+            }
+            if task.period_ms == Some(0) {
+                return Err(invalid("period tiene que ser mayor que 0"));
+            }
+            if task.deadline_ms.is_some() && task.sched != SchedulerType::RealTime {
+                return Err(invalid("solo las tareas realtime tienen deadline"));
+            }
+            if task.sched == SchedulerType::Lottery && task.tickets == 0 {
+                return Err(invalid("un hilo de sorteo necesita al menos un tiquete"));
+            }
+            set.tasks.push(task);
+        }
+        Ok(set)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Reparte las tareas con el scheduler del runtime sobre un reloj virtual.
+    /// Cada despacho corre hasta un quantum; las tareas periodicas dejan de
+    /// llegar en `horizon_ms`, y ahi termina la simulacion aunque quede trabajo.
+    /// Con la misma semilla los sorteos salen iguales.
+    pub fn simulate(&self, seed: u64, horizon_ms: u64) -> ModelReport {
+        let mut releases: Vec<(u64, usize)> = Vec::new();
+        for (index, task) in self.tasks.iter().enumerate() {
+            let mut at = task.arrival_ms;
+            while at < horizon_ms {
+                releases.push((at, index));
+                match task.period_ms {
+                    Some(period) => at += period,
+                    None => break,
+                }
+            }
+        }
+        releases.sort();
+        let mut releases: VecDeque<(u64, usize)> = releases.into();
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut jobs: HashMap<ThreadId, ModelJob> = HashMap::new();
+        let mut ready: VecDeque<ThreadId> = VecDeque::new();
+        let mut slices: Vec<GanttSlice> = Vec::new();
+        let mut now_ms = 0;
+
+        loop {
+            self.release(&mut releases, now_ms, &mut jobs, &mut ready);
+            if now_ms >= horizon_ms {
+                break;
+            }
+            // sin nada listo el reloj salta a la siguiente llegada
+            if ready.is_empty() {
+                let Some(&(next, _)) = releases.front() else {
+                    break;
+                };
+                push_slice(&mut slices, None, None, now_ms, next);
+                now_ms = next;
+                continue;
+            }
+
+            let tid = sched::select_next_thread(&ready, &jobs, now_ms, &mut rng).expect("la cola de listos no esta vacia");
+            ready.retain(|&ready_tid| ready_tid != tid);
+            let job = jobs.get_mut(&tid).unwrap();
+            job.start_ms.get_or_insert(now_ms);
+            let slice = job.remaining_ms.min(QUANTUM_MS);
+            job.remaining_ms -= slice;
+            push_slice(&mut slices, Some(tid), Some(&self.tasks[job.task].name), now_ms, now_ms + slice);
+            now_ms += slice;
+
+            // lo que llego durante el quantum se encola antes que el que cedio
+            self.release(&mut releases, now_ms, &mut jobs, &mut ready);
+            let job = jobs.get_mut(&tid).unwrap();
+            if job.remaining_ms == 0 {
+                job.finish_ms = Some(now_ms);
+            } else {
+                ready.push_back(tid);
+            }
+        }
+
+        let mut job_reports: Vec<JobReport> = jobs
+            .into_iter()
+            .map(|(tid, job)| {
+                let task = &self.tasks[job.task];
+                let missed_deadline = job.deadline_ms.is_some_and(|deadline| job.finish_ms.unwrap_or(now_ms) > deadline);
+                JobReport {
+                    job: tid,
+                    task: task.name.clone(),
+                    release_ms: job.release_ms,
+                    burst_ms: task.burst_ms,
+                    deadline_ms: job.deadline_ms,
+                    start_ms: job.start_ms,
+                    finish_ms: job.finish_ms,
+                    missed_deadline,
+                }
+            })
+            .collect();
+        job_reports.sort_by_key(|report| report.job);
+
+        ModelReport {
+            seed,
+            end_ms: now_ms,
+            tasks: self.tasks.iter().map(|t| t.name.clone()).collect(),
+            slices,
+            jobs: job_reports,
+        }
+    }
+
+    /// encola las llegadas que ya ocurrieron; los trabajos se numeran desde 1
+    fn release(
+        &self,
+        releases: &mut VecDeque<(u64, usize)>,
+        now_ms: u64,
+        jobs: &mut HashMap<ThreadId, ModelJob>,
+        ready: &mut VecDeque<ThreadId>,
+    ) {
+        while let Some(&(at, index)) = releases.front() {
+            if at > now_ms {
+                break;
+            }
+            releases.pop_front();
+            let task = &self.tasks[index];
+            let tid = jobs.len() as ThreadId + 1;
+            jobs.insert(
+                tid,
+                ModelJob {
+                    task: index,
+                    sched: task.sched,
+                    tickets: if task.sched == SchedulerType::RoundRobin { 1 } else { task.tickets },
+                    deadline_ms: (task.sched == SchedulerType::RealTime)
+                        .then_some(task.deadline_ms)
+                        .flatten()
+                        .map(|deadline| at + deadline),
+                    release_ms: at,
+                    remaining_ms: task.burst_ms,
+                    start_ms: None,
+                    finish_ms: None,
+                },
+            );
+            ready.push_back(tid);
+        }
+    }
+}
+
+fn sched_name(sched: SchedulerType) -> &'static str {
+    match sched {
+        SchedulerType::RoundRobin => "rr",
+        SchedulerType::Lottery => "lottery",
+        SchedulerType::RealTime => "realtime",
+    }
+}
+
+/// une el tramo con el anterior si es del mismo trabajo
+fn push_slice(slices: &mut Vec<GanttSlice>, job: Option<ThreadId>, task: Option<&str>, start_ms: u64, end_ms: u64) {
+    if let Some(last) = slices.last_mut() {
+        if last.job == job && last.end_ms == start_ms {
+            last.end_ms = end_ms;
+            return;
+        }
+    }
+    slices.push(GanttSlice {
+        job,
+        task: task.map(str::to_string),
+        start_ms,
+        end_ms,
+    });
+}
+
+/// una llegada de una tarea, vista por el scheduler
+struct ModelJob {
+    task: usize,
+    sched: SchedulerType,
+    tickets: u32,
+    deadline_ms: Option<u64>,
+    release_ms: u64,
+    remaining_ms: u64,
+    start_ms: Option<u64>,
+    finish_ms: Option<u64>,
+}
+
+impl Schedulable for ModelJob {
+    fn sched_type(&self) -> SchedulerType {
+        self.sched
+    }
+
+    fn effective_tickets(&self) -> u32 {
+        self.tickets
+    }
+
+    fn deadline(&self) -> Option<u64> {
+        self.deadline_ms
+    }
+}
+
+/// Tramo continuo del diagrama de Gantt; sin trabajo es tiempo ocioso
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GanttSlice {
+    pub job: Option<ThreadId>,
+    pub task: Option<String>,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// Resultado de una llegada
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobReport {
+    pub job: ThreadId,
+    pub task: String,
+    pub release_ms: u64,
+    pub burst_ms: u64,
+    /// deadline absoluto
+    pub deadline_ms: Option<u64>,
+    pub start_ms: Option<u64>,
+    /// None si no alcanzo a terminar antes del horizonte
+    pub finish_ms: Option<u64>,
+    pub missed_deadline: bool,
+}
+
+impl JobReport {
+    /// de la llegada al final
+    pub fn turnaround_ms(&self) -> Option<u64> {
+        Some(self.finish_ms? - self.release_ms)
+    }
+
+    /// tiempo en la cola de listos: turnaround menos la rafaga
+    pub fn waiting_ms(&self) -> Option<u64> {
+        Some(self.turnaround_ms()? - self.burst_ms)
+    }
+}
+
+/// Promedios de todas las llegadas de una tarea
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskSummary {
+    pub task: String,
+    pub jobs: usize,
+    pub finished: usize,
+    /// promedios sobre las llegadas que terminaron
+    pub avg_waiting_ms: f64,
+    pub avg_turnaround_ms: f64,
+    pub deadline_misses: usize,
+}
+
+/// Lo que produjo `TaskSet::simulate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelReport {
+    pub seed: u64,
+    /// reloj virtual al terminar
+    pub end_ms: u64,
+    /// nombres de las tareas, en el orden del conjunto
+    pub tasks: Vec<String>,
+    pub slices: Vec<GanttSlice>,
+    pub jobs: Vec<JobReport>,
+}
+
+impl ModelReport {
+    pub fn deadline_misses(&self) -> usize {
+        self.jobs.iter().filter(|j| j.missed_deadline).count()
+    }
+
+    pub fn summaries(&self) -> Vec<TaskSummary> {
+        self.tasks
+            .iter()
+            .map(|name| {
+                let jobs: Vec<&JobReport> = self.jobs.iter().filter(|j| &j.task == name).collect();
+                let finished: Vec<&&JobReport> = jobs.iter().filter(|j| j.finish_ms.is_some()).collect();
+                let avg = |f: fn(&JobReport) -> Option<u64>| {
+                    let total: u64 = finished.iter().filter_map(|j| f(j)).sum();
+                    total as f64 / finished.len().max(1) as f64
+                };
+                TaskSummary {
+                    task: name.clone(),
+                    jobs: jobs.len(),
+                    finished: finished.len(),
+                    avg_waiting_ms: avg(JobReport::waiting_ms),
+                    avg_turnaround_ms: avg(JobReport::turnaround_ms),
+                    deadline_misses: jobs.iter().filter(|j| j.missed_deadline).count(),
+                }
+            })
+            .collect()
+    }
+
+    /// Diagrama de Gantt en texto: una fila por tarea y una columna cada
+    /// `scale_ms`; '#' si la tarea corrio en esa columna.
+    pub fn gantt(&self, scale_ms: u64) -> String {
+        let scale_ms = scale_ms.max(1);
+        let columns = self.end_ms.div_ceil(scale_ms) as usize;
+        let width = self.tasks.iter().map(|t| t.len()).max().unwrap_or(0).max(4);
+
+        // regla con una marca cada 10 columnas
+        let mut out = format!("{:width$} ", "ms");
+        for column in (0..columns).step_by(10) {
+            let label = (column as u64 * scale_ms).to_string();
+            let _ = write!(out, "{:<10}", label);
+        }
+        out.push('\n');
+
+        // la ultima fila es el tiempo ocioso
+        let idle = self.tasks.len();
+        let mut rows: Vec<(&str, Vec<char>)> = self.tasks.iter().map(|t| (t.as_str(), vec!['.'; columns])).collect();
+        rows.push(("idle", vec![' '; columns]));
+        for slice in &self.slices {
+            let row = match &slice.task {
+                Some(task) => self.tasks.iter().position(|t| t == task).unwrap_or(idle),
+                None => idle,
+            };
+            let mark = if slice.task.is_some() { '#' } else { '-' };
+            let first = (slice.start_ms / scale_ms) as usize;
+            let last = slice.end_ms.div_ceil(scale_ms) as usize;
+            for cell in &mut rows[row].1[first..last.min(columns)] {
+                *cell = mark;
+            }
+        }
+        for (row, (name, cells)) in rows.into_iter().enumerate() {
+            if row == idle && !cells.contains(&'-') {
+                continue;
+            }
+            let _ = writeln!(out, "{:width$} |{}|", name, cells.into_iter().collect::<String>());
+        }
+        out
+    }
+
+    /// Gantt, cada llegada y los promedios por tarea
+    pub fn to_text(&self) -> String {
+        let mut out = format!("semilla {}, reloj final {} ms\n\n", self.seed, self.end_ms);
+        out.push_str(&self.gantt(QUANTUM_MS));
+        out.push('\n');
+
+        let opt = |v: Option<u64>| v.map_or("-".to_string(), |v| v.to_string());
+        let _ = writeln!(
+            out,
+            "{:>4} {:<12} {:>7} {:>7} {:>7} {:>8} {:>7} {:>10}",
+            "job", "tarea", "llegada", "inicio", "fin", "deadline", "espera", "turnaround"
+        );
+        for job in &self.jobs {
+            let _ = writeln!(
+                out,
+                "{:>4} {:<12} {:>7} {:>7} {:>7} {:>8} {:>7} {:>10}{}",
+                job.job,
+                job.task,
+                job.release_ms,
+                opt(job.start_ms),
+                opt(job.finish_ms),
+                opt(job.deadline_ms),
+                opt(job.waiting_ms()),
+                opt(job.turnaround_ms()),
+                if job.missed_deadline { "  DEADLINE PERDIDO" } else { "" }
+            );
+        }
+        out.push('\n');
+
+        for summary in self.summaries() {
+            let _ = writeln!(
+                out,
+                "{}: {}/{} terminadas, espera media {:.1} ms, turnaround medio {:.1} ms, {} deadlines perdidos",
+                summary.task,
+                summary.finished,
+                summary.jobs,
+                summary.avg_waiting_ms,
+                summary.avg_turnaround_ms,
+                summary.deadline_misses
+            );
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let opt = |v: Option<u64>| v.map_or("null".to_string(), |v| v.to_string());
+        let slices: Vec<String> = self
+            .slices
+            .iter()
+            .map(|s| {
+                let task = s.task.as_ref().map_or("null".to_string(), |t| format!("\"{}\"", escape_json(t)));
+                format!(
+                    r#"{{"task":{},"job":{},"start_ms":{},"end_ms":{}}}"#,
+                    task,
+                    opt(s.job.map(u64::from)),
+                    s.start_ms,
+                    s.end_ms
+                )
+            })
+            .collect();
+        let jobs: Vec<String> = self
+            .jobs
+            .iter()
+            .map(|j| {
+                format!(
+                    r#"{{"job":{},"task":"{}","release_ms":{},"burst_ms":{},"deadline_ms":{},"start_ms":{},"finish_ms":{},"waiting_ms":{},"turnaround_ms":{},"missed_deadline":{}}}"#,
+                    j.job,
+                    escape_json(&j.task),
+                    j.release_ms,
+                    j.burst_ms,
+                    opt(j.deadline_ms),
+                    opt(j.start_ms),
+                    opt(j.finish_ms),
+                    opt(j.waiting_ms()),
+                    opt(j.turnaround_ms()),
+                    j.missed_deadline
+                )
+            })
+            .collect();
+        let summaries: Vec<String> = self
+            .summaries()
+            .iter()
+            .map(|s| {
+                format!(
+                    r#"{{"task":"{}","jobs":{},"finished":{},"avg_waiting_ms":{:.3},"avg_turnaround_ms":{:.3},"deadline_misses":{}}}"#,
+                    escape_json(&s.task),
+                    s.jobs,
+                    s.finished,
+                    s.avg_waiting_ms,
+                    s.avg_turnaround_ms,
+                    s.deadline_misses
+                )
+            })
+            .collect();
+        format!(
+            "{{\"seed\":{},\"end_ms\":{},\"deadline_misses\":{},\n\"gantt\":[\n{}\n],\n\"jobs\":[\n{}\n],\n\"tasks\":[\n{}\n]}}\n",
+            self.seed,
+            self.end_ms,
+            self.deadline_misses(),
+            slices.join(",\n"),
+            jobs.join(",\n"),
+            summaries.join(",\n")
+        )
+    }
+}
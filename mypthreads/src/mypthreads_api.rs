@@ -1,169 +1,946 @@
 use crate::api_context;
-use crate::channels::SimpleMutex;
-use crate::runtime::ThreadRuntimeV2;
+use crate::blocking;
+use crate::executor;
+use crate::channels::{HandoffRequest, MutexKind, SimpleMutex, UNLOCKED};
+use crate::error::{MyResult, MyThreadError};
+use crate::group::GroupId;
+use crate::lottery::CurrencyId;
+use crate::observer::{ObserverId, RuntimeObserver};
+use crate::reactor::Interest;
+use crate::remote::{RemoteMessage, RemoteQueue, RuntimeHandle};
+use crate::replay::{Divergence, SchedRecording};
+use crate::runtime::{ThreadRuntimeV2, QUANTUM_MS};
 use crate::signals::ThreadSignal;
+use crate::snapshot::RuntimeSnapshot;
+use crate::stats::{StackUsage, StatsSummary, ThreadStats};
 use crate::thread::{ContextThreadEntry, SchedulerType, ThreadId};
-use std::sync::atomic::{AtomicBool, Ordering};
+use crate::tls::{self, KeyDestructor, MyKey};
+use crate::watchdog::WatchdogFlag;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::time::Duration;
 
 
 //RUNTIME GLOBAL 
 // Debe inicializarse explícitamente con `runtime_init()` antes de usar.
 pub static mut RUNTIME: Option<(SimpleMutex, ThreadRuntimeV2)> = None;
 
+// cola de pedidos remotos del runtime global, copiada en `runtime_init()` para
+// que `runtime_handle()` no tenga que leer `RUNTIME` desde otro hilo
+static REMOTE: OnceLock<Arc<RemoteQueue>> = OnceLock::new();
+
+// hilo del sistema que llamo a `runtime_init()`: es el unico que corre ciclos
+// desde `my_block_on`, los demas solo esperan su wake
+static OWNER: OnceLock<std::thread::ThreadId> = OnceLock::new();
+
 /// Inicializa el runtime global de mypthreads.
 /// Debe llamarse una sola vez!!!
 pub fn runtime_init() {
     unsafe {
         if RUNTIME.is_none() {
-            RUNTIME = Some((SimpleMutex::new(), ThreadRuntimeV2::new()));
+            let runtime = ThreadRuntimeV2::new();
+            let _ = REMOTE.set(runtime.channels.remote().clone());
+            let _ = OWNER.set(std::thread::current().id());
+            RUNTIME = Some((SimpleMutex::new(), runtime));
         }
     }
 }
 
-/// Helper interno para obtener acceso mutable al runtime global.
-fn get_runtime_mut() -> &'static mut (SimpleMutex, ThreadRuntimeV2) {
-    unsafe {
-        match RUNTIME.as_mut() {
-            Some(t) => t,
-            None => panic!("RUNTIME no inicializado: llamá a runtime_init() antes de usar la API"),
-        }
+/// true si el hilo del sistema que llama es el que creo el runtime global
+pub(crate) fn is_runtime_owner() -> bool {
+    OWNER.get() == Some(&std::thread::current().id())
+}
+
+/// Siembra el generador del scheduler para que los sorteos se repitan.
+/// Se llama después de `runtime_init()` y antes de crear hilos.
+pub fn runtime_set_seed(seed: u64) -> MyResult<()> {
+    locked(|runtime| {
+        runtime.set_seed(seed);
+        Ok(())
+    })
+}
+
+/// Empieza a registrar eventos del scheduler (dispatch, yield, block, ...).
+pub fn runtime_trace_enable() -> MyResult<()> {
+    locked(|runtime| {
+        runtime.tracer.enable();
+        Ok(())
+    })
+}
+
+/// Escribe los eventos registrados como Chrome Trace Event JSON en `path`.
+pub fn runtime_trace_write(path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    locked(|runtime| Ok(runtime.tracer.write_chrome_json(path))).map_err(std::io::Error::other)?
+}
+
+/// Empieza a grabar cada despacho y cada despertar del scheduler.
+pub fn runtime_record_start() -> MyResult<()> {
+    locked(|runtime| {
+        runtime.start_recording();
+        Ok(())
+    })
+}
+
+/// Escribe lo grabado hasta ahora en `path` (sin detener la grabación).
+pub fn runtime_record_write(path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    locked(|runtime| {
+        Ok(match runtime.replay.recording() {
+            Some(rec) => rec.write(path),
+            None => Err(std::io::Error::other("el runtime no está grabando")),
+        })
+    })
+    .map_err(std::io::Error::other)?
+}
+
+/// Fuerza al scheduler a repetir las decisiones de `recording`.
+/// La semilla de la grabación se aplica aparte con `runtime_set_seed`.
+pub fn runtime_replay_start(recording: SchedRecording) -> MyResult<()> {
+    locked(|runtime| {
+        runtime.start_replay(recording);
+        Ok(())
+    })
+}
+
+/// Dónde se apartó la ejecución de la grabación, si pasó.
+pub fn runtime_replay_divergence() -> MyResult<Option<Divergence>> {
+    locked(|runtime| Ok(runtime.replay.divergence().cloned()))
+}
+
+/// Registra un observador que recibe cada evento del runtime (spawn, dispatch, block, ...).
+pub fn runtime_add_observer(observer: impl RuntimeObserver + 'static) -> MyResult<ObserverId> {
+    locked(|runtime| Ok(runtime.add_observer(observer)))
+}
+
+/// Quita un observador registrado con `runtime_add_observer`.
+pub fn runtime_remove_observer(id: ObserverId) -> MyResult<bool> {
+    locked(|runtime| Ok(runtime.remove_observer(id)))
+}
+
+/// Acceso al runtime global para otros modulos del crate (p. ej. la capa C).
+pub(crate) fn with_runtime<R>(f: impl FnOnce(&mut ThreadRuntimeV2) -> R) -> MyResult<R> {
+    locked(|runtime| Ok(f(runtime)))
+}
+
+/// Helper interno para obtener acceso mutable al runtime global; solo `locked` lo usa.
+fn get_runtime_mut() -> MyResult<&'static mut (SimpleMutex, ThreadRuntimeV2)> {
+    unsafe { RUNTIME.as_mut().ok_or(MyThreadError::NotInitialized) }
+}
+
+/// Corre `f` con el lock del runtime tomado por el hilo actual (0 = main).
+/// La unica excepcion es un hilo verde llamando a la API en medio de su paso:
+/// el lock lo tiene el despachador que lo esta corriendo, asi que sigue sin
+/// tomarlo. Cualquier otro que encuentre el lock tomado recibe EBUSY.
+fn locked<R>(f: impl FnOnce(&mut ThreadRuntimeV2) -> MyResult<R>) -> MyResult<R> {
+    let (mutex, runtime) = get_runtime_mut()?;
+    let current_tid = api_context::try_current_tid().unwrap_or(0);
+
+    if mutex.try_lock(current_tid) {
+        let result = f(runtime);
+        let _ = mutex.unlock(current_tid);
+        return result;
     }
+    if api_context::in_dispatch() {
+        return f(runtime);
+    }
+    Err(MyThreadError::Busy)
 }
 
 /// Tipos de parámetros de planificación (scheduler)
 pub enum SchedulerParams {
     RoundRobin,
     Lottery { tickets: u32 },
+    /// Sorteo con tiquetes denominados en una moneda (ver `my_currency_create`)
+    LotteryFunded { currency: CurrencyId, tickets: u32 },
     RealTime { deadline: u64 },
 }
 
+impl SchedulerParams {
+    /// Lo que se puede revisar sin el runtime: EINVAL con 0 tiquetes.
+    pub(crate) fn validate(&self) -> MyResult<()> {
+        match self {
+            SchedulerParams::Lottery { tickets: 0 } | SchedulerParams::LotteryFunded { tickets: 0, .. } => {
+                Err(MyThreadError::InvalidArgument("un hilo de sorteo necesita al menos un tiquete"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Traduce los parámetros a (tipo, tiquetes, deadline, moneda).
+    /// EINVAL con 0 tiquetes o una moneda que no existe.
+    pub(crate) fn resolve(
+        self,
+        runtime: &ThreadRuntimeV2,
+    ) -> MyResult<(SchedulerType, u32, Option<u64>, Option<CurrencyId>)> {
+        self.validate()?;
+        match self {
+            SchedulerParams::RoundRobin => Ok((SchedulerType::RoundRobin, 1, None, None)),
+            SchedulerParams::Lottery { tickets } => Ok((SchedulerType::Lottery, tickets, None, None)),
+            SchedulerParams::LotteryFunded { currency, tickets } => {
+                if runtime.lottery.get(currency).is_none() {
+                    return Err(MyThreadError::InvalidArgument("moneda inexistente"));
+                }
+                Ok((SchedulerType::Lottery, tickets, None, Some(currency)))
+            }
+            SchedulerParams::RealTime { deadline } => Ok((SchedulerType::RealTime, 0, Some(deadline), None)),
+        }
+    }
+}
+
 /// Crea un nuevo hilo manejado por mypthreads.
 pub fn my_thread_create(
     name: &str,
     params: SchedulerParams,
     entry: ContextThreadEntry,
-) -> ThreadId {
-    let r = get_runtime_mut();
-    let (mutex, runtime) = &mut *r;
+) -> MyResult<ThreadId> {
+    create_named(|_| name.to_string(), params, entry)
+}
 
-    let current_tid = api_context::try_current_tid().unwrap_or(0);
-    mutex.lock(current_tid);
+/// como `my_thread_create`, con un nombre que depende del tid del hilo
+pub(crate) fn create_named(
+    name: impl FnOnce(ThreadId) -> String,
+    params: SchedulerParams,
+    entry: ContextThreadEntry,
+) -> MyResult<ThreadId> {
+    locked(|runtime| {
+        let (sched, tickets, deadline, currency) = params.resolve(runtime)?;
 
-    let (sched, tickets, deadline) = match params {
-        SchedulerParams::RoundRobin => (SchedulerType::RoundRobin, 1, None),
-        SchedulerParams::Lottery { tickets } => (SchedulerType::Lottery, tickets, None),
-        SchedulerParams::RealTime { deadline } => (SchedulerType::RealTime, 0, Some(deadline)),
-    };
+        let id = runtime.reserve_tid();
+        runtime.spawn_with_tid(id, name(id), sched, entry, tickets, deadline);
+        runtime.set_currency(id, currency);
+        Ok(id)
+    })
+}
 
-    let id = runtime.spawn(name, sched, entry, tickets, deadline);
+/// Marca un hilo como "detached": se libera solo al terminar, o de una vez si
+/// ya termino. EINVAL si ya lo era.
+pub fn my_thread_detach(tid: ThreadId) -> MyResult<()> {
+    locked(|runtime| {
+        let thread = runtime.threads.get_mut(&tid).ok_or(MyThreadError::NoSuchThread(tid))?;
+        if thread.detached {
+            return Err(MyThreadError::InvalidArgument("el hilo ya es detached"));
+        }
+        thread.detached = true;
+        runtime.reap(tid);
+        Ok(())
+    })
+}
 
-    mutex.unlock(current_tid);
-    id
+/// Espera a que termine un hilo, con las reglas de pthread_join. Retorna la
+/// señal con la que el paso debe bloquearse, o None si ya termino (y entonces
+/// lo libera): `if let Some(signal) = my_thread_join(tid)? { return signal; }`.
+/// Desde el hilo principal, Some significa que hay que correr el runtime y
+/// volver a llamar. EDEADLK si es el mismo hilo o si el objetivo lo espera a
+/// el, ESRCH si no existe, EINVAL si es detached o ya tiene quien lo espere.
+pub fn my_thread_join(tid: ThreadId) -> MyResult<Option<ThreadSignal>> {
+    locked(|runtime| {
+        let me = api_context::try_current_tid().unwrap_or(0);
+        if runtime.check_join(me, tid)? {
+            Ok(Some(ThreadSignal::Join(tid)))
+        } else {
+            runtime.reap(tid);
+            Ok(None)
+        }
+    })
 }
 
-/// Marca un hilo como "detached"
-pub fn my_thread_detach(tid: ThreadId) {
-    let r = get_runtime_mut();
-    let (mutex, runtime) = &mut *r;
-    let current_tid = api_context::try_current_tid().unwrap_or(0);
+/// Id del hilo que llama. Falla desde el hilo principal.
+pub fn my_thread_self() -> MyResult<ThreadId> {
+    api_context::try_current_tid().ok_or(MyThreadError::NotInThread)
+}
 
-    mutex.lock(current_tid);
-    if let Some(thread) = runtime.threads.get_mut(&tid) {
-        thread.detached = true;
+/// Cambia los parámetros de planificación de un hilo
+pub fn my_thread_chsched(tid: ThreadId, params: SchedulerParams) -> MyResult<()> {
+    locked(|runtime| {
+        if !runtime.threads.contains_key(&tid) {
+            return Err(MyThreadError::NoSuchThread(tid));
+        }
+        let (sched, tickets, deadline, currency) = params.resolve(runtime)?;
+        runtime.set_sched_params(tid, sched, tickets, deadline, currency);
+        Ok(())
+    })
+}
+
+/// Crea una moneda de tiquetes respaldada por `funding` tiquetes base.
+/// Los hilos creados con `SchedulerParams::LotteryFunded` se reparten ese respaldo.
+pub fn my_currency_create(name: &str, funding: u32) -> MyResult<CurrencyId> {
+    locked(|runtime| Ok(runtime.create_currency(name, funding)))
+}
+
+/// Cambia el respaldo de una moneda. EINVAL si la moneda no existe.
+pub fn my_currency_fund(currency: CurrencyId, funding: u32) -> MyResult<()> {
+    locked(|runtime| {
+        if runtime.fund_currency(currency, funding) {
+            Ok(())
+        } else {
+            Err(MyThreadError::InvalidArgument("moneda inexistente"))
+        }
+    })
+}
+
+fn check_group(runtime: &ThreadRuntimeV2, group: GroupId) -> MyResult<()> {
+    if runtime.groups.contains(group) {
+        Ok(())
+    } else {
+        Err(MyThreadError::InvalidArgument("grupo inexistente"))
     }
-    mutex.unlock(current_tid);
 }
 
-/// Cambia los parámetros de planificación de un hilo
-pub fn my_thread_chsched(tid: ThreadId, params: SchedulerParams) {
-    let r = get_runtime_mut();
-    let (mutex, runtime) = &mut *r;
-    let current_tid = api_context::try_current_tid().unwrap_or(0);
+/// Crea un grupo de hilos vacio
+pub fn my_group_create(name: &str) -> MyResult<GroupId> {
+    locked(|runtime| Ok(runtime.create_group(name)))
+}
+
+/// Crea un hilo dentro de `group`. EINVAL si el grupo no existe.
+pub fn my_thread_create_in_group(
+    group: GroupId,
+    name: &str,
+    params: SchedulerParams,
+    entry: ContextThreadEntry,
+) -> MyResult<ThreadId> {
+    locked(|runtime| {
+        check_group(runtime, group)?;
+        let (sched, tickets, deadline, currency) = params.resolve(runtime)?;
+
+        let id = runtime.spawn(name, sched, entry, tickets, deadline);
+        runtime.set_currency(id, currency);
+        runtime.set_group(id, Some(group));
+        Ok(id)
+    })
+}
 
-    mutex.lock(current_tid);
-    if let Some(thread) = runtime.threads.get_mut(&tid) {
-        let (sched, tickets, deadline) = match params {
-            SchedulerParams::RoundRobin => (SchedulerType::RoundRobin, 1, None),
-            SchedulerParams::Lottery { tickets } => (SchedulerType::Lottery, tickets, None),
-            SchedulerParams::RealTime { deadline } => (SchedulerType::RealTime, 0, Some(deadline)),
-        };
+/// Miembros de un grupo ordenados por tid (incluye los terminados que aun no se liberan)
+pub fn my_group_members(group: GroupId) -> MyResult<Vec<ThreadId>> {
+    locked(|runtime| {
+        check_group(runtime, group)?;
+        Ok(runtime.group_members(group))
+    })
+}
+
+/// Espera a todo el grupo desde un hilo. Retorna la señal con la que el paso
+/// debe bloquearse o None si ya terminaron todos:
+/// `if let Some(signal) = my_group_join(group)? { return signal; }`
+pub fn my_group_join(group: GroupId) -> MyResult<Option<ThreadSignal>> {
+    let me = api_context::try_current_tid().ok_or(MyThreadError::NotInThread)?;
+    locked(|runtime| {
+        check_group(runtime, group)?;
+        Ok(runtime
+            .group_live_members(group)
+            .into_iter()
+            .find(|&tid| tid != me)
+            .map(ThreadSignal::Join))
+    })
+}
+
+/// Cancela un hilo (ver `ThreadRuntimeV2::cancel_thread`). ESRCH si no existe o ya terminó.
+pub fn my_thread_cancel(tid: ThreadId) -> MyResult<()> {
+    locked(|runtime| {
+        if runtime.cancel_thread(tid) {
+            Ok(())
+        } else {
+            Err(MyThreadError::NoSuchThread(tid))
+        }
+    })
+}
+
+/// Cancela todos los miembros vivos de un grupo; retorna cuantos cancelo
+pub fn my_group_cancel(group: GroupId) -> MyResult<usize> {
+    locked(|runtime| {
+        check_group(runtime, group)?;
+        Ok(runtime.cancel_group(group))
+    })
+}
+
+/// Cambia los parámetros de planificación de todos los miembros vivos; retorna cuantos cambio
+pub fn my_group_chsched(group: GroupId, params: SchedulerParams) -> MyResult<usize> {
+    locked(|runtime| {
+        check_group(runtime, group)?;
+        let (sched, tickets, deadline, currency) = params.resolve(runtime)?;
+        Ok(runtime.set_group_sched_params(group, sched, tickets, deadline, currency))
+    })
+}
 
-        thread.sched_type = sched;
-        thread.tickets = tickets;
-        thread.deadline = deadline;
+/// Estadísticas sumadas de los miembros de un grupo
+pub fn my_group_stats(group: GroupId) -> MyResult<StatsSummary> {
+    locked(|runtime| {
+        runtime
+            .group_stats(group)
+            .ok_or(MyThreadError::InvalidArgument("grupo inexistente"))
+    })
+}
+
+/// Cede el control avisando que solo se usaron `used_ms` del quantum.
+/// El runtime le da tiquetes de compensación hasta su siguiente turno.
+/// EINVAL si `used_ms` no está entre 1 y `QUANTUM_MS`.
+pub fn my_thread_yield_early(used_ms: u64) -> MyResult<ThreadSignal> {
+    if !(1..=QUANTUM_MS).contains(&used_ms) {
+        return Err(MyThreadError::InvalidArgument("used_ms fuera del quantum"));
     }
-    mutex.unlock(current_tid);
+    Ok(ThreadSignal::YieldEarly(used_ms))
+}
+
+/// Devuelve las estadísticas acumuladas de un hilo
+pub fn my_thread_stats(tid: ThreadId) -> MyResult<ThreadStats> {
+    locked(|runtime| runtime.thread_stats(tid).ok_or(MyThreadError::NoSuchThread(tid)))
+}
+
+/// Devuelve los totales de todos los hilos del runtime
+pub fn runtime_stats_summary() -> MyResult<StatsSummary> {
+    locked(|runtime| Ok(runtime.stats_summary()))
+}
+
+/// Uso de pila por clase de hilo (grupo o nombre), para dimensionar pilas
+/// por tipo de hilo (ver `ThreadRuntimeV2::stack_usage`)
+pub fn runtime_stack_usage() -> MyResult<BTreeMap<String, StackUsage>> {
+    locked(|runtime| Ok(runtime.stack_usage()))
+}
+
+/// Cambia los umbrales del watchdog; `None` apaga esa revision
+pub fn runtime_watchdog_config(slow_dispatch: Option<Duration>, livelock_wakeups: Option<u32>) -> MyResult<()> {
+    locked(|runtime| {
+        runtime.watchdog.slow_dispatch = slow_dispatch;
+        runtime.watchdog.livelock_wakeups = livelock_wakeups;
+        Ok(())
+    })
+}
+
+/// Hilos que marco el watchdog, con el ultimo motivo
+pub fn runtime_watchdog_flags() -> MyResult<BTreeMap<ThreadId, WatchdogFlag>> {
+    locked(|runtime| Ok(runtime.watchdog.flagged().clone()))
+}
+
+/// Avisa que el paso actual avanzo; un hilo que se bloquea en cada ronda sin
+/// llamarlo termina marcado como livelock. EPERM desde el hilo principal.
+pub fn my_thread_progress() -> MyResult<()> {
+    api_context::try_current_tid().ok_or(MyThreadError::NotInThread)?;
+    api_context::ctx_progress();
+    Ok(())
+}
+
+/// Error con el que el runtime rechazo la ultima señal devuelta por el paso,
+/// que no tiene otra forma de llegarle al hilo: un `ThreadSignal::Join` a si
+/// mismo, a un detached o ya esperado, o un `ThreadSignal::MutexLock` del
+/// dueño de un errorcheck (EDEADLK). Se borra al leerlo, como errno en un
+/// llamado que falla. EPERM desde el hilo principal.
+pub fn my_thread_last_error() -> MyResult<Option<MyThreadError>> {
+    api_context::try_current_tid().ok_or(MyThreadError::NotInThread)?;
+    Ok(api_context::ctx_last_error())
+}
+
+/// Devuelve una copia del estado de todos los hilos, colas y reloj
+pub fn runtime_snapshot() -> MyResult<RuntimeSnapshot> {
+    locked(|runtime| Ok(runtime.snapshot()))
 }
 
 /// Ejecuta el runtime por una cantidad de ciclos simulados
-pub fn run_simulation(cycles: usize) {
-    let r = get_runtime_mut();
-    let (mutex, runtime) = &mut *r;
-    let tid = api_context::try_current_tid().unwrap_or(0);
+pub fn run_simulation(cycles: usize) -> MyResult<()> {
+    locked(|runtime| {
+        runtime.run(cycles);
+        Ok(())
+    })
+}
+
+/// Handle para crear hilos o mandarles mensajes desde otro hilo del sistema
+/// sin tocar `RUNTIME` (ver `RuntimeHandle`).
+pub fn runtime_handle() -> MyResult<RuntimeHandle> {
+    let remote = REMOTE.get().ok_or(MyThreadError::NotInitialized)?;
+    Ok(RuntimeHandle::new(remote.clone()))
+}
 
-    mutex.lock(tid);
-    runtime.run(cycles);
-    mutex.unlock(tid);
+/// Espera el siguiente mensaje mandado al hilo con `RuntimeHandle::send`.
+/// EPERM desde el hilo principal, que no tiene buzon.
+pub fn my_remote_recv() -> MyResult<RemoteMessage> {
+    api_context::try_current_tid().ok_or(MyThreadError::NotInThread)?;
+    Ok(api_context::ctx_remote_recv())
+}
+
+/// Como `my_remote_recv` pero devuelve None si no hay mensajes
+pub fn my_remote_try_recv() -> MyResult<Option<RemoteMessage>> {
+    api_context::try_current_tid().ok_or(MyThreadError::NotInThread)?;
+    Ok(api_context::ctx_remote_try_recv())
 }
 
 /// Desbloquea todos los hilos
-pub fn runtime_unblock_all() {
-    let r = get_runtime_mut();
-    let (_mutex, runtime) = &mut *r;
-    runtime.unblock_all_threads();
+pub fn runtime_unblock_all() -> MyResult<()> {
+    locked(|runtime| {
+        runtime.unblock_all_threads();
+        Ok(())
+    })
 }
 
 /// Ejecuta el scheduler por `cycles` ciclos
-pub fn runtime_run_cycles(cycles: usize) {
-    let r = get_runtime_mut();
-    let (_mutex, runtime) = &mut *r;
-    runtime.run(cycles);
+pub fn runtime_run_cycles(cycles: usize) -> MyResult<()> {
+    locked(|runtime| {
+        runtime.run(cycles);
+        Ok(())
+    })
 }
 
 
+/// Mutex para hilos verdes; el hilo principal lo usa como el hilo 0
 pub struct MyMutex {
-    locked: AtomicBool,
+    inner: SimpleMutex,
 }
 
 impl MyMutex {
+    /// mutex errorcheck
     pub fn new() -> Self {
+        Self::with_kind(MutexKind::default())
+    }
+
+    pub fn with_kind(kind: MutexKind) -> Self {
         Self {
-            locked: AtomicBool::new(false),
+            inner: SimpleMutex::with_kind(kind),
         }
     }
 
+    pub fn kind(&self) -> MutexKind {
+        self.inner.kind
+    }
+
     /// Forzado para desbloquear (solo debe usarlo el hilo `main`)
     pub fn force_unlock(&self) {
-        self.locked.store(false, Ordering::Release);
+        self.inner.force_unlock();
+    }
+
+    fn owner(&self) -> ThreadId {
+        self.inner.owner.load(Ordering::Relaxed)
     }
 }
 
-/// Inicializa un nuevo mutex cooperativo
-pub fn my_mutex_init() -> MyMutex {
-    MyMutex::new()
+impl Default for MyMutex {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-/// Intenta adquirir el lock cooperativamente
-pub fn my_mutex_lock(mtx: &MyMutex) -> ThreadSignal {
-    if mtx.locked.swap(true, Ordering::Acquire) {
+/// Inicializa un nuevo mutex cooperativo (errorcheck)
+pub fn my_mutex_init() -> MyResult<MyMutex> {
+    Ok(MyMutex::new())
+}
+
+/// Inicializa un mutex del tipo dado (normal, errorcheck o recursivo)
+pub fn my_mutex_init_kind(kind: MutexKind) -> MyResult<MyMutex> {
+    Ok(MyMutex::with_kind(kind))
+}
+
+/// Intenta adquirir el lock. Si está tomado, la señal devuelta bloquea al hilo
+/// hasta que el runtime se lo entregue. Si el hilo ya es el dueño depende del
+/// tipo: el recursivo cuenta un lock más, el errorcheck da EDEADLK y el normal
+/// se queda esperando para siempre. EBUSY desde el hilo principal, que no
+/// puede bloquearse (EDEADLK si ya es el dueño).
+pub fn my_mutex_lock(mtx: &MyMutex) -> MyResult<ThreadSignal> {
+    let thread = api_context::try_current_tid();
+    let tid = thread.unwrap_or(0);
+    if mtx.inner.try_lock(tid) {
+        // Lock adquirido inmediatamente
+        api_context::ctx_mutex_acquired();
+        return Ok(ThreadSignal::Continue);
+    }
+    let relock = mtx.owner() == tid;
+    match thread {
+        Some(_) if relock && mtx.kind() == MutexKind::ErrorCheck => Err(MyThreadError::Deadlock),
         // Ya estaba bloqueado, entonces devolvemos señal para que el runtime pause el hilo
-        ThreadSignal::MutexLock(mtx as *const _ as usize)
+        Some(_) => Ok(ThreadSignal::MutexLock(&mtx.inner as *const SimpleMutex as usize)),
+        None if relock => Err(MyThreadError::Deadlock),
+        None => Err(MyThreadError::Busy),
+    }
+}
+
+/// Intenta adquirir el lock sin bloquearse. EBUSY si está tomado.
+pub fn my_mutex_trylock(mtx: &MyMutex) -> MyResult<()> {
+    let tid = api_context::try_current_tid().unwrap_or(0);
+    if mtx.inner.try_lock(tid) {
+        api_context::ctx_mutex_acquired();
+        Ok(())
     } else {
-        // Lock adquirido inmediatamente
-        ThreadSignal::Continue
+        Err(MyThreadError::Busy)
+    }
+}
+
+/// Libera el lock y se lo pasa al primero que lo esperaba (un recursivo solo
+/// con el último unlock). EPERM si el hilo actual no es el dueño.
+pub fn my_mutex_unlock(mtx: &MyMutex) -> MyResult<ThreadSignal> {
+    let tid = api_context::try_current_tid().unwrap_or(0);
+    if let Some(next) = mtx.inner.unlock(tid)? {
+        hand_off(&mtx.inner, tid, next);
+    }
+    Ok(ThreadSignal::Continue)
+}
+
+/// Le entrega a `to` el mutex que `from` acaba de soltar, con la misma
+/// contabilidad que la señal `MutexUnlock`: desde un hilo al terminar el
+/// paso actual, desde el principal de una vez.
+pub(crate) fn hand_off(mutex: &SimpleMutex, from: ThreadId, to: ThreadId) {
+    let waiters = unsafe { &*mutex.wait_queue.get() }.iter().copied().collect();
+    if api_context::try_current_tid().is_some() {
+        api_context::ctx_hand_off(HandoffRequest { from, to, waiters });
+    } else {
+        let _ = with_runtime(|runtime| runtime.hand_off_mutex(from, to, waiters));
+    }
+}
+
+/// Destruye el mutex. EBUSY si está tomado.
+pub fn my_mutex_destroy(mtx: &mut MyMutex) -> MyResult<()> {
+    if mtx.owner() != UNLOCKED {
+        return Err(MyThreadError::Busy);
+    }
+    Ok(())
+}
+
+// --- BARRERAS, WAITGROUPS Y ONCE ---
+// A diferencia del mutex, estas esperas bloquean dentro del paso (`ctx_suspend`):
+// el hilo se suspende y el runtime lo vuelve a correr cuando lo despiertan.
+
+/// Despierta a `tid`: desde un hilo al terminar el paso actual, desde el
+/// principal de una vez.
+fn wake(tid: ThreadId) {
+    if api_context::try_current_tid().is_some() {
+        api_context::ctx_wake(tid);
+    } else {
+        let _ = with_runtime(|runtime| runtime.unblock_thread(tid));
+    }
+}
+
+pub(crate) fn wake_all(waiters: &Mutex<Vec<ThreadId>>) {
+    let waiters = std::mem::take(&mut *waiters.lock().unwrap_or_else(|e| e.into_inner()));
+    for tid in waiters {
+        wake(tid);
+    }
+}
+
+/// Espera hasta que `done()` sea cierto. Un hilo se anota en `waiters` y se
+/// bloquea; el hilo principal, que no puede bloquearse, corre el runtime.
+/// EDEADLK si desde el principal ya no queda ningún hilo listo.
+pub(crate) fn wait_until(waiters: &Mutex<Vec<ThreadId>>, done: impl Fn() -> bool) -> MyResult<()> {
+    match api_context::try_current_tid() {
+        Some(me) => {
+            loop {
+                {
+                    let mut waiters = waiters.lock().unwrap_or_else(|e| e.into_inner());
+                    if done() {
+                        return Ok(());
+                    }
+                    if !waiters.contains(&me) {
+                        waiters.push(me);
+                    }
+                }
+                // un despertar de mas (p. ej. runtime_unblock_all) vuelve a revisar
+                api_context::ctx_suspend(ThreadSignal::Block);
+            }
+        }
+        None => {
+            while !done() {
+                locked(|runtime| {
+                    if runtime.ready.is_empty() && !runtime.has_io_waiters() {
+                        return Err(MyThreadError::Deadlock);
+                    }
+                    runtime.run_once();
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[derive(Default)]
+struct BarrierState {
+    arrived: usize,
+    generation: u64,
+}
+
+/// Barrera reutilizable para `parties` hilos
+pub struct MyBarrier {
+    parties: usize,
+    state: Mutex<BarrierState>,
+    waiters: Mutex<Vec<ThreadId>>,
+}
+
+/// Crea una barrera para `parties` hilos. EINVAL si `parties` es 0.
+pub fn my_barrier_init(parties: usize) -> MyResult<MyBarrier> {
+    if parties == 0 {
+        return Err(MyThreadError::InvalidArgument("una barrera necesita al menos un hilo"));
+    }
+    Ok(MyBarrier {
+        parties,
+        state: Mutex::new(BarrierState::default()),
+        waiters: Mutex::new(Vec::new()),
+    })
+}
+
+/// Espera a que lleguen los `parties` hilos. Retorna true en exactamente uno
+/// de ellos (el "serial thread", el último en llegar) y la barrera queda lista
+/// para la siguiente ronda.
+pub fn my_barrier_wait(barrier: &MyBarrier) -> MyResult<bool> {
+    let generation = {
+        let mut state = barrier.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.arrived += 1;
+        if state.arrived == barrier.parties {
+            state.arrived = 0;
+            state.generation += 1;
+            drop(state);
+            wake_all(&barrier.waiters);
+            return Ok(true);
+        }
+        state.generation
+    };
+
+    let passed = || barrier.state.lock().unwrap_or_else(|e| e.into_inner()).generation != generation;
+    if let Err(e) = wait_until(&barrier.waiters, passed) {
+        // el principal se rinde: ya no cuenta como llegado
+        barrier.state.lock().unwrap_or_else(|e| e.into_inner()).arrived -= 1;
+        return Err(e);
     }
+    Ok(false)
+}
+
+/// Contador de tareas pendientes al estilo de `sync.WaitGroup` de Go
+#[derive(Default)]
+pub struct MyWaitGroup {
+    count: Mutex<usize>,
+    waiters: Mutex<Vec<ThreadId>>,
 }
 
-/// Intenta adquirir el lock sin bloquearse.
-pub fn my_mutex_trylock(mtx: &MyMutex) -> bool {
-    !mtx.locked.swap(true, Ordering::Acquire)
+/// Crea un waitgroup sin tareas pendientes
+pub fn my_waitgroup_init() -> MyResult<MyWaitGroup> {
+    Ok(MyWaitGroup::default())
 }
 
-/// Libera el lock.
-pub fn my_mutex_unlock(mtx: &MyMutex) -> ThreadSignal {
-    mtx.locked.store(false, Ordering::Release);
-    ThreadSignal::Continue
+/// Suma `n` tareas pendientes
+pub fn my_waitgroup_add(wg: &MyWaitGroup, n: usize) -> MyResult<()> {
+    *wg.count.lock().unwrap_or_else(|e| e.into_inner()) += n;
+    Ok(())
 }
 
-/// Destruye el mutex (no hace nada porque no hay recursos dinámicos)
-pub fn my_mutex_destroy(_mtx: &mut MyMutex) {}
+/// Marca una tarea como terminada y despierta a los que esperan si era la
+/// última. EINVAL si no había tareas pendientes.
+pub fn my_waitgroup_done(wg: &MyWaitGroup) -> MyResult<()> {
+    let mut count = wg.count.lock().unwrap_or_else(|e| e.into_inner());
+    if *count == 0 {
+        return Err(MyThreadError::InvalidArgument("done sin tareas pendientes"));
+    }
+    *count -= 1;
+    let finished = *count == 0;
+    drop(count);
+    if finished {
+        wake_all(&wg.waiters);
+    }
+    Ok(())
+}
+
+/// Espera a que no queden tareas pendientes. Desde el hilo principal corre
+/// el runtime mientras tanto (EDEADLK si no queda nada que correr).
+pub fn my_waitgroup_wait(wg: &MyWaitGroup) -> MyResult<()> {
+    wait_until(&wg.waiters, || *wg.count.lock().unwrap_or_else(|e| e.into_inner()) == 0)
+}
+
+const ONCE_NEW: u8 = 0;
+const ONCE_RUNNING: u8 = 1;
+const ONCE_DONE: u8 = 2;
+
+/// Inicialización que corre una sola vez (`pthread_once`)
+pub struct MyOnce {
+    state: AtomicU8,
+    runner: AtomicU32,
+    waiters: Mutex<Vec<ThreadId>>,
+}
+
+impl MyOnce {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(ONCE_NEW),
+            runner: AtomicU32::new(UNLOCKED),
+            waiters: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for MyOnce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// si `init` entra en panico, deja el once como nuevo y despierta a los que
+/// esperaban para que alguno lo vuelva a intentar
+struct OnceReset<'a>(&'a MyOnce);
+
+impl Drop for OnceReset<'_> {
+    fn drop(&mut self) {
+        self.0.runner.store(UNLOCKED, Ordering::Relaxed);
+        self.0.state.store(ONCE_NEW, Ordering::Release);
+        wake_all(&self.0.waiters);
+    }
+}
+
+/// Corre `init` la primera vez; los demás que llamen mientras corre se
+/// bloquean hasta que termine. Si `init` entra en panico no cuenta como
+/// hecho: el siguiente que llame lo corre de nuevo. EDEADLK si `init` vuelve
+/// a llamar al mismo once.
+pub fn my_call_once(once: &MyOnce, init: impl FnOnce()) -> MyResult<()> {
+    let me = api_context::try_current_tid().unwrap_or(0);
+    loop {
+        match once.state.compare_exchange(ONCE_NEW, ONCE_RUNNING, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => {
+                once.runner.store(me, Ordering::Relaxed);
+                let reset = OnceReset(once);
+                init();
+                std::mem::forget(reset);
+                once.state.store(ONCE_DONE, Ordering::Release);
+                wake_all(&once.waiters);
+                return Ok(());
+            }
+            Err(ONCE_DONE) => return Ok(()),
+            Err(_) if once.runner.load(Ordering::Relaxed) == me => return Err(MyThreadError::Deadlock),
+            // termino o se cayo: se vuelve a mirar el estado
+            Err(_) => wait_until(&once.waiters, || once.state.load(Ordering::Acquire) != ONCE_RUNNING)?,
+        }
+    }
+}
+
+// --- E/S ---
+// Los fds deben ser no bloqueantes. Cuando la llamada daria EAGAIN el hilo se
+// estaciona en el reactor y el scheduler lo despierta cuando el fd esta listo.
+
+/// Repite `op` hasta que no de EAGAIN. Desde el principal, que no puede
+/// estacionarse, el EAGAIN se devuelve como `Io(EAGAIN)`.
+fn retry_io(fd: RawFd, interest: Interest, mut op: impl FnMut() -> isize) -> MyResult<usize> {
+    loop {
+        let n = op();
+        if n >= 0 {
+            return Ok(n as usize);
+        }
+        let errno = io::Error::last_os_error().raw_os_error().unwrap_or(libc::EIO);
+        match errno {
+            libc::EINTR => continue,
+            libc::EAGAIN if api_context::try_current_tid().is_some() => {}
+            _ => return Err(MyThreadError::Io(errno)),
+        }
+        api_context::ctx_wait_fd(fd, interest)
+            .map_err(|e| MyThreadError::Io(e.raw_os_error().unwrap_or(libc::EIO)))?;
+    }
+}
+
+/// Lee de `fd` al estilo de read(2); 0 es fin de archivo
+pub fn my_read(fd: RawFd, buf: &mut [u8]) -> MyResult<usize> {
+    retry_io(fd, Interest::Readable, || unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) })
+}
+
+/// Escribe en `fd` al estilo de write(2); puede escribir menos que `buf`
+pub fn my_write(fd: RawFd, buf: &[u8]) -> MyResult<usize> {
+    retry_io(fd, Interest::Writable, || unsafe { libc::write(fd, buf.as_ptr().cast(), buf.len()) })
+}
+
+/// Acepta una conexion del socket `fd`; la conexion nueva ya es no bloqueante
+pub fn my_accept(fd: RawFd) -> MyResult<RawFd> {
+    let flags = libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC;
+    let conn = retry_io(fd, Interest::Readable, || unsafe {
+        libc::accept4(fd, std::ptr::null_mut(), std::ptr::null_mut(), flags) as isize
+    })?;
+    Ok(conn as RawFd)
+}
+
+// --- TAREAS BLOQUEANTES ---
+
+/// Corre `f` en el pool de hilos del sistema y devuelve su resultado. El hilo
+/// verde queda estacionado en el reactor mientras tanto, asi el runtime sigue
+/// corriendo a los demas. Desde el principal simplemente espera el resultado.
+/// Si `f` entra en panico se devuelve `Panicked`.
+pub fn my_spawn_blocking<T, F>(f: F) -> MyResult<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+    if fd < 0 {
+        return Err(MyThreadError::Io(io::Error::last_os_error().raw_os_error().unwrap_or(libc::EIO)));
+    }
+    // el eventfd es de los dos: si cancelan al hilo verde el pool igual puede avisar
+    let done = Arc::new(unsafe { OwnedFd::from_raw_fd(fd) });
+    let (sender, receiver) = mpsc::sync_channel(1);
+
+    let notify = done.clone();
+    blocking::submit(Box::new(move || {
+        let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        let one = 1u64.to_ne_bytes();
+        unsafe { libc::write(notify.as_raw_fd(), one.as_ptr().cast(), one.len()) };
+    }));
+
+    if api_context::try_current_tid().is_some() {
+        let mut count = [0u8; 8];
+        my_read(done.as_raw_fd(), &mut count)?;
+    }
+    match receiver.recv() {
+        Ok(Ok(value)) => Ok(value),
+        _ => Err(MyThreadError::Panicked),
+    }
+}
+
+// --- ASYNC ---
+
+/// Corre un future hasta que termine (ver `executor::block_on`). Dentro de un
+/// hilo verde solo se suspende ese hilo mientras el future esta pendiente.
+pub fn my_block_on<F: Future>(future: F) -> F::Output {
+    executor::block_on(future)
+}
+
+/// Crea un hilo verde que corre `future`; termina cuando el future termina.
+pub fn my_thread_spawn_future<F>(name: &str, params: SchedulerParams, future: F) -> MyResult<ThreadId>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut future = Some(future);
+    my_thread_create(
+        name,
+        params,
+        Box::new(move |_, _| {
+            if let Some(future) = future.take() {
+                executor::block_on(future);
+            }
+            ThreadSignal::Exit
+        }),
+    )
+}
+
+// --- ALMACENAMIENTO LOCAL POR HILO ---
+
+fn check_key(key: MyKey) -> MyResult<()> {
+    if tls::key_exists(key) {
+        Ok(())
+    } else {
+        Err(MyThreadError::InvalidArgument("llave inexistente"))
+    }
+}
+
+/// Crea una llave de almacenamiento local; cada hilo verde tiene su propio valor.
+/// El destructor recibe el valor de cada hilo que termina con uno asignado.
+pub fn my_key_create(destructor: Option<KeyDestructor>) -> MyResult<MyKey> {
+    Ok(tls::key_create(destructor))
+}
+
+/// Borra la llave. Los valores que quedaban no pasan por el destructor.
+pub fn my_key_delete(key: MyKey) -> MyResult<()> {
+    check_key(key)?;
+    tls::key_delete(key);
+    Ok(())
+}
+
+/// Asigna el valor de la llave para el hilo que está corriendo.
+pub fn my_setspecific<T: Any + Send>(key: MyKey, value: T) -> MyResult<()> {
+    check_key(key)?;
+    tls::set_specific(key, value);
+    Ok(())
+}
+
+/// Devuelve una copia del valor de la llave para el hilo que está corriendo
+/// (None si no tiene uno o es de otro tipo).
+pub fn my_getspecific<T: Any + Clone>(key: MyKey) -> MyResult<Option<T>> {
+    check_key(key)?;
+    Ok(tls::get_specific(key))
+}
@@ -0,0 +1,79 @@
+//! observadores de eventos del runtime
+//!
+//! Cualquier `FnMut(u64, &RuntimeEvent) + Send` sirve como observador; el
+//! primer argumento es el reloj simulado (`now_ms`) en que ocurrio el evento.
+
+use crate::stats::BlockReason;
+use crate::thread::{SchedulerType, ThreadId};
+use crate::watchdog::WatchdogFlag;
+
+/// Identificador devuelto al registrar un observador
+pub type ObserverId = u32;
+
+/// Eventos que el runtime notifica a los observadores
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeEvent {
+    Spawn { tid: ThreadId, name: String },
+    Dispatch { tid: ThreadId },
+    /// el hilo cedio (o se reencolo) tras usar `used_ms` del quantum
+    Yield { tid: ThreadId, used_ms: u64 },
+    Block { tid: ThreadId, reason: BlockReason },
+    Unblock { tid: ThreadId },
+    Exit { tid: ThreadId },
+    SchedChange {
+        tid: ThreadId,
+        sched_type: SchedulerType,
+        tickets: u32,
+        deadline: Option<u64>,
+    },
+    /// se despacho un hilo de tiempo real despues de su deadline
+    DeadlineMiss { tid: ThreadId, deadline: u64 },
+    /// el watchdog marco al hilo (ver `watchdog.rs`)
+    Watchdog { tid: ThreadId, flag: WatchdogFlag },
+}
+
+pub trait RuntimeObserver: Send {
+    fn on_event(&mut self, now_ms: u64, event: &RuntimeEvent);
+}
+
+impl<F> RuntimeObserver for F
+where
+    F: FnMut(u64, &RuntimeEvent) + Send,
+{
+    fn on_event(&mut self, now_ms: u64, event: &RuntimeEvent) {
+        self(now_ms, event)
+    }
+}
+
+/// Observadores registrados en un runtime
+#[derive(Default)]
+pub struct ObserverList {
+    next_id: ObserverId,
+    observers: Vec<(ObserverId, Box<dyn RuntimeObserver>)>,
+}
+
+impl ObserverList {
+    pub fn add(&mut self, observer: Box<dyn RuntimeObserver>) -> ObserverId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.observers.push((id, observer));
+        id
+    }
+
+    /// retorna false si no habia un observador con ese id
+    pub fn remove(&mut self, id: ObserverId) -> bool {
+        let before = self.observers.len();
+        self.observers.retain(|(oid, _)| *oid != id);
+        self.observers.len() != before
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub fn notify(&mut self, now_ms: u64, event: RuntimeEvent) {
+        for (_, observer) in &mut self.observers {
+            observer.on_event(now_ms, &event);
+        }
+    }
+}
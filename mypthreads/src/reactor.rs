@@ -0,0 +1,181 @@
+//! reactor de E/S sobre epoll: los hilos verdes esperan a que un fd este listo
+//! sin bloquear al runtime entero
+
+use crate::thread::ThreadId;
+use std::collections::HashMap;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+/// cuanto espera el scheduler por E/S cuando no le queda ningun hilo listo
+pub const IO_POLL_TIMEOUT_MS: i32 = 50;
+
+/// eventos que se atienden por cada llamada a epoll_wait
+const MAX_EVENTS: usize = 64;
+
+/// para que esta esperando un hilo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interest {
+    Readable,
+    Writable,
+}
+
+/// hilos estacionados en un mismo fd
+#[derive(Default)]
+struct FdWaiters {
+    readers: Vec<ThreadId>,
+    writers: Vec<ThreadId>,
+}
+
+impl FdWaiters {
+    fn list_mut(&mut self, interest: Interest) -> &mut Vec<ThreadId> {
+        match interest {
+            Interest::Readable => &mut self.readers,
+            Interest::Writable => &mut self.writers,
+        }
+    }
+
+    fn events(&self) -> u32 {
+        let mut events = 0;
+        if !self.readers.is_empty() {
+            events |= libc::EPOLLIN as u32;
+        }
+        if !self.writers.is_empty() {
+            events |= libc::EPOLLOUT as u32;
+        }
+        events
+    }
+}
+
+/// Registro de hilos esperando E/S. El epoll se crea con el primer registro,
+/// asi un runtime que nunca hace E/S no abre ningun fd.
+#[derive(Default)]
+pub struct Reactor {
+    epoll: Option<OwnedFd>,
+    waiters: HashMap<RawFd, FdWaiters>,
+}
+
+impl Reactor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// true si algun hilo esta esperando un fd
+    pub fn has_waiters(&self) -> bool {
+        !self.waiters.is_empty()
+    }
+
+    /// true si `tid` esta esperando algun fd
+    pub fn is_waiting(&self, tid: ThreadId) -> bool {
+        self.waiters.values().any(|entry| entry.readers.contains(&tid) || entry.writers.contains(&tid))
+    }
+
+    /// anota a `tid` para despertarlo cuando `fd` este listo
+    pub fn register(&mut self, fd: RawFd, interest: Interest, tid: ThreadId) -> io::Result<()> {
+        let epfd = self.epoll_fd()?;
+        let known = self.waiters.contains_key(&fd);
+        let mut entry = self.waiters.remove(&fd).unwrap_or_default();
+        let added = !entry.list_mut(interest).contains(&tid);
+        if added {
+            entry.list_mut(interest).push(tid);
+        }
+
+        let mut result = ctl(epfd, if known { libc::EPOLL_CTL_MOD } else { libc::EPOLL_CTL_ADD }, fd, entry.events());
+        // el fd se cerro y el numero se reutilizo: epoll ya lo habia olvidado
+        if known && result.as_ref().is_err_and(|e| e.raw_os_error() == Some(libc::ENOENT)) {
+            result = ctl(epfd, libc::EPOLL_CTL_ADD, fd, entry.events());
+        }
+        if result.is_err() && added {
+            entry.list_mut(interest).pop();
+        }
+        if result.is_ok() || known {
+            self.waiters.insert(fd, entry);
+        }
+        result
+    }
+
+    /// quita a `tid` de la espera de `fd` (ya se desperto o dejo de esperar)
+    pub fn remove(&mut self, fd: RawFd, tid: ThreadId) {
+        if let Some(entry) = self.waiters.get_mut(&fd) {
+            entry.readers.retain(|&id| id != tid);
+            entry.writers.retain(|&id| id != tid);
+            self.rearm(fd);
+        }
+    }
+
+    /// quita a `tid` de todos los fds (p. ej. al cancelarlo)
+    pub fn remove_thread(&mut self, tid: ThreadId) {
+        let fds: Vec<RawFd> = self.waiters.keys().copied().collect();
+        for fd in fds {
+            self.remove(fd, tid);
+        }
+    }
+
+    /// Espera hasta `timeout_ms` (0 = solo revisar) a que algun fd este listo y
+    /// devuelve los hilos que hay que despertar. Un error o un cierre del otro
+    /// lado despierta a todos los del fd: la proxima lectura o escritura lo reporta.
+    pub fn poll(&mut self, timeout_ms: i32) -> io::Result<Vec<ThreadId>> {
+        let Some(epoll) = self.epoll.as_ref().filter(|_| self.has_waiters()) else {
+            return Ok(Vec::new());
+        };
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; MAX_EVENTS];
+        let n = unsafe { libc::epoll_wait(epoll.as_raw_fd(), events.as_mut_ptr(), MAX_EVENTS as i32, timeout_ms) };
+        if n < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::Interrupted => Ok(Vec::new()),
+                _ => Err(err),
+            };
+        }
+
+        let mut woken = Vec::new();
+        for event in &events[..n as usize] {
+            let (ready, fd) = (event.events, event.u64 as RawFd);
+            let Some(entry) = self.waiters.get_mut(&fd) else {
+                continue;
+            };
+            let failed = ready & (libc::EPOLLERR | libc::EPOLLHUP) as u32 != 0;
+            if failed || ready & libc::EPOLLIN as u32 != 0 {
+                woken.append(&mut entry.readers);
+            }
+            if failed || ready & libc::EPOLLOUT as u32 != 0 {
+                woken.append(&mut entry.writers);
+            }
+            self.rearm(fd);
+        }
+        Ok(woken)
+    }
+
+    /// ajusta lo que epoll vigila del fd, o lo suelta si ya nadie lo espera
+    fn rearm(&mut self, fd: RawFd) {
+        let (Some(epoll), Some(entry)) = (self.epoll.as_ref(), self.waiters.get(&fd)) else {
+            return;
+        };
+        let events = entry.events();
+        if events == 0 {
+            self.waiters.remove(&fd);
+            // si el fd ya se cerro epoll lo solto solo
+            let _ = ctl(epoll.as_raw_fd(), libc::EPOLL_CTL_DEL, fd, 0);
+        } else {
+            let _ = ctl(epoll.as_raw_fd(), libc::EPOLL_CTL_MOD, fd, events);
+        }
+    }
+
+    fn epoll_fd(&mut self) -> io::Result<RawFd> {
+        if self.epoll.is_none() {
+            let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            self.epoll = Some(unsafe { OwnedFd::from_raw_fd(fd) });
+        }
+        Ok(self.epoll.as_ref().unwrap().as_raw_fd())
+    }
+}
+
+fn ctl(epfd: RawFd, op: i32, fd: RawFd, events: u32) -> io::Result<()> {
+    let mut event = libc::epoll_event { events, u64: fd as u64 };
+    if unsafe { libc::epoll_ctl(epfd, op, fd, &mut event) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+//! cola para hablarle al runtime desde otros hilos del sistema (p. ej. la UI)
+//!
+//! El runtime no es `Sync`: un `RuntimeHandle` solo encola pedidos y el
+//! runtime los aplica al principio de cada ciclo, desde su propio hilo.
+
+use crate::error::MyResult;
+use crate::mypthreads_api::SchedulerParams;
+use crate::thread::{ContextThreadEntry, ThreadId};
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// mensaje para un hilo verde; el que lo recibe hace `downcast`
+pub type RemoteMessage = Box<dyn Any + Send>;
+
+/// pedido pendiente de aplicar en el runtime
+pub(crate) enum RemoteCommand {
+    Spawn {
+        tid: ThreadId,
+        name: String,
+        params: SchedulerParams,
+        entry: ContextThreadEntry,
+    },
+    ChangeSched {
+        tid: ThreadId,
+        params: SchedulerParams,
+    },
+    /// llego un mensaje para un hilo que estaba esperando uno
+    Wake(ThreadId),
+}
+
+#[derive(Default)]
+struct Mailbox {
+    messages: VecDeque<RemoteMessage>,
+    waiting: bool,
+}
+
+/// Estado compartido entre el runtime, sus hilos y los handles
+pub(crate) struct RemoteQueue {
+    /// los tids se reparten aqui para que un handle pueda devolverlo de una vez
+    next_tid: AtomicU32,
+    commands: Mutex<VecDeque<RemoteCommand>>,
+    mailboxes: Mutex<HashMap<ThreadId, Mailbox>>,
+}
+
+impl RemoteQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            next_tid: AtomicU32::new(1),
+            commands: Mutex::new(VecDeque::new()),
+            mailboxes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn next_tid(&self) -> ThreadId {
+        self.next_tid.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn commands(&self) -> MutexGuard<'_, VecDeque<RemoteCommand>> {
+        self.commands.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn mailboxes(&self) -> MutexGuard<'_, HashMap<ThreadId, Mailbox>> {
+        self.mailboxes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// saca los pedidos pendientes, en el orden en que llegaron
+    pub(crate) fn take_commands(&self) -> VecDeque<RemoteCommand> {
+        std::mem::take(&mut *self.commands())
+    }
+
+    /// Saca el siguiente mensaje de `tid`. Si no hay, lo marca como esperando
+    /// para que el proximo `send` pida despertarlo.
+    pub(crate) fn recv(&self, tid: ThreadId, wait: bool) -> Option<RemoteMessage> {
+        let mut mailboxes = self.mailboxes();
+        let mailbox = mailboxes.entry(tid).or_default();
+        let message = mailbox.messages.pop_front();
+        mailbox.waiting = wait && message.is_none();
+        message
+    }
+
+    /// pide despertar a `tid` en el proximo ciclo; sirve desde cualquier hilo
+    pub(crate) fn request_wake(&self, tid: ThreadId) {
+        self.commands().push_back(RemoteCommand::Wake(tid));
+    }
+
+    /// descarta el buzon de un hilo que ya se libero
+    pub(crate) fn forget(&self, tid: ThreadId) {
+        self.mailboxes().remove(&tid);
+    }
+}
+
+/// Handle `Send + Sync` para crear hilos, cambiar su planificacion o mandarles
+/// mensajes desde otro hilo del sistema mientras el runtime corre.
+#[derive(Clone)]
+pub struct RuntimeHandle {
+    remote: Arc<RemoteQueue>,
+}
+
+impl RuntimeHandle {
+    pub(crate) fn new(remote: Arc<RemoteQueue>) -> Self {
+        Self { remote }
+    }
+
+    /// Pide crear un hilo; se crea al empezar el siguiente ciclo del runtime.
+    /// EINVAL con 0 tiquetes; si la moneda no existe el runtime lo avisa en el log.
+    pub fn spawn_remote(&self, name: &str, params: SchedulerParams, entry: ContextThreadEntry) -> MyResult<ThreadId> {
+        params.validate()?;
+        let tid = self.remote.next_tid();
+        self.remote.commands().push_back(RemoteCommand::Spawn {
+            tid,
+            name: name.to_string(),
+            params,
+            entry,
+        });
+        Ok(tid)
+    }
+
+    /// Pide cambiar la planificacion de `tid` (como `my_thread_chsched`)
+    pub fn chsched_remote(&self, tid: ThreadId, params: SchedulerParams) -> MyResult<()> {
+        params.validate()?;
+        self.remote.commands().push_back(RemoteCommand::ChangeSched { tid, params });
+        Ok(())
+    }
+
+    /// Deja `message` en el buzon de `tid` (ver `my_remote_recv`)
+    pub fn send(&self, tid: ThreadId, message: impl Any + Send) {
+        let mut mailboxes = self.remote.mailboxes();
+        let mailbox = mailboxes.entry(tid).or_default();
+        mailbox.messages.push_back(Box::new(message));
+        if std::mem::take(&mut mailbox.waiting) {
+            self.remote.request_wake(tid);
+        }
+    }
+}
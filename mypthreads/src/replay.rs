@@ -0,0 +1,258 @@
+//! grabacion y reproduccion de las decisiones del scheduler
+//!
+//! En modo grabacion el runtime anota cada hilo despachado (o que no habia
+//! ninguno listo) y el orden en que despierta hilos bloqueados. En modo
+//! reproduccion fuerza exactamente esas decisiones; si el programa se aparta de
+//! la grabacion se guarda la divergencia y el scheduler vuelve a decidir solo.
+//!
+//! Formato del archivo, una decision por linea:
+//! ```text
+//! # mypthreads schedule v1
+//! seed 42
+//! dispatch 3
+//! wake 5
+//! idle
+//! ```
+
+use crate::mp_log;
+use crate::thread::ThreadId;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+const HEADER: &str = "# mypthreads schedule v1";
+
+/// Una decision del scheduler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedDecision {
+    /// se despacho este hilo
+    Dispatch(ThreadId),
+    /// no habia hilos listos
+    Idle,
+    /// el hilo paso de bloqueado a listo
+    Wake(ThreadId),
+}
+
+impl fmt::Display for SchedDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchedDecision::Dispatch(tid) => write!(f, "dispatch {}", tid),
+            SchedDecision::Idle => f.write_str("idle"),
+            SchedDecision::Wake(tid) => write!(f, "wake {}", tid),
+        }
+    }
+}
+
+/// Secuencia de decisiones de una corrida
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedRecording {
+    /// semilla del runtime cuando empezo la grabacion, si se conocia
+    pub seed: Option<u64>,
+    pub decisions: Vec<SchedDecision>,
+}
+
+impl SchedRecording {
+    pub fn to_text(&self) -> String {
+        let mut out = String::from(HEADER);
+        out.push('\n');
+        if let Some(seed) = self.seed {
+            out.push_str(&format!("seed {}\n", seed));
+        }
+        for d in &self.decisions {
+            out.push_str(&d.to_string());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// lee el formato de `to_text`; el error indica la linea invalida
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut rec = SchedRecording::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let word = parts.next().unwrap_or_default();
+            let arg = parts.next();
+            let invalid = || format!("linea {}: '{}' no es valida", n + 1, line);
+            let tid = || arg.and_then(|a| a.parse::<ThreadId>().ok()).ok_or_else(invalid);
+            match word {
+                "seed" => {
+                    rec.seed = Some(arg.and_then(|a| a.parse().ok()).ok_or_else(invalid)?);
+                }
+                "dispatch" => rec.decisions.push(SchedDecision::Dispatch(tid()?)),
+                "wake" => rec.decisions.push(SchedDecision::Wake(tid()?)),
+                "idle" => rec.decisions.push(SchedDecision::Idle),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(rec)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Punto donde la reproduccion dejo de coincidir con la grabacion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// indice de la decision esperada dentro de la grabacion
+    pub index: usize,
+    pub expected: SchedDecision,
+    /// hilos listos (para dispatch/idle) o despertados (para wake) en ese momento
+    pub found: Vec<ThreadId>,
+}
+
+#[derive(Debug, Default)]
+enum Mode {
+    #[default]
+    Off,
+    Recording(SchedRecording),
+    Replaying {
+        recording: SchedRecording,
+        pos: usize,
+    },
+}
+
+/// Estado de grabacion/reproduccion de un runtime
+#[derive(Debug, Default)]
+pub struct SchedReplay {
+    mode: Mode,
+    divergence: Option<Divergence>,
+}
+
+impl SchedReplay {
+    pub fn start_recording(&mut self, seed: Option<u64>) {
+        self.mode = Mode::Recording(SchedRecording {
+            seed,
+            decisions: Vec::new(),
+        });
+    }
+
+    /// termina la grabacion y la devuelve (None si no se estaba grabando)
+    pub fn stop_recording(&mut self) -> Option<SchedRecording> {
+        match std::mem::take(&mut self.mode) {
+            Mode::Recording(rec) => Some(rec),
+            other => {
+                self.mode = other;
+                None
+            }
+        }
+    }
+
+    pub fn recording(&self) -> Option<&SchedRecording> {
+        match &self.mode {
+            Mode::Recording(rec) => Some(rec),
+            _ => None,
+        }
+    }
+
+    pub fn start_replay(&mut self, recording: SchedRecording) {
+        self.divergence = None;
+        self.mode = Mode::Replaying { recording, pos: 0 };
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.mode, Mode::Replaying { .. })
+    }
+
+    pub fn divergence(&self) -> Option<&Divergence> {
+        self.divergence.as_ref()
+    }
+
+    /// decisiones de la grabacion que faltan por reproducir
+    pub fn remaining(&self) -> usize {
+        match &self.mode {
+            Mode::Replaying { recording, pos } => recording.decisions.len() - pos,
+            _ => 0,
+        }
+    }
+
+    fn diverge(&mut self, index: usize, expected: SchedDecision, found: Vec<ThreadId>) {
+        mp_log!(Warn, "replay", "divergencia en la decision {}: se esperaba '{}', habia {:?}", index, expected, found);
+        self.divergence = Some(Divergence { index, expected, found });
+        self.mode = Mode::Off;
+    }
+
+    /// siguiente decision a reproducir; al terminar la grabacion vuelve a modo normal
+    fn peek(&mut self) -> Option<(usize, SchedDecision)> {
+        let Mode::Replaying { recording, pos } = &self.mode else {
+            return None;
+        };
+        match recording.decisions.get(*pos) {
+            Some(&d) => Some((*pos, d)),
+            None => {
+                self.mode = Mode::Off;
+                None
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        if let Mode::Replaying { pos, .. } = &mut self.mode {
+            *pos += 1;
+        }
+    }
+
+    /// En reproduccion devuelve el despacho forzado (`Some(None)` = idle).
+    /// `None` significa que decide el scheduler.
+    pub(crate) fn forced_dispatch(&mut self, ready: &VecDeque<ThreadId>) -> Option<Option<ThreadId>> {
+        let (index, expected) = self.peek()?;
+        let forced = match expected {
+            SchedDecision::Dispatch(tid) if ready.contains(&tid) => Some(tid),
+            SchedDecision::Idle if ready.is_empty() => None,
+            _ => {
+                self.diverge(index, expected, ready.iter().copied().collect());
+                return None;
+            }
+        };
+        self.advance();
+        Some(forced)
+    }
+
+    /// anota el despacho si se esta grabando
+    pub(crate) fn record_dispatch(&mut self, tid: Option<ThreadId>) {
+        if let Mode::Recording(rec) = &mut self.mode {
+            rec.decisions
+                .push(tid.map_or(SchedDecision::Idle, SchedDecision::Dispatch));
+        }
+    }
+
+    /// Orden en que se despiertan `woken`: el grabado si se reproduce,
+    /// el recibido (y anotado) si se graba.
+    pub(crate) fn order_wakes(&mut self, mut woken: Vec<ThreadId>) -> Vec<ThreadId> {
+        if let Mode::Recording(rec) = &mut self.mode {
+            rec.decisions.extend(woken.iter().map(|&t| SchedDecision::Wake(t)));
+            return woken;
+        }
+
+        let mut ordered = Vec::with_capacity(woken.len());
+        while !woken.is_empty() {
+            let Some((index, expected)) = self.peek() else {
+                break;
+            };
+            match expected {
+                SchedDecision::Wake(tid) if woken.contains(&tid) => {
+                    woken.retain(|&t| t != tid);
+                    ordered.push(tid);
+                    self.advance();
+                }
+                _ => {
+                    self.diverge(index, expected, woken.clone());
+                    break;
+                }
+            }
+        }
+        ordered.extend(woken);
+        ordered
+    }
+}
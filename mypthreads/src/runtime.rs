@@ -1,36 +1,111 @@
-use crate::channels::ThreadChannels;
+use crate::channels::{ThreadChannels, UNLOCKED};
 use crate::context_wrapper::ThreadContext;
+use crate::error::{MyResult, MyThreadError};
+use crate::group::{GroupId, GroupRegistry};
+use crate::lottery::{CurrencyId, LotteryLedger};
+use crate::observer::{ObserverId, ObserverList, RuntimeEvent, RuntimeObserver};
+use crate::reactor::IO_POLL_TIMEOUT_MS;
+use crate::remote::{RemoteCommand, RuntimeHandle};
+use crate::replay::{SchedRecording, SchedReplay};
+use crate::mp_log;
 use crate::sched;
+use crate::snapshot::{RuntimeSnapshot, ThreadSnapshot, WaitReason};
+use crate::stats::{self, BlockReason, StackUsage, StatsSummary, ThreadStats, MAX_STACK_CLASSES};
 use crate::thread::{ContextThreadEntry, MyThread, SchedulerType, ThreadId, ThreadState};
 use crate::thread_data::{ThreadResponse, TransferMessage};
+use crate::tls;
+use crate::trace::{SchedTracer, TraceEventKind};
+use crate::watchdog::Watchdog;
 use crate::SimpleMutex;
-use std::collections::{HashMap, VecDeque};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::cell::UnsafeCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::u64;
 
+/// duracion simulada de cada despacho (quantum)
+pub const QUANTUM_MS: u64 = 10;
 
 pub struct ThreadRuntimeV2 {
     now_ms: u64,
-    next_tid: ThreadId,
     pub threads: HashMap<ThreadId, Box<MyThread>>,
     pub ready: VecDeque<ThreadId>,
     pub blocked: Vec<ThreadId>,
     pub runtime_context: ThreadContext,
     pub channels: ThreadChannels,
+    pub lottery: LotteryLedger,
+    pub groups: GroupRegistry,
+    /// cola del mutex en la que espera cada hilo bloqueado en un lock (para cancelarlo)
+    mutex_waits: HashMap<ThreadId, Arc<UnsafeCell<VecDeque<ThreadId>>>>,
+    /// generador de los sorteos, sembrado para poder repetir una corrida
+    rng: StdRng,
+    seed: Option<u64>,
+    /// registro de eventos para exportar en formato Chrome trace
+    pub tracer: SchedTracer,
+    observers: ObserverList,
+    /// grabacion o reproduccion de las decisiones del scheduler
+    pub replay: SchedReplay,
+    /// uso de pila de los hilos que ya terminaron, por clase
+    stack_usage: BTreeMap<String, StackUsage>,
+    /// estadisticas sumadas de los hilos ya liberados (`reap`)
+    reaped: StatsSummary,
+    /// cuantas clases distintas guarda `stack_usage` (ver `OTHER_STACK_CLASS`)
+    pub max_stack_classes: usize,
+    /// umbrales del watchdog y los hilos que marco
+    pub watchdog: Watchdog,
 }
 
 impl ThreadRuntimeV2 {
     pub fn new() -> Self {
+        Self::with_rng(StdRng::from_os_rng(), None)
+    }
+
+    /// crea un runtime cuyos sorteos son reproducibles con la misma semilla
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed), Some(seed))
+    }
+
+    fn with_rng(rng: StdRng, seed: Option<u64>) -> Self {
         Self {
             now_ms: 0,
-            next_tid: 1,
             threads: HashMap::new(),
             ready: VecDeque::new(),
             blocked: Vec::new(),
             runtime_context: ThreadContext::new_runtime(),
             channels: ThreadChannels::new(),
+            lottery: LotteryLedger::new(),
+            groups: GroupRegistry::new(),
+            mutex_waits: HashMap::new(),
+            rng,
+            seed,
+            tracer: SchedTracer::new(),
+            observers: ObserverList::default(),
+            replay: SchedReplay::default(),
+            stack_usage: BTreeMap::new(),
+            reaped: StatsSummary::default(),
+            max_stack_classes: MAX_STACK_CLASSES,
+            watchdog: Watchdog::new(),
         }
     }
 
+    /// vuelve a sembrar el generador de los sorteos
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.seed = Some(seed);
+    }
+
+    /// empieza a grabar las decisiones del scheduler (incluye la semilla si se conoce)
+    pub fn start_recording(&mut self) {
+        self.replay.start_recording(self.seed);
+    }
+
+    /// fuerza las decisiones de una grabacion; la semilla no se cambia aqui
+    pub fn start_replay(&mut self, recording: SchedRecording) {
+        self.replay.start_replay(recording);
+    }
+
     /// crea un nuevo hilo v2
     pub fn spawn(
         &mut self,
@@ -40,10 +115,32 @@ impl ThreadRuntimeV2 {
         tickets: u32,
         deadline: Option<u64>,
     ) -> ThreadId {
-        let tid = self.next_tid;
-        self.next_tid += 1;
+        let tid = self.reserve_tid();
+        self.spawn_with_tid(tid, name, sched, entry, tickets, deadline);
+        tid
+    }
 
-        let thread = MyThread::new(tid, name.into(), sched, tickets, deadline, entry);
+    /// tid para el proximo hilo, para quien necesita saberlo antes de crearlo
+    pub(crate) fn reserve_tid(&self) -> ThreadId {
+        self.channels.remote().next_tid()
+    }
+
+    pub(crate) fn spawn_with_tid(
+        &mut self,
+        tid: ThreadId,
+        name: impl Into<String>,
+        sched: SchedulerType,
+        entry: ContextThreadEntry,
+        tickets: u32,
+        deadline: Option<u64>,
+    ) {
+        let mut thread = MyThread::new(tid, name.into(), sched, tickets, deadline, entry);
+        thread.stats.on_ready(self.now_ms);
+        self.tracer.name_thread(tid, &thread.name);
+        self.notify(RuntimeEvent::Spawn {
+            tid,
+            name: thread.name.clone(),
+        });
 
         self.threads.insert(tid, Box::new(thread));
         self.ready.push_back(tid);
@@ -53,23 +150,81 @@ impl ThreadRuntimeV2 {
         //    tid,
         //    self.threads.len()
         //);
+    }
 
-        tid
+    /// handle para hablarle a este runtime desde otros hilos del sistema
+    pub fn handle(&self) -> RuntimeHandle {
+        RuntimeHandle::new(self.channels.remote().clone())
+    }
+
+    /// aplica lo que encolaron los `RuntimeHandle` desde el ciclo anterior
+    fn apply_remote(&mut self) {
+        for command in self.channels.remote().take_commands() {
+            match command {
+                RemoteCommand::Spawn { tid, name, params, entry } => match params.resolve(self) {
+                    Ok((sched, tickets, deadline, currency)) => {
+                        self.spawn_with_tid(tid, name, sched, entry, tickets, deadline);
+                        self.set_currency(tid, currency);
+                    }
+                    Err(e) => mp_log!(Warn, "runtime", "no se pudo crear el hilo remoto {}: {}", tid, e),
+                },
+                RemoteCommand::ChangeSched { tid, params } => match params.resolve(self) {
+                    Ok((sched, tickets, deadline, currency)) => {
+                        self.set_sched_params(tid, sched, tickets, deadline, currency)
+                    }
+                    Err(e) => mp_log!(Warn, "runtime", "no se pudo cambiar el hilo remoto {}: {}", tid, e),
+                },
+                RemoteCommand::Wake(tid) => self.unblock_thread(tid),
+            }
+        }
     }
 
     //pasar de un hilo bloqueado a listo
     pub fn unblock_thread(&mut self, tid: ThreadId) {
         if let Some(pos) = self.blocked.iter().position(|&id| id == tid) {
             let unblocked_tid = self.blocked.remove(pos);
-            self.threads.get_mut(&unblocked_tid).unwrap().state = ThreadState::Ready;
+            self.replay.order_wakes(vec![unblocked_tid]);
+            let thread = self.threads.get_mut(&unblocked_tid).unwrap();
+            thread.state = ThreadState::Ready;
+            thread.lending_to = None;
+            thread.stats.on_ready(self.now_ms);
             self.ready.push_back(unblocked_tid);
+            self.tracer.record(unblocked_tid, TraceEventKind::Unblock, self.now_ms);
+            self.notify(RuntimeEvent::Unblock { tid: unblocked_tid });
             //println!("[Runtime] Hilo {} desbloqueado.", unblocked_tid);
         }
     }
 
+    /// Entrega un mutex liberado por `from` a `to`, que lo esperaba: lo
+    /// despierta como dueño y los que siguen en `waiters` le prestan a el.
+    pub(crate) fn hand_off_mutex(&mut self, from: ThreadId, to: ThreadId, waiters: Vec<ThreadId>) {
+        self.mutex_waits.remove(&to);
+        //println!(
+        //    "[Runtime] Mutex liberado, despertando al hilo {}.",
+        //    to
+        //);
+        self.unblock_thread(to);
+        if let Some(thread) = self.threads.get_mut(&to) {
+            thread.stats.mutex_acquisitions += 1;
+        }
+        self.tracer.record(to, TraceEventKind::MutexHandoff { from }, self.now_ms);
+
+        // Los que siguen esperando ahora le prestan al nuevo dueño.
+        for waiter in waiters {
+            if let Some(thread) = self.threads.get_mut(&waiter) {
+                thread.lending_to = Some(to);
+            }
+        }
+    }
+
     //Decide que hilo ejecutar a continuacion
     fn select_next_thread(&mut self) -> Option<ThreadId> {
-        let selected_tid = sched::select_next_thread(&self.ready, &self.threads, self.now_ms);
+        self.lottery.refresh(&mut self.threads);
+        let selected_tid = match self.replay.forced_dispatch(&self.ready) {
+            Some(forced) => forced,
+            None => sched::select_next_thread(&self.ready, &self.threads, self.now_ms, &mut self.rng),
+        };
+        self.replay.record_dispatch(selected_tid);
 
         if let Some(tid) = selected_tid {
             self.ready.retain(|&ready_tid| ready_tid != tid);
@@ -81,10 +236,18 @@ impl ThreadRuntimeV2 {
     /// Mueve TODOS los hilos de la cola de bloqueados a la cola de listos.
     pub fn unblock_all_threads(&mut self) {
         // Tomamos todos los hilos bloqueados y los movemos a la cola de listos.
-        for tid in self.blocked.drain(..) {
+        let woken = self.replay.order_wakes(std::mem::take(&mut self.blocked));
+        for tid in woken {
+            // el que espera E/S vuelve a estacionarse sin avanzar, pero no gira
+            let io_waiter = self.channels.reactor().is_waiting(tid);
             if let Some(thread) = self.threads.get_mut(&tid) {
                 thread.state = ThreadState::Ready;
+                thread.lending_to = None;
+                thread.watch.broadcast_woken = !io_waiter;
+                thread.stats.on_ready(self.now_ms);
                 self.ready.push_back(tid);
+                self.tracer.record(tid, TraceEventKind::Unblock, self.now_ms);
+                self.notify(RuntimeEvent::Unblock { tid });
                 //println!(
                 //    "[Runtime] Hilo {} desbloqueado por el ciclo de simulación.",
                 //    tid
@@ -93,18 +256,418 @@ impl ThreadRuntimeV2 {
         }
     }
 
+    /// crea una moneda de tiquetes respaldada por `funding` tiquetes base
+    pub fn create_currency(&mut self, name: impl Into<String>, funding: u32) -> CurrencyId {
+        self.lottery.create_currency(name, funding)
+    }
+
+    /// cambia el respaldo de una moneda existente
+    pub fn fund_currency(&mut self, currency: CurrencyId, funding: u32) -> bool {
+        self.lottery.fund(currency, funding)
+    }
+
+    /// denomina los tiquetes de un hilo en una moneda (None = tiquetes base)
+    pub fn set_currency(&mut self, tid: ThreadId, currency: Option<CurrencyId>) {
+        if let Some(thread) = self.threads.get_mut(&tid) {
+            thread.currency = currency;
+        }
+    }
+
+    /// cambia tipo de planificador, tiquetes, deadline y moneda de un hilo
+    pub fn set_sched_params(
+        &mut self,
+        tid: ThreadId,
+        sched_type: SchedulerType,
+        tickets: u32,
+        deadline: Option<u64>,
+        currency: Option<CurrencyId>,
+    ) {
+        let Some(thread) = self.threads.get_mut(&tid) else {
+            return;
+        };
+        thread.sched_type = sched_type;
+        thread.tickets = tickets;
+        thread.deadline = deadline;
+        thread.currency = currency;
+        self.notify(RuntimeEvent::SchedChange {
+            tid,
+            sched_type,
+            tickets,
+            deadline,
+        });
+    }
+
+    /// crea un grupo de hilos vacio
+    pub fn create_group(&mut self, name: impl Into<String>) -> GroupId {
+        self.groups.create(name)
+    }
+
+    /// mueve un hilo a un grupo (None = sin grupo); false si el hilo o el grupo no existen
+    pub fn set_group(&mut self, tid: ThreadId, group: Option<GroupId>) -> bool {
+        if group.is_some_and(|g| !self.groups.contains(g)) {
+            return false;
+        }
+        match self.threads.get_mut(&tid) {
+            Some(thread) => {
+                thread.group = group;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// miembros de un grupo ordenados por tid, incluidos los terminados que
+    /// aun no se liberan (de los liberados solo quedan sus estadisticas)
+    pub fn group_members(&self, group: GroupId) -> Vec<ThreadId> {
+        let mut members: Vec<ThreadId> = self
+            .threads
+            .values()
+            .filter(|t| t.group == Some(group))
+            .map(|t| t.id)
+            .collect();
+        members.sort_unstable();
+        members
+    }
+
+    /// miembros de un grupo que todavia no terminaron
+    pub fn group_live_members(&self, group: GroupId) -> Vec<ThreadId> {
+        self.group_members(group)
+            .into_iter()
+            .filter(|tid| self.threads.get(tid).is_some_and(|t| t.state != ThreadState::Terminated))
+            .collect()
+    }
+
+    /// aplica los mismos parametros de planificacion a los miembros vivos; retorna cuantos cambio
+    pub fn set_group_sched_params(
+        &mut self,
+        group: GroupId,
+        sched_type: SchedulerType,
+        tickets: u32,
+        deadline: Option<u64>,
+        currency: Option<CurrencyId>,
+    ) -> usize {
+        let members = self.group_live_members(group);
+        for &tid in &members {
+            self.set_sched_params(tid, sched_type, tickets, deadline, currency);
+        }
+        members.len()
+    }
+
+    /// estadisticas sumadas de los miembros de un grupo, incluidos los ya
+    /// liberados (None si no existe)
+    pub fn group_stats(&self, group: GroupId) -> Option<StatsSummary> {
+        if !self.groups.contains(group) {
+            return None;
+        }
+        let members: Vec<ThreadStats> = self
+            .threads
+            .values()
+            .filter(|t| t.group == Some(group))
+            .map(|t| self.report_stats(t))
+            .collect();
+        let mut summary = StatsSummary::collect(self.now_ms, &members);
+        summary.merge(&self.groups.get(group).unwrap().exited);
+        Some(summary)
+    }
+
+    /// Termina un hilo sin correrlo de nuevo. Si esta corriendo (se cancela a si
+    /// mismo o a su grupo) termina al acabar el paso actual. Los mutex que tenga
+    /// tomados quedan tomados, como en pthreads sin handlers de limpieza.
+    /// Retorna false si no existe o ya termino.
+    pub fn cancel_thread(&mut self, tid: ThreadId) -> bool {
+        let Some(thread) = self.threads.get_mut(&tid) else {
+            return false;
+        };
+        match thread.state {
+            ThreadState::Terminated => return false,
+            ThreadState::Running => {
+                thread.cancel_pending = true;
+                return true;
+            }
+            _ => {}
+        }
+        self.record_stack(tid);
+        let thread = self.threads.get_mut(&tid).unwrap();
+        thread.state = ThreadState::Terminated;
+        thread.cancel_pending = false;
+        thread.lending_to = None;
+        let joiners = std::mem::take(&mut thread.joiners);
+        // sus llaves pasan por los destructores, como si hubiera terminado solo
+        let locals = std::mem::take(&mut thread.locals);
+        if !locals.is_empty() {
+            let previous = tls::swap_active(locals);
+            tls::run_destructors();
+            tls::swap_active(previous);
+        }
+
+        self.ready.retain(|&id| id != tid);
+        self.blocked.retain(|&id| id != tid);
+        if let Some(queue) = self.mutex_waits.remove(&tid) {
+            unsafe { &mut *queue.get() }.retain(|&id| id != tid);
+        }
+        self.channels.reactor().remove_thread(tid);
+        // si estaba esperando un join, el otro hilo ya no lo debe despertar
+        for other in self.threads.values_mut() {
+            other.joiners.retain(|&id| id != tid);
+        }
+
+        self.tracer.record(tid, TraceEventKind::Exit, self.now_ms);
+        self.notify(RuntimeEvent::Exit { tid });
+        for joiner in joiners {
+            self.unblock_thread(joiner);
+        }
+        if self.threads[&tid].detached {
+            self.reap(tid);
+        }
+        true
+    }
+
+    /// Reglas de pthread_join para que `caller` espere a `target`: Ok(true) si
+    /// debe bloquearse, Ok(false) si ya termino.
+    pub fn check_join(&self, caller: ThreadId, target: ThreadId) -> MyResult<bool> {
+        if caller == target {
+            return Err(MyThreadError::Deadlock);
+        }
+        let thread = self.threads.get(&target).ok_or(MyThreadError::NoSuchThread(target))?;
+        if thread.detached {
+            return Err(MyThreadError::InvalidArgument("no se puede hacer join a un hilo detached"));
+        }
+        if thread.joiners.iter().any(|&id| id != caller) {
+            return Err(MyThreadError::InvalidArgument("otro hilo ya espera a este hilo"));
+        }
+        if thread.state == ThreadState::Terminated {
+            return Ok(false);
+        }
+        // el objetivo ya esta esperando al que llama
+        if self.threads.get(&caller).is_some_and(|t| t.joiners.contains(&target)) {
+            return Err(MyThreadError::Deadlock);
+        }
+        Ok(true)
+    }
+
+    /// Libera un hilo terminado (su pila y su registro). Retorna false si no
+    /// existe o todavia no termina.
+    pub fn reap(&mut self, tid: ThreadId) -> bool {
+        if self.threads.get(&tid).is_none_or(|t| t.state != ThreadState::Terminated) {
+            return false;
+        }
+        let thread = self.threads.remove(&tid).unwrap();
+        let stats = thread.measured_stats();
+        self.reaped.add(&stats);
+        if let Some(group) = thread.group {
+            self.groups.record_exit(group, &stats);
+        }
+        self.channels.remote().forget(tid);
+        self.watchdog.forget(tid);
+        true
+    }
+
+    /// cancela todos los miembros vivos de un grupo; retorna cuantos cancelo
+    pub fn cancel_group(&mut self, group: GroupId) -> usize {
+        self.group_live_members(group)
+            .into_iter()
+            .filter(|&tid| self.cancel_thread(tid))
+            .count()
+    }
+
+    /// registra un observador de eventos del runtime
+    pub fn add_observer(&mut self, observer: impl RuntimeObserver + 'static) -> ObserverId {
+        self.observers.add(Box::new(observer))
+    }
+
+    /// quita un observador; false si no estaba registrado
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool {
+        self.observers.remove(id)
+    }
+
+    fn notify(&mut self, event: RuntimeEvent) {
+        self.observers.notify(self.now_ms, event);
+    }
+
+    /// estadisticas de un hilo
+    pub fn thread_stats(&self, tid: ThreadId) -> Option<ThreadStats> {
+        self.threads.get(&tid).map(|t| t.measured_stats())
+    }
+
+    /// totales de todos los hilos, incluidos los ya liberados
+    pub fn stats_summary(&self) -> StatsSummary {
+        let stats: Vec<ThreadStats> = self.threads.values().map(|t| self.report_stats(t)).collect();
+        let mut summary = StatsSummary::collect(self.now_ms, &stats);
+        summary.merge(&self.reaped);
+        summary
+    }
+
+    /// estadisticas de un hilo para los resumenes: un bloqueo que sigue
+    /// abierto cuenta hasta ahora
+    fn report_stats(&self, thread: &MyThread) -> ThreadStats {
+        let stats = thread.measured_stats();
+        if thread.state == ThreadState::Terminated {
+            stats
+        } else {
+            stats.with_open_block(self.now_ms)
+        }
+    }
+
+    /// Uso de pila por clase de hilo (su grupo, o su nombre si no tiene): los
+    /// que ya terminaron (aunque se hayan liberado) mas los vivos, medidos ahora.
+    /// Guarda a lo sumo `max_stack_classes` clases; las demas se suman en
+    /// `OTHER_STACK_CLASS`, asi los nombres unicos no la hacen crecer sin fin.
+    pub fn stack_usage(&self) -> BTreeMap<String, StackUsage> {
+        let mut usage = self.stack_usage.clone();
+        for thread in self.threads.values().filter(|t| t.state != ThreadState::Terminated) {
+            let class = self.stack_class(thread);
+            stats::add_stack_usage(&mut usage, class, &thread.measured_stats(), self.max_stack_classes);
+        }
+        usage
+    }
+
+    fn stack_class<'a>(&'a self, thread: &'a MyThread) -> &'a str {
+        match thread.group.and_then(|g| self.groups.get(g)) {
+            Some(group) => &group.name,
+            None => &thread.name,
+        }
+    }
+
+    /// mide la pila de un hilo que termina y la anota en su clase
+    fn record_stack(&mut self, tid: ThreadId) {
+        let Some(thread) = self.threads.get_mut(&tid) else {
+            return;
+        };
+        thread.stats = thread.measured_stats();
+        let class = self.stack_class(&self.threads[&tid]).to_string();
+        let measured = &self.threads[&tid].stats;
+        stats::add_stack_usage(&mut self.stack_usage, &class, measured, self.max_stack_classes);
+    }
+
+    /// pasa un despacho por el watchdog; solo avisa, no hay como forzar un yield
+    fn watch(&mut self, tid: ThreadId, elapsed: Duration, blocked: bool) {
+        let Some(thread) = self.threads.get_mut(&tid) else {
+            return;
+        };
+        thread.stats.on_dispatch_time(elapsed.as_micros() as u64);
+        let mut flags = Vec::new();
+        if let Some(flag) = self.watchdog.check_dispatch(tid, elapsed) {
+            thread.stats.slow_dispatches += 1;
+            mp_log!(Warn, "watchdog", "hilo {} ({}) corrio {} ms sin ceder", tid, thread.name, elapsed.as_millis());
+            flags.push(flag);
+        }
+        if let Some(flag) = self.watchdog.check_step(tid, &mut thread.watch, blocked) {
+            thread.stats.livelocks += 1;
+            mp_log!(Warn, "watchdog", "hilo {} ({}) parece en livelock: {:?}", tid, thread.name, flag);
+            flags.push(flag);
+        }
+        for flag in flags {
+            self.notify(RuntimeEvent::Watchdog { tid, flag });
+        }
+    }
+
+    /// copia del estado de todos los hilos y de las colas
+    pub fn snapshot(&self) -> RuntimeSnapshot {
+        let mut threads: Vec<ThreadSnapshot> = self
+            .threads
+            .values()
+            .map(|t| ThreadSnapshot {
+                id: t.id,
+                name: t.name.clone(),
+                state: t.state,
+                sched_type: t.sched_type,
+                tickets: t.tickets,
+                effective_tickets: t.effective_tickets,
+                deadline: t.deadline,
+                detached: t.detached,
+                joiners: t.joiners.clone(),
+                wait_reason: self.wait_reason(t),
+            })
+            .collect();
+        threads.sort_by_key(|t| t.id);
+
+        RuntimeSnapshot {
+            now_ms: self.now_ms,
+            ready: self.ready.iter().copied().collect(),
+            blocked: self.blocked.clone(),
+            threads,
+        }
+    }
+
+    fn wait_reason(&self, thread: &MyThread) -> Option<WaitReason> {
+        if thread.state != ThreadState::Blocked {
+            return None;
+        }
+        Some(match thread.stats.blocked_reason()? {
+            BlockReason::Explicit => WaitReason::Explicit,
+            BlockReason::Join => {
+                // el objetivo del join es el hilo que lo tiene en sus joiners
+                let target = self
+                    .threads
+                    .values()
+                    .find(|t| t.state != ThreadState::Terminated && t.joiners.contains(&thread.id))
+                    .map(|t| t.id)?;
+                WaitReason::Join(target)
+            }
+            BlockReason::Mutex => WaitReason::Mutex {
+                owner: thread.lending_to,
+            },
+        })
+    }
+
+    /// true si algun hilo esta bloqueado esperando un fd
+    pub fn has_io_waiters(&self) -> bool {
+        self.channels.reactor().has_waiters()
+    }
+
+    /// Despierta a los hilos cuyo fd ya esta listo. Si no queda ningun hilo
+    /// listo espera un rato por E/S en lugar de girar en vacio.
+    fn poll_io(&mut self) {
+        let timeout_ms = if self.ready.is_empty() { IO_POLL_TIMEOUT_MS } else { 0 };
+        let woken = self.channels.reactor().poll(timeout_ms);
+        match woken {
+            Ok(woken) => {
+                for tid in woken {
+                    self.unblock_thread(tid);
+                }
+            }
+            Err(e) => mp_log!(Warn, "runtime", "fallo la espera de E/S: {}", e),
+        }
+    }
+
     pub fn run_once(&mut self) {
-        self.now_ms += 10;
-        let Some(tid) = self.ready.pop_front() else {
+        self.now_ms += QUANTUM_MS;
+        self.apply_remote();
+        self.poll_io();
+        let Some(tid) = self.select_next_thread() else {
             //println!("[Runtime] no hay hilos ready");
             return;
         };
 
+        self.dispatch(tid);
+    }
+
+    /// Despacha `tid` sin consultar al scheduler (lo usa el explorador de
+    /// intercalaciones). Retorna false si el hilo no estaba listo.
+    pub fn run_chosen(&mut self, tid: ThreadId) -> bool {
+        if !self.ready.contains(&tid) {
+            return false;
+        }
+        self.now_ms += QUANTUM_MS;
+        self.lottery.refresh(&mut self.threads);
+        self.ready.retain(|&ready_tid| ready_tid != tid);
+        self.replay.record_dispatch(Some(tid));
+        self.dispatch(tid);
+        true
+    }
 
+    /// corre un paso del hilo ya sacado de la cola de listos
+    fn dispatch(&mut self, tid: ThreadId) {
         // obtener el hilo
         let thread = self.threads.get_mut(&tid).expect("hilo debe existir");
-        let current_tickets = thread.tickets;
+        let current_tickets = thread.effective_tickets;
         thread.state = ThreadState::Running;
+        // la compensacion solo dura hasta el siguiente despacho
+        thread.compensation_tickets = 0;
+        let deadline = (thread.sched_type == SchedulerType::RealTime)
+            .then_some(thread.deadline)
+            .flatten();
+        let missed_deadline = thread.stats.on_dispatch(self.now_ms, deadline);
 
         // preparar mensaje inicial
         let thread_ptr = &mut **thread as *mut MyThread;
@@ -117,90 +680,153 @@ impl ThreadRuntimeV2 {
             current_tickets,
         };
 
+        self.tracer.record(tid, TraceEventKind::Dispatch, self.now_ms);
+        self.notify(RuntimeEvent::Dispatch { tid });
+        if let Some(deadline) = missed_deadline {
+            self.notify(RuntimeEvent::DeadlineMiss { tid, deadline });
+        }
+
+        let thread = self.threads.get_mut(&tid).unwrap();
+
         // hacer resume al hilo
+        let started = Instant::now();
         let response_data = unsafe { thread.context.resume_with_data(init_msg.pack()) };
+        let elapsed = started.elapsed();
 
         // procesar respuesta
         let response = unsafe { ThreadResponse::unpack(response_data) };
 
         //println!("[Runtime] hilo {} retornó: {:?}", tid, response);
 
+        // datos para las estadisticas, antes de consumir la respuesta
+        let used_ms = match response {
+            ThreadResponse::YieldEarly(used_ms) => used_ms.clamp(1, QUANTUM_MS),
+            _ => QUANTUM_MS,
+        };
+        let block_reason = match response {
+            ThreadResponse::Join(_) => BlockReason::Join,
+            ThreadResponse::MutexLock(_) => BlockReason::Mutex,
+            _ => BlockReason::Explicit,
+        };
+
         match response {
             ThreadResponse::Yield => {
                 //println!("[Runtime] hilo {} hizo yield, reencolando", tid);
                 let thread = self.threads.get_mut(&tid).unwrap();
                 thread.state = ThreadState::Ready;
                 self.ready.push_back(tid);
+                self.tracer.record(tid, TraceEventKind::Yield, self.now_ms);
+                self.notify(RuntimeEvent::Yield { tid, used_ms });
+            }
+            ThreadResponse::YieldEarly(used_ms) => {
+                // compensacion: si uso una fraccion f del quantum, sus tiquetes valen 1/f
+                let thread = self.threads.get_mut(&tid).unwrap();
+                let used_ms = used_ms.clamp(1, QUANTUM_MS);
+                let extra = thread.tickets as u64 * (QUANTUM_MS - used_ms) / used_ms;
+                thread.compensation_tickets = extra.min(u32::MAX as u64) as u32;
+                thread.state = ThreadState::Ready;
+                self.ready.push_back(tid);
+                self.tracer.record(tid, TraceEventKind::Yield, self.now_ms);
+                self.notify(RuntimeEvent::Yield { tid, used_ms });
             }
             ThreadResponse::Block => {
                 // println!("[Runtime] hilo {} se bloqueó", tid);
                 let thread = self.threads.get_mut(&tid).unwrap();
                 thread.state = ThreadState::Blocked;
                 self.blocked.push(tid);
+                self.tracer.record(tid, TraceEventKind::Block, self.now_ms);
+                self.notify(RuntimeEvent::Block { tid, reason: block_reason });
             }
             ThreadResponse::Exit => {
                 //println!("[Runtime] hilo {} terminó", tid);
+                self.record_stack(tid);
                 let thread = self.threads.get_mut(&tid).unwrap();
                 thread.state = ThreadState::Terminated;
-
-                //Despierta TODOS los hilos que estaban esperando por este en cuestion
-                let joiners_unblock = thread.joiners.clone(); //Es mejor clonar para evitar problemas de borrow
+                //Despierta al hilo que estaba esperando por este en cuestion
+                let joiners_unblock = std::mem::take(&mut thread.joiners);
+                self.tracer.record(tid, TraceEventKind::Exit, self.now_ms);
+                self.notify(RuntimeEvent::Exit { tid });
                 for joiner_tid in joiners_unblock {
                     self.unblock_thread(joiner_tid);
                 }
             }
             ThreadResponse::Continue => {
+                self.threads.get_mut(&tid).unwrap().state = ThreadState::Ready;
                 self.ready.push_back(tid);
+                self.tracer.record(tid, TraceEventKind::Yield, self.now_ms);
+                self.notify(RuntimeEvent::Yield { tid, used_ms });
             }
             ThreadResponse::Join(target_tid) => {
                 let current_tid = tid;
-                let mut should_block = true;
-
-                if let Some(target) = self.threads.get(&target_tid) {
-                    if target.state == ThreadState::Terminated {
-                        should_block = false;
-                        //println!(
-                        //    "[Runtime] Hilo {} no se bloquea, {} ya terminó.",
-                        //    current_tid, target_tid
-                        //);
-                        self.ready.push_back(current_tid);
-                    } else {
-                        //println!("[Runtime] Hilo {} esperando a {}.", current_tid, target_tid);
-                        self.threads
-                            .get_mut(&target_tid)
-                            .unwrap()
-                            .joiners
-                            .push(current_tid);
+                let should_block = match self.check_join(current_tid, target_tid) {
+                    Ok(should_block) => should_block,
+                    Err(e) => {
+                        if !matches!(e, MyThreadError::NoSuchThread(_)) {
+                            mp_log!(Warn, "runtime", "hilo {} no puede esperar a {}: {}", current_tid, target_tid, e);
+                        }
+                        // el paso ya termino: el hilo lo ve con `my_thread_last_error`
+                        self.threads.get_mut(&current_tid).unwrap().last_error = Some(e);
+                        false
                     }
-                } else {
-                    should_block = false;
-                    self.ready.push_back(current_tid);
-                }
+                };
 
                 if should_block {
+                    //println!("[Runtime] Hilo {} esperando a {}.", current_tid, target_tid);
+                    let joiners = &mut self.threads.get_mut(&target_tid).unwrap().joiners;
+                    if !joiners.contains(&current_tid) {
+                        joiners.push(current_tid);
+                    }
                     let thread = self.threads.get_mut(&current_tid).unwrap();
                     thread.state = ThreadState::Blocked;
                     self.blocked.push(current_tid);
+                    self.tracer.record(current_tid, TraceEventKind::Block, self.now_ms);
+                    self.notify(RuntimeEvent::Block { tid: current_tid, reason: block_reason });
+                } else {
+                    // ya termino, no existe o el join no es valido: sigue listo
+                    self.threads.get_mut(&current_tid).unwrap().state = ThreadState::Ready;
+                    self.ready.push_back(current_tid);
+                    self.tracer.record(current_tid, TraceEventKind::Yield, self.now_ms);
+                    self.notify(RuntimeEvent::Yield { tid: current_tid, used_ms });
                 }
             }
             ThreadResponse::MutexLock(mutex_addr) => {
                 let mutex = unsafe { &*(mutex_addr as *const SimpleMutex) };
                 let current_tid = tid;
 
-                // `lock` ahora devuelve `true` si se debe bloquear
-                if mutex.lock(current_tid) {
+                // `lock` devuelve `true` si se debe bloquear
+                let lock_result = mutex.lock(current_tid);
+                if lock_result == Ok(true) {
                     // El lock no se pudo adquirir, bloquear el hilo.
                     //println!(
                     //    "[Runtime] Hilo {} se bloquea esperando un mutex.",
                     //    current_tid
                     //);
+                    // Mientras espera, le presta sus tiquetes al dueño del lock.
+                    let owner = mutex.owner.load(std::sync::atomic::Ordering::Relaxed);
                     let thread = self.threads.get_mut(&current_tid).unwrap();
+                    thread.stats.mutex_contentions += 1;
                     thread.state = ThreadState::Blocked;
+                    thread.lending_to = (owner != current_tid && owner != UNLOCKED).then_some(owner);
+                    self.mutex_waits.insert(current_tid, mutex.wait_queue.clone());
                     self.blocked.push(current_tid);
+                    self.tracer.record(current_tid, TraceEventKind::Block, self.now_ms);
+                    self.notify(RuntimeEvent::Block { tid: current_tid, reason: block_reason });
                 } else {
-                    // El lock se adquirió, el hilo sigue listo.
+                    // El lock se adquirió (o el dueño de un errorcheck lo pidió otra vez), el hilo sigue listo.
                     //println!("[Runtime] Hilo {} adquirió un mutex.", current_tid);
+                    let thread = self.threads.get_mut(&current_tid).unwrap();
+                    match lock_result {
+                        Ok(_) => thread.stats.mutex_acquisitions += 1,
+                        Err(e) => {
+                            mp_log!(Warn, "runtime", "hilo {} no pudo tomar el mutex: {}", current_tid, e);
+                            // el EDEADLK que `my_mutex_lock` hubiera devuelto
+                            thread.last_error = Some(e);
+                        }
+                    }
+                    thread.state = ThreadState::Ready;
                     self.ready.push_back(current_tid);
+                    self.tracer.record(current_tid, TraceEventKind::Yield, self.now_ms);
+                    self.notify(RuntimeEvent::Yield { tid: current_tid, used_ms });
                 }
             }
             ThreadResponse::MutexUnlock(mutex_addr) => {
@@ -208,18 +834,51 @@ impl ThreadRuntimeV2 {
                 let current_tid = tid;
 
                 // `unlock` devuelve el siguiente hilo a despertar, si lo hay
-                if let Some(unblocked_tid) = mutex.unlock(current_tid) {
-                    //println!(
-                    //    "[Runtime] Mutex liberado, despertando al hilo {}.",
-                    //    unblocked_tid
-                    //);
-                    self.unblock_thread(unblocked_tid);
+                let next = mutex.unlock(current_tid).unwrap_or_else(|e| {
+                    mp_log!(Warn, "runtime", "hilo {} no pudo liberar el mutex: {}", current_tid, e);
+                    None
+                });
+                if let Some(unblocked_tid) = next {
+                    let waiters = unsafe { &*mutex.wait_queue.get() }.iter().copied().collect();
+                    self.hand_off_mutex(current_tid, unblocked_tid, waiters);
                 }
 
                 // El hilo que liberó el mutex vuelve a estar listo.
+                self.threads.get_mut(&current_tid).unwrap().state = ThreadState::Ready;
                 self.ready.push_back(current_tid);
+                self.tracer.record(current_tid, TraceEventKind::Yield, self.now_ms);
+                self.notify(RuntimeEvent::Yield { tid: current_tid, used_ms });
             }
         }
+
+        if let Some(thread) = self.threads.get_mut(&tid) {
+            thread.stats.on_run(used_ms);
+            match thread.state {
+                ThreadState::Ready => thread.stats.on_ready(self.now_ms),
+                ThreadState::Blocked => thread.stats.on_block(block_reason, self.now_ms),
+                _ => {}
+            }
+        }
+        let blocked_alone = block_reason == BlockReason::Explicit
+            && self.threads.get(&tid).is_some_and(|t| t.state == ThreadState::Blocked);
+        self.watch(tid, elapsed, blocked_alone);
+
+        // mutex que el hilo entrego durante el paso (`my_mutex_unlock`)
+        for handoff in self.channels.take_handoff_requests() {
+            self.hand_off_mutex(handoff.from, handoff.to, handoff.waiters);
+        }
+        // despertares pedidos por el hilo durante el paso (ctx_wake)
+        for woken in self.channels.take_wake_requests() {
+            self.unblock_thread(woken);
+        }
+
+        if self.threads.get(&tid).is_some_and(|t| t.cancel_pending) {
+            self.cancel_thread(tid);
+        }
+        // un hilo detached se libera apenas termina
+        if self.threads.get(&tid).is_some_and(|t| t.detached) {
+            self.reap(tid);
+        }
     }
 
     /// ejecuta multiples ciclos
@@ -1,19 +1,56 @@
+use crate::mp_log;
 use crate::thread::{ThreadId, SchedulerType, MyThread};
 use std::collections::{HashMap, VecDeque};
 use rand::Rng;
 
+/// Lo que las politicas necesitan de un hilo. Lo implementan los hilos del
+/// runtime y las tareas del simulador (`model.rs`), asi ambos deciden igual.
+pub trait Schedulable {
+    fn sched_type(&self) -> SchedulerType;
+    /// tiquetes con los que entra al sorteo
+    fn effective_tickets(&self) -> u32;
+    /// deadline absoluto, solo para tiempo real
+    fn deadline(&self) -> Option<u64>;
+}
+
+impl Schedulable for MyThread {
+    fn sched_type(&self) -> SchedulerType {
+        self.sched_type
+    }
+
+    fn effective_tickets(&self) -> u32 {
+        self.effective_tickets
+    }
+
+    fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+}
+
+impl<T: Schedulable + ?Sized> Schedulable for Box<T> {
+    fn sched_type(&self) -> SchedulerType {
+        (**self).sched_type()
+    }
 
+    fn effective_tickets(&self) -> u32 {
+        (**self).effective_tickets()
+    }
+
+    fn deadline(&self) -> Option<u64> {
+        (**self).deadline()
+    }
+}
 
 /// SCHEDULER DE TIEMPO REAL: Encuentra el hilo listo con el deadline más cercano.
 fn schedule_real_time<'a>(
     ready_queue: &'a VecDeque<ThreadId>,
-    threads: &HashMap<ThreadId, Box<MyThread>>
+    threads: &HashMap<ThreadId, impl Schedulable>
 ) -> Option<ThreadId> {
     ready_queue.iter()
         .filter_map(|&tid| {
             let thread = threads.get(&tid)?;
-            if thread.sched_type == SchedulerType::RealTime {
-                Some((tid, thread.deadline.unwrap_or(u64::MAX)))
+            if thread.sched_type() == SchedulerType::RealTime {
+                Some((tid, thread.deadline().unwrap_or(u64::MAX)))
             } else {
                 None
             }
@@ -23,23 +60,30 @@ fn schedule_real_time<'a>(
 }
 
 /// SCHEDULER DE SORTEO: Elige un ganador basado en tiquetes entre los hilos que no son de tiempo real.
+/// El generador se inyecta para que una misma semilla repita los mismos sorteos.
 fn schedule_lottery<'a>(
     ready_queue: &'a VecDeque<ThreadId>,
-    threads: &HashMap<ThreadId, Box<MyThread>>
+    threads: &HashMap<ThreadId, impl Schedulable>,
+    rng: &mut impl Rng,
 ) -> Option<ThreadId> {
     
     // Filtramos solo los candidatos para sorteo (Lottery y RoundRobin).
     let lottery_candidates: Vec<ThreadId> = ready_queue.iter()
-        .filter(|&&tid| threads.get(&tid).map_or(false, |t| t.sched_type != SchedulerType::RealTime))
+        .filter(|&&tid| threads.get(&tid).map_or(false, |t| t.sched_type() != SchedulerType::RealTime))
         .cloned()
         .collect();
 
-    if lottery_candidates.is_empty() {
+    // Si ningun candidato es de sorteo, dejamos que Round Robin respete el orden FIFO.
+    let any_lottery = lottery_candidates.iter()
+        .any(|tid| threads.get(tid).is_some_and(|t| t.sched_type() == SchedulerType::Lottery));
+    if !any_lottery {
         return None;
     }
     
-    let total_tickets = lottery_candidates.iter()
-        .map(|&tid| threads.get(&tid).unwrap().tickets)
+    // Se usan los tiquetes efectivos (moneda, compensacion y prestamos ya aplicados).
+    // Se suma en u64: las monedas pueden inflar los tiquetes hasta pasar u32.
+    let total_tickets: u64 = lottery_candidates.iter()
+        .map(|&tid| threads.get(&tid).unwrap().effective_tickets() as u64)
         .sum();
 
     if total_tickets == 0 {
@@ -47,11 +91,15 @@ fn schedule_lottery<'a>(
         return None;
     }
 
-    let winning_ticket = rand::rng().random_range(1..=total_tickets);
+    // si cabe en u32 se sortea como siempre, asi una semilla repite los mismos sorteos
+    let winning_ticket = match u32::try_from(total_tickets) {
+        Ok(total) => rng.random_range(1..=total) as u64,
+        Err(_) => rng.random_range(1..=total_tickets),
+    };
     let mut accumulated_tickets = 0;
 
     for &tid in &lottery_candidates {
-        accumulated_tickets += threads.get(&tid).unwrap().tickets;
+        accumulated_tickets += threads.get(&tid).unwrap().effective_tickets() as u64;
         if accumulated_tickets >= winning_ticket {
             return Some(tid);
         }
@@ -63,10 +111,10 @@ fn schedule_lottery<'a>(
 /// SCHEDULER ROUND ROBIN: Simplemente toma el primer hilo que no sea de tiempo real.
 fn schedule_round_robin<'a>(
     ready_queue: &'a VecDeque<ThreadId>,
-    threads: &HashMap<ThreadId, Box<MyThread>>
+    threads: &HashMap<ThreadId, impl Schedulable>
 ) -> Option<ThreadId> {
     ready_queue.iter()
-        .find(|&&tid| threads.get(&tid).map_or(false, |t| t.sched_type != SchedulerType::RealTime))
+        .find(|&&tid| threads.get(&tid).map_or(false, |t| t.sched_type() != SchedulerType::RealTime))
         .copied()
 }
 
@@ -74,8 +122,9 @@ fn schedule_round_robin<'a>(
 /// La función principal que maneja los schedulers según su prioridad.
 pub fn select_next_thread(
     ready_queue: &VecDeque<ThreadId>,
-    threads: &HashMap<ThreadId, Box<MyThread>>,
+    threads: &HashMap<ThreadId, impl Schedulable>,
     now_ms: u64,
+    rng: &mut impl Rng,
 ) -> Option<ThreadId> {
     
     if ready_queue.is_empty() {
@@ -84,23 +133,23 @@ pub fn select_next_thread(
 
     // 1. MÁXIMA PRIORIDAD: RT
     if let Some(tid) = schedule_real_time(ready_queue, threads) {
-        let deadline = threads.get(&tid).unwrap().deadline.unwrap_or(0);
+        let deadline = threads.get(&tid).unwrap().deadline().unwrap_or(0);
         if deadline < now_ms {
-            println!("[Scheduler] ¡¡¡FALLO DE TIEMPO REAL!!! Hilo {} falló su deadline {}. Tiempo actual: {}", tid, deadline, now_ms);
+            mp_log!(Warn, "sched", "¡¡¡FALLO DE TIEMPO REAL!!! Hilo {} falló su deadline {}. Tiempo actual: {}", tid, deadline, now_ms);
         }
-        println!("[Scheduler] TIEMPO REAL: Seleccionado hilo {}", tid);
+        mp_log!(Debug, "sched", "TIEMPO REAL: Seleccionado hilo {}", tid);
         return Some(tid);
     }
 
     // 2. PRIORIDAD NORMAL: Si no hay hilos de tiempo real, realizamos un sorteo.
-    if let Some(tid) = schedule_lottery(ready_queue, threads) {
-        println!("[Scheduler] SORTEO: Seleccionado hilo {}", tid);
+    if let Some(tid) = schedule_lottery(ready_queue, threads, rng) {
+        mp_log!(Debug, "sched", "SORTEO: Seleccionado hilo {}", tid);
         return Some(tid);
     }
 
     // 3. FALLBACK: Si el sorteo falla (ej. 0 tiquetes), usamos Round Robin simple.
     if let Some(tid) = schedule_round_robin(ready_queue, threads) {
-        println!("[Scheduler] ROUND ROBIN (Fallback): Seleccionado hilo {}", tid);
+        mp_log!(Debug, "sched", "ROUND ROBIN (Fallback): Seleccionado hilo {}", tid);
         return Some(tid);
     }
     
@@ -6,6 +6,7 @@ use crate::thread::ThreadId;
 pub enum ThreadSignal {
     Continue, // hilo sigue y se reencola como yield en este mvp
     Yield,    // hilo cede y se reencola al final
+    YieldEarly(u64), // hilo cede habiendo usado solo N ms del quantum (recibe compensacion)
     Block,    // hilo se bloquea y no se reencola
     Exit,     // hilo termina
     Join(ThreadId),
@@ -0,0 +1,106 @@
+//! vista propia (sin referencias al runtime) del estado de todos los hilos
+//!
+//! Se obtiene con `ThreadRuntimeV2::snapshot` o `runtime_snapshot()` y se puede
+//! serializar a JSON con `RuntimeSnapshot::to_json`.
+
+use crate::thread::{SchedulerType, ThreadId, ThreadState};
+use crate::trace::escape_json;
+use std::fmt::Write as _;
+
+/// Por que esta bloqueado un hilo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitReason {
+    /// el hilo retorno `ThreadSignal::Block`
+    Explicit,
+    /// esperando que termine el hilo indicado
+    Join(ThreadId),
+    /// esperando un mutex; `owner` es el dueño actual si se conoce
+    Mutex { owner: Option<ThreadId> },
+}
+
+/// Estado de un hilo en el momento de la captura
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadSnapshot {
+    pub id: ThreadId,
+    pub name: String,
+    pub state: ThreadState,
+    pub sched_type: SchedulerType,
+    pub tickets: u32,
+    /// tiquetes con los que participa en el sorteo (moneda, compensacion, prestamos)
+    pub effective_tickets: u32,
+    pub deadline: Option<u64>,
+    pub detached: bool,
+    pub joiners: Vec<ThreadId>,
+    /// solo para hilos bloqueados
+    pub wait_reason: Option<WaitReason>,
+}
+
+/// Captura completa del runtime
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuntimeSnapshot {
+    pub now_ms: u64,
+    /// cola de listos, en orden
+    pub ready: Vec<ThreadId>,
+    pub blocked: Vec<ThreadId>,
+    /// todos los hilos ordenados por id
+    pub threads: Vec<ThreadSnapshot>,
+}
+
+impl RuntimeSnapshot {
+    pub fn thread(&self, tid: ThreadId) -> Option<&ThreadSnapshot> {
+        self.threads
+            .binary_search_by_key(&tid, |t| t.id)
+            .ok()
+            .map(|i| &self.threads[i])
+    }
+
+    /// serializa la captura como un objeto JSON
+    pub fn to_json(&self) -> String {
+        let threads: Vec<String> = self.threads.iter().map(ThreadSnapshot::to_json).collect();
+        format!(
+            r#"{{"now_ms":{},"ready":{},"blocked":{},"threads":[{}]}}"#,
+            self.now_ms,
+            json_ids(&self.ready),
+            json_ids(&self.blocked),
+            threads.join(",")
+        )
+    }
+}
+
+impl ThreadSnapshot {
+    fn to_json(&self) -> String {
+        let mut out = format!(
+            r#"{{"id":{},"name":"{}","state":"{:?}","sched_type":"{:?}","tickets":{},"effective_tickets":{},"deadline":{},"detached":{},"joiners":{},"wait_reason":"#,
+            self.id,
+            escape_json(&self.name),
+            self.state,
+            self.sched_type,
+            self.tickets,
+            self.effective_tickets,
+            json_opt(self.deadline),
+            self.detached,
+            json_ids(&self.joiners),
+        );
+        match self.wait_reason {
+            None => out.push_str("null"),
+            Some(WaitReason::Explicit) => out.push_str(r#"{"kind":"explicit"}"#),
+            Some(WaitReason::Join(target)) => {
+                let _ = write!(out, r#"{{"kind":"join","target":{}}}"#, target);
+            }
+            Some(WaitReason::Mutex { owner }) => {
+                let _ = write!(out, r#"{{"kind":"mutex","owner":{}}}"#, json_opt(owner));
+            }
+        }
+        out.push('}');
+        out
+    }
+}
+
+fn json_ids(ids: &[ThreadId]) -> String {
+    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    format!("[{}]", ids.join(","))
+}
+
+fn json_opt<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "null".to_string(), |v| v.to_string())
+}
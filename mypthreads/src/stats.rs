@@ -0,0 +1,219 @@
+//! contadores por hilo y resumen del runtime
+//!
+//! Todos los tiempos estan en el reloj simulado del runtime (`now_ms`).
+
+use std::collections::BTreeMap;
+
+/// Motivo por el que un hilo quedo bloqueado
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BlockReason {
+    /// el hilo retorno `ThreadSignal::Block`
+    Explicit,
+    /// esperando que termine otro hilo
+    Join,
+    /// esperando un mutex
+    Mutex,
+}
+
+/// Historial de un hilo
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThreadStats {
+    /// veces que el scheduler lo eligio
+    pub dispatches: u64,
+    /// ms de quantum consumidos (un yield temprano cuenta solo lo usado)
+    pub run_ms: u64,
+    /// ms esperando en la cola de listos
+    pub ready_ms: u64,
+    /// ms bloqueado, por motivo
+    pub blocked_ms: BTreeMap<BlockReason, u64>,
+    pub mutex_acquisitions: u64,
+    /// veces que tuvo que esperar un mutex ocupado
+    pub mutex_contentions: u64,
+    /// deadlines de tiempo real que vencieron antes de despacharlo
+    pub deadline_misses: u64,
+    /// tamaño de su pila en bytes
+    pub stack_bytes: usize,
+    /// lo mas que llego a usar de la pila (se mide al terminar o al pedir las estadisticas)
+    pub stack_peak_bytes: usize,
+    /// el despacho mas largo, en microsegundos de tiempo real
+    pub max_dispatch_us: u64,
+    /// despachos que pasaron del umbral del watchdog
+    pub slow_dispatches: u64,
+    /// veces que el watchdog lo marco como livelock
+    pub livelocks: u64,
+    ready_since: Option<u64>,
+    blocked_since: Option<(BlockReason, u64)>,
+    last_missed_deadline: Option<u64>,
+}
+
+impl ThreadStats {
+    /// quantums completos equivalentes
+    pub fn run_quanta(&self, quantum_ms: u64) -> f64 {
+        self.run_ms as f64 / quantum_ms.max(1) as f64
+    }
+
+    pub fn total_blocked_ms(&self) -> u64 {
+        self.blocked_ms.values().sum()
+    }
+
+    /// motivo del bloqueo en curso, si esta bloqueado
+    pub(crate) fn blocked_reason(&self) -> Option<BlockReason> {
+        self.blocked_since.map(|(reason, _)| reason)
+    }
+
+    /// el hilo entro a la cola de listos
+    pub(crate) fn on_ready(&mut self, now_ms: u64) {
+        if let Some((reason, since)) = self.blocked_since.take() {
+            *self.blocked_ms.entry(reason).or_insert(0) += now_ms.saturating_sub(since);
+        }
+        if self.ready_since.is_none() {
+            self.ready_since = Some(now_ms);
+        }
+    }
+
+    /// el scheduler lo eligio; retorna el deadline si se acaba de perder
+    pub(crate) fn on_dispatch(&mut self, now_ms: u64, deadline: Option<u64>) -> Option<u64> {
+        self.dispatches += 1;
+        if let Some(since) = self.ready_since.take() {
+            self.ready_ms += now_ms.saturating_sub(since);
+        }
+        let deadline = deadline?;
+        if deadline < now_ms && self.last_missed_deadline != Some(deadline) {
+            self.deadline_misses += 1;
+            self.last_missed_deadline = Some(deadline);
+            return Some(deadline);
+        }
+        None
+    }
+
+    pub(crate) fn on_run(&mut self, used_ms: u64) {
+        self.run_ms += used_ms;
+    }
+
+    pub(crate) fn on_block(&mut self, reason: BlockReason, now_ms: u64) {
+        self.blocked_since = Some((reason, now_ms));
+    }
+
+    /// copia que cuenta el bloqueo en curso hasta `now_ms`, para los reportes
+    pub(crate) fn with_open_block(mut self, now_ms: u64) -> Self {
+        if let Some((reason, since)) = self.blocked_since {
+            *self.blocked_ms.entry(reason).or_insert(0) += now_ms.saturating_sub(since);
+            self.blocked_since = Some((reason, now_ms));
+        }
+        self
+    }
+
+    pub(crate) fn on_dispatch_time(&mut self, elapsed_us: u64) {
+        self.max_dispatch_us = self.max_dispatch_us.max(elapsed_us);
+    }
+
+    pub(crate) fn on_stack_measure(&mut self, peak_bytes: usize) {
+        self.stack_peak_bytes = self.stack_peak_bytes.max(peak_bytes);
+    }
+}
+
+/// clases distintas que guarda el uso de pila por defecto
+pub const MAX_STACK_CLASSES: usize = 64;
+/// clase que junta a los hilos que llegan cuando ya no caben mas clases
+pub const OTHER_STACK_CLASS: &str = "(otras)";
+
+/// Uso de pila de todos los hilos de una misma clase (su grupo, o su nombre
+/// si no tiene), para dimensionar pilas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StackUsage {
+    /// hilos medidos de esa clase
+    pub threads: usize,
+    /// el pico mas alto entre ellos, en bytes
+    pub peak_bytes: usize,
+    /// tamaño de la pila mas grande entre ellos
+    pub stack_bytes: usize,
+}
+
+impl StackUsage {
+    pub(crate) fn add(&mut self, stats: &ThreadStats) {
+        self.threads += 1;
+        self.peak_bytes = self.peak_bytes.max(stats.stack_peak_bytes);
+        self.stack_bytes = self.stack_bytes.max(stats.stack_bytes);
+    }
+}
+
+/// suma `stats` a su clase; una clase nueva que ya no cabe en `limit` va a `OTHER_STACK_CLASS`
+pub(crate) fn add_stack_usage(
+    usage: &mut BTreeMap<String, StackUsage>,
+    class: &str,
+    stats: &ThreadStats,
+    limit: usize,
+) {
+    let key = if usage.contains_key(class) || usage.len() < limit {
+        class
+    } else {
+        OTHER_STACK_CLASS
+    };
+    usage.entry(key.to_string()).or_default().add(stats);
+}
+
+/// Totales de todos los hilos del runtime
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsSummary {
+    pub now_ms: u64,
+    pub threads: usize,
+    pub dispatches: u64,
+    pub run_ms: u64,
+    pub ready_ms: u64,
+    pub blocked_ms: BTreeMap<BlockReason, u64>,
+    pub mutex_acquisitions: u64,
+    pub mutex_contentions: u64,
+    pub deadline_misses: u64,
+    /// el pico de pila mas alto entre todos los hilos
+    pub stack_peak_bytes: usize,
+    pub slow_dispatches: u64,
+    pub livelocks: u64,
+}
+
+impl StatsSummary {
+    /// suma los contadores de varios hilos
+    pub fn collect<'a>(now_ms: u64, stats: impl IntoIterator<Item = &'a ThreadStats>) -> Self {
+        let mut summary = StatsSummary {
+            now_ms,
+            ..Default::default()
+        };
+        for s in stats {
+            summary.add(s);
+        }
+        summary
+    }
+
+    /// suma los contadores de un hilo mas
+    pub fn add(&mut self, s: &ThreadStats) {
+        self.threads += 1;
+        self.dispatches += s.dispatches;
+        self.run_ms += s.run_ms;
+        self.ready_ms += s.ready_ms;
+        for (reason, ms) in &s.blocked_ms {
+            *self.blocked_ms.entry(*reason).or_insert(0) += ms;
+        }
+        self.mutex_acquisitions += s.mutex_acquisitions;
+        self.mutex_contentions += s.mutex_contentions;
+        self.deadline_misses += s.deadline_misses;
+        self.stack_peak_bytes = self.stack_peak_bytes.max(s.stack_peak_bytes);
+        self.slow_dispatches += s.slow_dispatches;
+        self.livelocks += s.livelocks;
+    }
+
+    /// suma otro resumen (p. ej. el de los hilos ya liberados); conserva `now_ms`
+    pub fn merge(&mut self, other: &StatsSummary) {
+        self.threads += other.threads;
+        self.dispatches += other.dispatches;
+        self.run_ms += other.run_ms;
+        self.ready_ms += other.ready_ms;
+        for (reason, ms) in &other.blocked_ms {
+            *self.blocked_ms.entry(*reason).or_insert(0) += ms;
+        }
+        self.mutex_acquisitions += other.mutex_acquisitions;
+        self.mutex_contentions += other.mutex_contentions;
+        self.deadline_misses += other.deadline_misses;
+        self.stack_peak_bytes = self.stack_peak_bytes.max(other.stack_peak_bytes);
+        self.slow_dispatches += other.slow_dispatches;
+        self.livelocks += other.livelocks;
+    }
+}
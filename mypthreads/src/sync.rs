@@ -1,10 +1,13 @@
+use crate::api_context::try_current_tid;
+use crate::error::{MyResult, MyThreadError};
 use crate::mypthreads_api::{
-    my_mutex_destroy, my_mutex_init, my_mutex_lock, my_mutex_trylock, my_mutex_unlock, MyMutex,
+    my_mutex_destroy, my_mutex_lock, my_mutex_trylock, my_mutex_unlock, wait_until, wake_all, MyMutex,
 };
 use crate::signals::ThreadSignal;
+use crate::thread::ThreadId;
 use std::cell::UnsafeCell;
 use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 /// Celda protegida por un MyMutex
 pub struct MyMutexCell<T> {
@@ -22,12 +25,12 @@ impl<T> MyMutexCell<T> {
     /// Crea un nuevo MyMutexCell con el valor dado
     pub fn new(value: T) -> Self {
         Self {
-            mtx: my_mutex_init(),
+            mtx: MyMutex::new(),
             data: UnsafeCell::new(value),
         }
     }
     /// Solicitar el lock.   
-    pub fn request_lock(&self) -> ThreadSignal {
+    pub fn request_lock(&self) -> MyResult<ThreadSignal> {
         my_mutex_lock(&self.mtx)
     }
 
@@ -45,13 +48,13 @@ impl<T> MyMutexCell<T> {
     }
 
     /// Liberar el lock.
-    pub fn request_unlock(&self) -> ThreadSignal {
+    pub fn request_unlock(&self) -> MyResult<ThreadSignal> {
         my_mutex_unlock(&self.mtx)
     }
 
     /// Intenta entrar a la sección crítica sin bloquear.
     pub fn try_enter(&self) -> Option<MyGuard<'_, T>> {
-        let ok = my_mutex_trylock(&self.mtx);
+        let ok = my_mutex_trylock(&self.mtx).is_ok();
         // println!("try_enter: try_lock returned {}", ok);
         if ok {
             Some(MyGuard {
@@ -67,7 +70,8 @@ impl<T> MyMutexCell<T> {
 impl<T> Drop for MyMutexCell<T> {
     fn drop(&mut self) {
         // Destruir el mutex cuando se destruye el cell
-        my_mutex_destroy(&mut self.mtx);
+        // si quedo tomado no hay nada que liberar
+        let _ = my_mutex_destroy(&mut self.mtx);
     }
 }
 
@@ -102,6 +106,203 @@ pub fn shared<T>(value: T) -> Shared<T> {
     Arc::new(MyMutexCell::new(value))
 }
 
+/// A quién favorece un `MyRwLock` cuando hay lectores y escritores esperando
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RwPreference {
+    /// nuevos lectores entran aunque haya escritores esperando (pueden dejarlos sin turno)
+    Readers,
+    /// con un escritor esperando ya no entran lectores nuevos
+    #[default]
+    Writers,
+}
+
+#[derive(Default)]
+struct RwState {
+    /// un hilo puede aparecer varias veces si toma la lectura de forma anidada
+    readers: Vec<ThreadId>,
+    writer: Option<ThreadId>,
+    waiting_writers: usize,
+}
+
+/// Lock de lectores/escritores para hilos verdes. Las esperas bloquean por el
+/// runtime (como `my_barrier_wait`) y los guards lo liberan al soltarse.
+pub struct MyRwLock<T> {
+    preference: RwPreference,
+    state: Mutex<RwState>,
+    waiters: Mutex<Vec<ThreadId>>,
+    data: UnsafeCell<T>,
+}
+
+// SAFETY: los lectores solo comparten &T y el escritor es exclusivo
+unsafe impl<T: Send> Send for MyRwLock<T> {}
+unsafe impl<T: Send + Sync> Sync for MyRwLock<T> {}
+
+impl<T> MyRwLock<T> {
+    /// Crea el lock con preferencia de escritores
+    pub fn new(value: T) -> Self {
+        Self::with_preference(value, RwPreference::default())
+    }
+
+    pub fn with_preference(value: T, preference: RwPreference) -> Self {
+        Self {
+            preference,
+            state: Mutex::new(RwState::default()),
+            waiters: Mutex::new(Vec::new()),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn preference(&self) -> RwPreference {
+        self.preference
+    }
+
+    fn state(&self) -> MutexGuard<'_, RwState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// intenta registrar una lectura de `me`
+    fn grant_read(&self, me: ThreadId) -> MyResult<bool> {
+        let mut state = self.state();
+        if state.writer == Some(me) {
+            return Err(MyThreadError::Deadlock);
+        }
+        // una lectura anidada siempre entra, si no el escritor esperando nos bloquea a los dos
+        let writers_first = self.preference == RwPreference::Writers && state.waiting_writers > 0;
+        let can_read = state.writer.is_none() && (!writers_first || state.readers.contains(&me));
+        if can_read {
+            state.readers.push(me);
+        }
+        Ok(can_read)
+    }
+
+    /// intenta registrar la escritura de `me`
+    fn grant_write(&self, me: ThreadId) -> MyResult<bool> {
+        let mut state = self.state();
+        if state.writer == Some(me) || state.readers.contains(&me) {
+            return Err(MyThreadError::Deadlock);
+        }
+        let can_write = state.writer.is_none() && state.readers.is_empty();
+        if can_write {
+            state.writer = Some(me);
+        }
+        Ok(can_write)
+    }
+
+    /// Toma el lock para leer, esperando si hay un escritor. EDEADLK si el
+    /// hilo ya lo tiene para escribir.
+    pub fn read(&self) -> MyResult<MyReadGuard<'_, T>> {
+        let me = try_current_tid().unwrap_or(0);
+        if !self.grant_read(me)? {
+            wait_until(&self.waiters, || self.grant_read(me).unwrap_or(false))?;
+        }
+        Ok(MyReadGuard {
+            lock: self,
+            tid: me,
+            _no_send: std::marker::PhantomData,
+        })
+    }
+
+    /// Toma el lock para escribir, esperando a que salgan todos. EDEADLK si
+    /// el hilo ya lo tiene (para leer o escribir).
+    pub fn write(&self) -> MyResult<MyWriteGuard<'_, T>> {
+        let me = try_current_tid().unwrap_or(0);
+        if !self.grant_write(me)? {
+            self.state().waiting_writers += 1;
+            let result = wait_until(&self.waiters, || self.grant_write(me).unwrap_or(false));
+            self.state().waiting_writers -= 1;
+            result?;
+        }
+        Ok(MyWriteGuard {
+            lock: self,
+            _no_send: std::marker::PhantomData,
+        })
+    }
+
+    pub fn try_read(&self) -> Option<MyReadGuard<'_, T>> {
+        let me = try_current_tid().unwrap_or(0);
+        self.grant_read(me).ok()?.then_some(MyReadGuard {
+            lock: self,
+            tid: me,
+            _no_send: std::marker::PhantomData,
+        })
+    }
+
+    pub fn try_write(&self) -> Option<MyWriteGuard<'_, T>> {
+        let me = try_current_tid().unwrap_or(0);
+        self.grant_write(me).ok()?.then_some(MyWriteGuard {
+            lock: self,
+            _no_send: std::marker::PhantomData,
+        })
+    }
+}
+
+/// Guard de lectura; libera su parte del lock al soltarse
+pub struct MyReadGuard<'a, T> {
+    lock: &'a MyRwLock<T>,
+    tid: ThreadId,
+    _no_send: std::marker::PhantomData<*const ()>,
+}
+
+impl<T> Deref for MyReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: mientras haya lectores no hay escritor
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for MyReadGuard<'_, T> {
+    fn drop(&mut self) {
+        let mut state = self.lock.state();
+        if let Some(i) = state.readers.iter().position(|&id| id == self.tid) {
+            state.readers.swap_remove(i);
+        }
+        let last = state.readers.is_empty();
+        drop(state);
+        if last {
+            wake_all(&self.lock.waiters);
+        }
+    }
+}
+
+/// Guard de escritura; libera el lock al soltarse
+pub struct MyWriteGuard<'a, T> {
+    lock: &'a MyRwLock<T>,
+    _no_send: std::marker::PhantomData<*const ()>,
+}
+
+impl<T> Deref for MyWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: el escritor es exclusivo
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for MyWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: el escritor es exclusivo
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for MyWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state().writer = None;
+        wake_all(&self.lock.waiters);
+    }
+}
+
+/// Tipo conveniente para compartir un MyRwLock entre hilos
+pub type SharedRw<T> = Arc<MyRwLock<T>>;
+
+/// Función helper para crear un SharedRw con preferencia de escritores
+pub fn shared_rw<T>(value: T) -> SharedRw<T> {
+    Arc::new(MyRwLock::new(value))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,7 +318,7 @@ mod tests {
             drop(guard); // Drop no hace unlock
 
             // Manualmente liberar
-            cell.request_unlock();
+            cell.request_unlock().unwrap();
         }
     }
 
@@ -128,7 +329,7 @@ mod tests {
         if let Some(guard) = shared_cell.try_enter() {
             assert_eq!(guard.len(), 3);
             drop(guard);
-            shared_cell.request_unlock();
+            shared_cell.request_unlock().unwrap();
         }
     }
 }
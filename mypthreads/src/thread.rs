@@ -1,10 +1,18 @@
 //! version 2 de thread con soporte para cambio de contexto real
 
+use crate::api_context::{self, SavedContext};
+use crate::channels::ThreadChannels;
 use crate::context_wrapper::ThreadContext;
+use crate::error::MyThreadError;
+use crate::group::GroupId;
+use crate::lottery::CurrencyId;
 use crate::signals::ThreadSignal;
+use crate::stats::ThreadStats;
+use crate::tls::ThreadLocals;
 use crate::thread_data::{ThreadGlobalContext, ThreadResponse, TransferMessage};
+use crate::watchdog::WatchState;
 use crate::JoinHandle;
-use context::Transfer;
+use context::{Context, Transfer};
 
 pub type ThreadId = u32;
 pub type ContextThreadEntry = Box<dyn FnMut(ThreadId, u32) -> ThreadSignal + Send + 'static>;
@@ -34,11 +42,31 @@ pub struct MyThread {
     pub state: ThreadState,
     pub sched_type: SchedulerType,
     pub tickets: u32,
+    /// moneda en la que estan denominados los tiquetes (None = tiquetes base)
+    pub currency: Option<CurrencyId>,
+    /// grupo al que pertenece (ver `group.rs`)
+    pub group: Option<GroupId>,
+    /// tiquetes extra por ceder antes de agotar el quantum, se pierden al volver a correr
+    pub compensation_tickets: u32,
+    /// hilo al que se le prestan los tiquetes mientras este espera un lock
+    pub lending_to: Option<ThreadId>,
+    /// tiquetes base que usa el sorteo (propios + compensacion + prestados)
+    pub effective_tickets: u32,
     pub deadline: Option<u64>,
     pub detached: bool,
     pub joiners: Vec<ThreadId>,
+    /// se pidio cancelarlo mientras corria; el runtime lo termina al acabar el paso
+    pub cancel_pending: bool,
     pub join_handle: JoinHandle,
+    pub stats: ThreadStats,
+    /// valores de las llaves `my_key_create`, instalados solo mientras corre
+    pub locals: ThreadLocals,
     pub context: ThreadContext,
+    pub(crate) link: Option<RuntimeLink>,
+    pub(crate) watch: WatchState,
+    /// error de una señal que el runtime no pudo cumplir (p. ej. un `Join` a si
+    /// mismo o el relock de un errorcheck); se lee y se borra con `my_thread_last_error`
+    pub last_error: Option<MyThreadError>,
     entry: Option<ContextThreadEntry>,
 }
 
@@ -52,6 +80,8 @@ impl MyThread {
         entry: ContextThreadEntry,
     ) -> Self {
         let context = ThreadContext::new(thread_entry_wrapper);
+        let mut stats = ThreadStats::default();
+        stats.stack_bytes = context.stack_size();
 
         Self {
             id,
@@ -59,15 +89,33 @@ impl MyThread {
             state: ThreadState::New,
             sched_type,
             tickets,
+            currency: None,
+            group: None,
+            compensation_tickets: 0,
+            lending_to: None,
+            effective_tickets: tickets,
             deadline,
             detached: false,
             joiners: Vec::new(),
+            cancel_pending: false,
             join_handle: JoinHandle::new(),
+            stats,
+            locals: ThreadLocals::default(),
             context,
+            link: None,
+            watch: WatchState::default(),
+            last_error: None,
             entry: Some(entry),
         }
     }
 
+    /// estadisticas con el pico de pila medido ahora
+    pub fn measured_stats(&self) -> ThreadStats {
+        let mut stats = self.stats.clone();
+        stats.on_stack_measure(self.context.stack_high_water());
+        stats
+    }
+
     /// ejecutar un paso del hilo, pasando los tiquetes actuales
     pub(crate) fn execute_step(&mut self, current_tickets: u32) -> ThreadSignal {
         if let Some(ref mut entry) = self.entry {
@@ -78,10 +126,18 @@ impl MyThread {
     }
 }
 
+/// Lo que un hilo necesita para devolver el control al runtime; solo existe mientras corre.
+pub(crate) struct RuntimeLink {
+    context: Context,
+    saved: SavedContext,
+    /// contexto global que habia antes de entrar, se repone al salir
+    global: Option<ThreadGlobalContext>,
+}
+
 /// WRAPPER: Se ejecuta en la pila del nuevo hilo y maneja la comunicación con el Runtime.
-extern "C" fn thread_entry_wrapper(mut transfer: Transfer) -> ! {
+extern "C" fn thread_entry_wrapper(transfer: Transfer) -> ! {
     // Desempacamos el mensaje inicial que nos envió el Runtime
-    let (thread_ptr, channels, tid, mut current_tickets) =
+    let (thread_ptr, channels, mut current_tickets) =
         if let TransferMessage::Init {
             thread_ptr,
             channels,
@@ -89,72 +145,87 @@ extern "C" fn thread_entry_wrapper(mut transfer: Transfer) -> ! {
             current_tickets,
         } = unsafe { TransferMessage::unpack(transfer.data) }
         {
-            let tid = unsafe { (*thread_ptr).id };
-            (thread_ptr, channels, tid, current_tickets)
+            (thread_ptr, channels, current_tickets)
         } else {
             eprintln!("ERROR: thread_entry_wrapper esperaba mensaje Init");
             std::process::abort();
         };
 
     // Inicializar contextos para que las APIs funcionen
-    ThreadGlobalContext::init(tid, channels.clone());
-    crate::api_context::init_thread_context(tid, channels);
+    unsafe { enter(thread_ptr, transfer.context, channels) };
 
     // println!("[Hilo {}] inicializado correctamente", tid);
 
     loop {
         // Ejecutar un paso de la lógica del hilo 
         // Pasamos los tiquetes que recibimos del Runtime
-        let signal = unsafe {
-            let thread = &mut *thread_ptr;
-            thread.execute_step(current_tickets)
-        };
+        let signal = unsafe { (*thread_ptr).execute_step(current_tickets) };
 
         // println!("[Hilo {}] execute_step retornó: {:?}", tid, signal);
 
-        // Convertir la señal del hilo en una respuesta para el Runtime
-        let response = match signal {
-            ThreadSignal::Yield | ThreadSignal::Continue => {
-                // println!("[Hilo {}] preparando yield al runtime", tid);
-                ThreadResponse::Yield
-            }
-            ThreadSignal::Block => {
-                // println!("[Hilo {}] preparando block", tid);
-                ThreadResponse::Block
-            }
-            ThreadSignal::Exit => {
-                // println!("[Hilo {}] preparando exit", tid);
-                ThreadResponse::Exit
-            }
-            // Las demás señales se pasan directamente
-            ThreadSignal::Join(target_tid) => ThreadResponse::Join(target_tid),
-            ThreadSignal::MutexLock(mutex_addr) => ThreadResponse::MutexLock(mutex_addr),
-            ThreadSignal::MutexUnlock(mutex_addr) => ThreadResponse::MutexUnlock(mutex_addr),
-        };
+        if matches!(signal, ThreadSignal::Exit) {
+            crate::tls::run_destructors();
+        }
 
-        let is_exit = matches!(response, ThreadResponse::Exit);
-        let response_data = response.pack();
+        // Devolvemos el control (y la respuesta) al Runtime; volvemos cuando nos despierte
+        current_tickets = unsafe { park(thread_ptr, ThreadResponse::from(signal)) };
+    }
+}
 
-        // Devolvemos el control (y la respuesta) al Runtime
-        transfer = unsafe { transfer.context.resume(response_data) };
+/// instala el contexto del hilo (tid, canales, llaves) y guarda como volver al runtime
+/// y lo que habia antes, para reponerlo en `park`
+unsafe fn enter(thread_ptr: *mut MyThread, runtime: Context, channels: ThreadChannels) {
+    let thread = &mut *thread_ptr;
+    let global = ThreadGlobalContext::init(thread.id, channels.clone());
+    let saved = api_context::enter_thread(
+        thread_ptr,
+        thread.id,
+        channels,
+        std::mem::take(&mut thread.locals),
+    );
+    thread.link = Some(RuntimeLink {
+        context: runtime,
+        saved,
+        global,
+    });
+}
 
-        // Cuando volvemos, el runtime nos ha despertado
-        // println!("[Hilo {}] despertado por el runtime", tid);
+/// Devuelve el control al runtime con `response` y espera a que vuelva a
+/// despachar el hilo. Retorna los tiquetes del nuevo despacho.
+///
+/// Se usa al final de cada paso y tambien para suspender en medio de uno
+/// (`api_context::ctx_suspend`).
+pub(crate) unsafe fn park(thread_ptr: *mut MyThread, response: ThreadResponse) -> u32 {
+    let is_exit = matches!(response, ThreadResponse::Exit);
+    let thread = &mut *thread_ptr;
+    let tid = thread.id;
+    let link = thread.link.take().expect("el hilo no tiene enlace con el runtime");
+    thread.locals = api_context::leave_thread(link.saved);
+    ThreadGlobalContext::restore(link.global);
+
+    let transfer = link.context.resume(response.pack());
+
+    // Cuando volvemos, el runtime nos ha despertado
+    // println!("[Hilo {}] despertado por el runtime", tid);
+
+    if is_exit {
+        eprintln!("[Hilo {}] ERROR: runtime despertó un hilo terminado", tid);
+        std::process::abort();
+    }
 
-        if is_exit {
-            eprintln!("[Hilo {}] ERROR: runtime despertó un hilo terminado", tid);
-            std::process::abort();
+    // El Runtime nos envió un nuevo mensaje con el estado actualizado (incluyendo los tiquetes)
+    match TransferMessage::unpack(transfer.data) {
+        TransferMessage::Init {
+            channels,
+            current_tickets,
+            ..
+        } => {
+            enter(thread_ptr, transfer.context, channels);
+            current_tickets
         }
-
-        // El Runtime nos envió un nuevo mensaje con el estado actualizado (incluyendo los tiquetes)
-        // Desempacamos y actualizamos nuestros tiquetes por si cambiaron.
-        if let TransferMessage::Init { current_tickets: new_tickets, .. } =
-            unsafe { TransferMessage::unpack(transfer.data) }
-        {
-            current_tickets = new_tickets;
-        } else {
+        _ => {
             eprintln!("[Hilo {}] ERROR: esperaba mensaje Init al despertar", tid);
             std::process::abort();
         }
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,6 @@
 use crate::thread::{ThreadId, MyThread};
 use crate::channels::ThreadChannels;
+use crate::signals::ThreadSignal;
 
 /// tipo de mensaje que se pasa via Transfer.data
 #[repr(C)]
@@ -40,6 +41,7 @@ impl TransferMessage {
 #[derive(Debug)]
 pub enum ThreadResponse {
     Yield,
+    YieldEarly(u64),
     Block,
     Exit,
     Continue,
@@ -48,6 +50,22 @@ pub enum ThreadResponse {
     MutexUnlock(usize),
 }
 
+impl From<ThreadSignal> for ThreadResponse {
+    /// Convertir la señal del hilo en una respuesta para el Runtime
+    fn from(signal: ThreadSignal) -> Self {
+        match signal {
+            ThreadSignal::Yield | ThreadSignal::Continue => ThreadResponse::Yield,
+            ThreadSignal::Block => ThreadResponse::Block,
+            ThreadSignal::Exit => ThreadResponse::Exit,
+            // Las demás señales se pasan directamente
+            ThreadSignal::YieldEarly(used_ms) => ThreadResponse::YieldEarly(used_ms),
+            ThreadSignal::Join(target_tid) => ThreadResponse::Join(target_tid),
+            ThreadSignal::MutexLock(mutex_addr) => ThreadResponse::MutexLock(mutex_addr),
+            ThreadSignal::MutexUnlock(mutex_addr) => ThreadResponse::MutexUnlock(mutex_addr),
+        }
+    }
+}
+
 impl ThreadResponse {
     pub fn pack(self) -> usize {
         Box::into_raw(Box::new(self)) as usize
@@ -76,14 +94,16 @@ thread_local! {
 }
 
 impl ThreadGlobalContext {
-    /// inicializa el contexto global del hilo actual
-    pub fn init(tid: ThreadId, channels: ThreadChannels) {
-        GLOBAL_CTX.with(|ctx| {
-            *ctx.borrow_mut() = Some(ThreadGlobalContext {
-                tid,
-                channels,
-            });
-        });
+    /// Instala el contexto global del hilo que va a correr y devuelve el que
+    /// habia en este hilo del sistema, para reponerlo con `restore` cuando el
+    /// hilo devuelva el control (el despachador puede ser otro hilo verde).
+    pub fn init(tid: ThreadId, channels: ThreadChannels) -> Option<ThreadGlobalContext> {
+        GLOBAL_CTX.with(|ctx| ctx.borrow_mut().replace(ThreadGlobalContext { tid, channels }))
+    }
+
+    /// repone el contexto que estaba antes de `init`
+    pub fn restore(previous: Option<ThreadGlobalContext>) {
+        GLOBAL_CTX.with(|ctx| *ctx.borrow_mut() = previous);
     }
     
     /// obtiene una referencia al contexto global
@@ -0,0 +1,105 @@
+//! almacenamiento local por hilo verde (equivalente a pthread_key_*)
+//!
+//! Cada `MyThread` guarda sus valores en un `ThreadLocals`. El wrapper del hilo
+//! los instala en el slot activo al despertar y los recupera antes de devolver
+//! el control al runtime, asi cada hilo ve solo los suyos aunque todos compartan
+//! el mismo hilo del sistema operativo.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Se llama con el valor de la llave cuando el hilo termina
+pub type KeyDestructor = fn(Box<dyn Any + Send>);
+
+/// Llave creada con `my_key_create`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MyKey(u32);
+
+// indice = id de la llave; None = llave borrada
+static KEYS: Mutex<Vec<Option<Option<KeyDestructor>>>> = Mutex::new(Vec::new());
+
+/// Valores de las llaves de un hilo
+#[derive(Default)]
+pub struct ThreadLocals {
+    values: HashMap<MyKey, Box<dyn Any + Send>>,
+}
+
+impl ThreadLocals {
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+thread_local! {
+    // valores del hilo verde que esta corriendo (o del hilo principal)
+    static ACTIVE: RefCell<ThreadLocals> = RefCell::new(ThreadLocals::default());
+}
+
+fn key_destructor(key: MyKey) -> Option<Option<KeyDestructor>> {
+    let keys = KEYS.lock().unwrap_or_else(|e| e.into_inner());
+    keys.get(key.0 as usize).copied().flatten()
+}
+
+/// true si la llave fue creada y no se borro
+pub(crate) fn key_exists(key: MyKey) -> bool {
+    key_destructor(key).is_some()
+}
+
+/// Crea una llave nueva; `destructor` recibe el valor de cada hilo que termine con uno asignado
+pub fn key_create(destructor: Option<KeyDestructor>) -> MyKey {
+    let mut keys = KEYS.lock().unwrap_or_else(|e| e.into_inner());
+    keys.push(Some(destructor));
+    MyKey((keys.len() - 1) as u32)
+}
+
+/// Borra la llave; los valores que quedan no pasan por el destructor
+pub fn key_delete(key: MyKey) -> bool {
+    let mut keys = KEYS.lock().unwrap_or_else(|e| e.into_inner());
+    match keys.get_mut(key.0 as usize) {
+        Some(slot @ Some(_)) => {
+            *slot = None;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Asigna el valor del hilo actual; false si la llave no existe
+pub fn set_specific<T: Any + Send>(key: MyKey, value: T) -> bool {
+    if key_destructor(key).is_none() {
+        return false;
+    }
+    ACTIVE.with(|a| a.borrow_mut().values.insert(key, Box::new(value)));
+    true
+}
+
+/// Copia del valor del hilo actual (None si no tiene o es de otro tipo)
+pub fn get_specific<T: Any + Clone>(key: MyKey) -> Option<T> {
+    key_destructor(key)?;
+    ACTIVE.with(|a| a.borrow().values.get(&key)?.downcast_ref::<T>().cloned())
+}
+
+/// Quita el valor del hilo actual sin llamar al destructor
+pub fn take_specific<T: Any>(key: MyKey) -> Option<T> {
+    let value = ACTIVE.with(|a| a.borrow_mut().values.remove(&key))?;
+    value.downcast::<T>().ok().map(|v| *v)
+}
+
+/// instala los valores de un hilo y devuelve los que estaban activos
+pub(crate) fn swap_active(locals: ThreadLocals) -> ThreadLocals {
+    ACTIVE.with(|a| std::mem::replace(&mut *a.borrow_mut(), locals))
+}
+
+/// llama los destructores de los valores activos (el hilo esta terminando)
+pub(crate) fn run_destructors() {
+    let values = ACTIVE.with(|a| std::mem::take(&mut a.borrow_mut().values));
+    let mut values: Vec<_> = values.into_iter().collect();
+    values.sort_by_key(|(key, _)| *key);
+    for (key, value) in values {
+        if let Some(Some(destructor)) = key_destructor(key) {
+            destructor(value);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! registro de eventos del scheduler y exportacion en formato Chrome Trace Event
+//!
+//! El JSON generado se abre en chrome://tracing o en ui.perfetto.dev: cada
+//! ThreadId es una pista con el nombre del hilo, cada despacho es un bloque y
+//! yield/block/unblock/exit/traspaso de mutex son eventos instantaneos.
+
+use crate::thread::ThreadId;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+/// Tipo de evento registrado por el runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEventKind {
+    Dispatch,
+    Yield,
+    Block,
+    Unblock,
+    Exit,
+    /// el mutex paso del hilo `from` al hilo del evento
+    MutexHandoff { from: ThreadId },
+}
+
+impl TraceEventKind {
+    fn label(&self) -> &'static str {
+        match self {
+            TraceEventKind::Dispatch => "dispatch",
+            TraceEventKind::Yield => "yield",
+            TraceEventKind::Block => "block",
+            TraceEventKind::Unblock => "unblock",
+            TraceEventKind::Exit => "exit",
+            TraceEventKind::MutexHandoff { .. } => "mutex_handoff",
+        }
+    }
+}
+
+/// Un evento con el reloj simulado y el de pared (us desde que inicio el registro)
+#[derive(Debug, Clone)]
+pub struct TraceEvent {
+    pub tid: ThreadId,
+    pub kind: TraceEventKind,
+    pub now_ms: u64,
+    pub wall_us: u64,
+}
+
+/// Acumula eventos mientras esta habilitado
+#[derive(Debug)]
+pub struct SchedTracer {
+    enabled: bool,
+    start: Instant,
+    events: Vec<TraceEvent>,
+    names: BTreeMap<ThreadId, String>,
+}
+
+impl Default for SchedTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedTracer {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            start: Instant::now(),
+            events: Vec::new(),
+            names: BTreeMap::new(),
+        }
+    }
+
+    pub fn enable(&mut self) {
+        if !self.enabled {
+            self.enabled = true;
+            self.start = Instant::now();
+        }
+    }
+
+    pub fn disable(&mut self) {
+        self.enabled = false;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// guarda el nombre de la pista, se llama al crear cada hilo
+    pub fn name_thread(&mut self, tid: ThreadId, name: &str) {
+        self.names.insert(tid, name.to_string());
+    }
+
+    pub fn record(&mut self, tid: ThreadId, kind: TraceEventKind, now_ms: u64) {
+        if !self.enabled {
+            return;
+        }
+        let wall_us = self.start.elapsed().as_micros() as u64;
+        self.events.push(TraceEvent {
+            tid,
+            kind,
+            now_ms,
+            wall_us,
+        });
+    }
+
+    /// Serializa los eventos como Chrome Trace Event JSON.
+    ///
+    /// Un despacho abre un bloque ("B") que se cierra ("E") con el siguiente
+    /// yield/block/exit del mismo hilo.
+    pub fn to_chrome_json(&self) -> String {
+        let mut entries: Vec<String> = Vec::new();
+
+        for (tid, name) in &self.names {
+            entries.push(format!(
+                r#"{{"name":"thread_name","ph":"M","pid":1,"tid":{},"args":{{"name":"{}"}}}}"#,
+                tid,
+                escape_json(name)
+            ));
+        }
+
+        let mut running: Option<ThreadId> = None;
+        for ev in &self.events {
+            let mut args = format!(r#""now_ms":{}"#, ev.now_ms);
+            if let TraceEventKind::MutexHandoff { from } = ev.kind {
+                let _ = write!(args, r#","from":{}"#, from);
+            }
+
+            match ev.kind {
+                TraceEventKind::Dispatch => {
+                    entries.push(format!(
+                        r#"{{"name":"run","cat":"sched","ph":"B","pid":1,"tid":{},"ts":{},"args":{{{}}}}}"#,
+                        ev.tid, ev.wall_us, args
+                    ));
+                    running = Some(ev.tid);
+                }
+                _ => {
+                    let ends_run = matches!(
+                        ev.kind,
+                        TraceEventKind::Yield | TraceEventKind::Block | TraceEventKind::Exit
+                    );
+                    if ends_run && running == Some(ev.tid) {
+                        entries.push(format!(
+                            r#"{{"name":"run","cat":"sched","ph":"E","pid":1,"tid":{},"ts":{}}}"#,
+                            ev.tid, ev.wall_us
+                        ));
+                        running = None;
+                    }
+                    entries.push(format!(
+                        r#"{{"name":"{}","cat":"sched","ph":"i","s":"t","pid":1,"tid":{},"ts":{},"args":{{{}}}}}"#,
+                        ev.kind.label(),
+                        ev.tid,
+                        ev.wall_us,
+                        args
+                    ));
+                }
+            }
+        }
+
+        format!(
+            "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[\n{}\n]}}\n",
+            entries.join(",\n")
+        )
+    }
+
+    /// escribe el JSON en `path`
+    pub fn write_chrome_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_chrome_json())
+    }
+}
+
+pub(crate) fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,107 @@
+//! vigilancia de hilos que no ceden o que giran sin avanzar
+//!
+//! Los hilos son cooperativos: no hay expropiacion, asi que el runtime no
+//! puede quitarle la CPU a un paso que no retorna. Lo que si hace es medir en
+//! tiempo real cuanto dura cada despacho y marcar al hilo que pasa del umbral.
+//! Tambien detecta el livelock tipico de la simulacion: un hilo que se bloquea,
+//! lo despierta `unblock_all_threads` y se vuelve a bloquear sin avanzar.
+//! Los que esperan un fd en el reactor no cuentan: esperar E/S no es girar.
+
+use crate::thread::ThreadId;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// umbral por defecto para un despacho lento
+pub const SLOW_DISPATCH_MS: u64 = 100;
+/// despertares seguidos sin avanzar antes de marcar livelock
+pub const LIVELOCK_WAKEUPS: u32 = 50;
+
+/// Por que se marco un hilo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogFlag {
+    /// un despacho tardo `elapsed_ms` de tiempo real sin devolver el control
+    SlowDispatch { elapsed_ms: u64 },
+    /// `wakeups` despertares generales seguidos terminaron en otro bloqueo sin avanzar
+    Livelock { wakeups: u32 },
+}
+
+/// estado del watchdog que viaja con cada hilo
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct WatchState {
+    /// el hilo aviso que avanzo durante el paso (`ctx_progress`)
+    pub(crate) progressed: bool,
+    /// lo saco de bloqueados un despertar general, no uno dirigido
+    pub(crate) broadcast_woken: bool,
+    pub(crate) fruitless_wakeups: u32,
+}
+
+/// Umbrales y hilos marcados. `None` en un umbral apaga esa revision.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    pub slow_dispatch: Option<Duration>,
+    pub livelock_wakeups: Option<u32>,
+    flagged: BTreeMap<ThreadId, WatchdogFlag>,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            slow_dispatch: Some(Duration::from_millis(SLOW_DISPATCH_MS)),
+            livelock_wakeups: Some(LIVELOCK_WAKEUPS),
+            flagged: BTreeMap::new(),
+        }
+    }
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// el ultimo motivo por el que se marco cada hilo
+    pub fn flagged(&self) -> &BTreeMap<ThreadId, WatchdogFlag> {
+        &self.flagged
+    }
+
+    pub fn is_flagged(&self, tid: ThreadId) -> bool {
+        self.flagged.contains_key(&tid)
+    }
+
+    /// retorna el motivo si el despacho paso del umbral
+    pub(crate) fn check_dispatch(&mut self, tid: ThreadId, elapsed: Duration) -> Option<WatchdogFlag> {
+        if elapsed <= self.slow_dispatch? {
+            return None;
+        }
+        let flag = WatchdogFlag::SlowDispatch {
+            elapsed_ms: elapsed.as_millis() as u64,
+        };
+        self.flagged.insert(tid, flag);
+        Some(flag)
+    }
+
+    /// El hilo termino su paso; `blocked` si volvio a bloquearse por su cuenta.
+    /// Retorna el motivo cuando se completa una racha de despertares inutiles.
+    pub(crate) fn check_step(&mut self, tid: ThreadId, watch: &mut WatchState, blocked: bool) -> Option<WatchdogFlag> {
+        let fruitless = blocked && watch.broadcast_woken && !watch.progressed;
+        watch.progressed = false;
+        watch.broadcast_woken = false;
+        if !fruitless {
+            watch.fruitless_wakeups = 0;
+            return None;
+        }
+        watch.fruitless_wakeups = watch.fruitless_wakeups.saturating_add(1);
+        if watch.fruitless_wakeups < self.livelock_wakeups? {
+            return None;
+        }
+        // se avisa una vez por racha
+        let flag = WatchdogFlag::Livelock {
+            wakeups: std::mem::take(&mut watch.fruitless_wakeups),
+        };
+        self.flagged.insert(tid, flag);
+        Some(flag)
+    }
+
+    pub(crate) fn forget(&mut self, tid: ThreadId) {
+        self.flagged.remove(&tid);
+    }
+}
@@ -0,0 +1,165 @@
+//! tests del puente async/await sobre los hilos verdes
+
+use mypthreads::mypthreads_api::{
+    my_block_on, my_thread_spawn_future, runtime_add_observer, runtime_init, SchedulerParams,
+};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::{SchedulerType, ThreadState};
+use mypthreads::{async_channel, AsyncMutex, RuntimeEvent};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[test]
+fn test_pending_futures_block_only_their_thread() {
+    println!("\n=== TEST: Un future pendiente bloquea solo a su hilo ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(9);
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let shared = Arc::new(AsyncMutex::new(Vec::new()));
+    let (numbers_tx, mut numbers_rx) = async_channel::<u32>();
+    let (go_tx, mut go_rx) = async_channel::<()>();
+
+    // consumidor async: suma lo que llega hasta que se cierra el canal
+    let consumer_log = log.clone();
+    let consumer = rt.spawn(
+        "consumer",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            let total = my_block_on(async {
+                let mut total = 0;
+                while let Some(n) = numbers_rx.recv().await {
+                    total += n;
+                }
+                total
+            });
+            consumer_log.lock().unwrap().push(format!("total {}", total));
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+
+    // "a" se queda con el mutex esperando la señal; "b" espera el mutex
+    let (holder_shared, holder_log) = (shared.clone(), log.clone());
+    let holder = rt.spawn(
+        "holder",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            my_block_on(async {
+                let mut guard = holder_shared.lock().await;
+                go_rx.recv().await;
+                guard.push("a");
+                holder_log.lock().unwrap().push("a suelta".to_string());
+            });
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+    let waiter_shared = shared.clone();
+    let waiter = rt.spawn(
+        "waiter",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            my_block_on(async {
+                waiter_shared.lock().await.push("b");
+            });
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+
+    // productor comun: un numero por paso, luego la señal y cierra el canal
+    let mut next = 1;
+    let mut numbers_tx = Some(numbers_tx);
+    let mut go_tx = Some(go_tx);
+    rt.spawn(
+        "producer",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            if next <= 3 {
+                numbers_tx.as_ref().unwrap().send(next);
+                next += 1;
+                return ThreadSignal::Yield;
+            }
+            go_tx.take().unwrap().send(());
+            numbers_tx = None;
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+
+    rt.run(3);
+    assert_eq!(rt.threads[&consumer].state, ThreadState::Blocked, "espera el canal");
+    assert_eq!(rt.threads[&holder].state, ThreadState::Blocked, "espera la señal");
+    assert_eq!(rt.threads[&waiter].state, ThreadState::Blocked, "espera el mutex");
+    assert!(shared.try_lock().is_none());
+
+    rt.run(40);
+    for tid in [consumer, holder, waiter] {
+        assert_eq!(rt.threads[&tid].state, ThreadState::Terminated);
+    }
+    assert_eq!(*shared.try_lock().unwrap(), vec!["a", "b"]);
+    let log = log.lock().unwrap().clone();
+    println!("log: {:?}", log);
+    assert!(log.contains(&"total 6".to_string()));
+    assert!(log.contains(&"a suelta".to_string()));
+
+    println!("  Test pasado: los futures pendientes no frenan a los demas hilos!");
+}
+
+#[test]
+fn test_future_threads_and_main_block_on() {
+    println!("\n=== TEST: Hilos future, wakes desde otro hilo y block_on en el principal ===\n");
+
+    runtime_init();
+    // anota desde que hilo del sistema se despacha cada hilo verde
+    let drivers = Arc::new(Mutex::new(Vec::new()));
+    let sink = drivers.clone();
+    runtime_add_observer(move |_now: u64, event: &RuntimeEvent| {
+        if let RuntimeEvent::Dispatch { .. } = event {
+            sink.lock().unwrap().push(std::thread::current().id());
+        }
+    })
+    .unwrap();
+
+    let (tx, mut rx) = async_channel::<String>();
+    let (done_tx, mut done_rx) = async_channel::<String>();
+    let (side_tx, mut side_rx) = async_channel::<String>();
+
+    // el future espera un valor que manda un hilo del sistema
+    my_thread_spawn_future("relay", SchedulerParams::Lottery { tickets: 5 }, async move {
+        while let Some(text) = rx.recv().await {
+            side_tx.send(format!("copia: {}", text));
+            done_tx.send(format!("relay: {}", text));
+        }
+    })
+    .unwrap();
+
+    // otro hilo del sistema tambien espera con block_on, pero no corre el runtime
+    let os_waiter = std::thread::spawn(move || my_block_on(async { side_rx.recv().await }));
+
+    let os_thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        tx.send("desde otro hilo".to_string());
+    });
+
+    // el principal espera corriendo el runtime
+    let received = my_block_on(async { done_rx.recv().await });
+    os_thread.join().unwrap();
+    println!("recibido: {:?}", received);
+    assert_eq!(received.as_deref(), Some("relay: desde otro hilo"));
+    assert_eq!(os_waiter.join().unwrap().as_deref(), Some("copia: desde otro hilo"));
+
+    // se cerro el emisor: el relay termina y su canal tambien se cierra
+    assert_eq!(my_block_on(async { done_rx.recv().await }), None);
+    let main = std::thread::current().id();
+    let drivers = drivers.lock().unwrap().clone();
+    assert!(!drivers.is_empty());
+    assert!(drivers.iter().all(|id| *id == main), "solo el principal corre el runtime");
+
+    println!("  Test pasado: futures como hilos verdes y block_on desde el principal!");
+}
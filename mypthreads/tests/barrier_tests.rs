@@ -0,0 +1,105 @@
+//! tests de barreras, waitgroups y once
+
+use mypthreads::api_context::ctx_suspend;
+use mypthreads::mypthreads_api::{
+    my_barrier_init, my_barrier_wait, my_call_once, my_thread_create, my_waitgroup_add, my_waitgroup_done,
+    my_waitgroup_init, my_waitgroup_wait, runtime_init, runtime_stats_summary, MyOnce, SchedulerParams,
+};
+use mypthreads::signals::ThreadSignal;
+use mypthreads::MyThreadError;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_barrier_waitgroup_and_once() {
+    println!("\n=== TEST: Barrera, waitgroup y once ===\n");
+
+    runtime_init();
+    assert!(matches!(my_barrier_init(0), Err(MyThreadError::InvalidArgument(_))));
+
+    const WORKERS: usize = 3;
+    let barrier = Arc::new(my_barrier_init(WORKERS).unwrap());
+    let finished = Arc::new(my_waitgroup_init().unwrap());
+    let once = Arc::new(MyOnce::new());
+    let init_runs = Arc::new(AtomicU32::new(0));
+    let config = Arc::new(Mutex::new(None));
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    my_waitgroup_add(&finished, WORKERS).unwrap();
+    for worker in 0..WORKERS {
+        let (barrier, finished, once) = (barrier.clone(), finished.clone(), once.clone());
+        let (init_runs, config, log) = (init_runs.clone(), config.clone(), log.clone());
+        my_thread_create(
+            &format!("worker-{}", worker),
+            SchedulerParams::RoundRobin,
+            Box::new(move |_, _| {
+                // la inicializacion cede a mitad de camino: los demas deben esperarla
+                my_call_once(&once, || {
+                    init_runs.fetch_add(1, Ordering::Relaxed);
+                    ctx_suspend(ThreadSignal::Yield);
+                    *config.lock().unwrap() = Some(42);
+                })
+                .unwrap();
+                assert_eq!(*config.lock().unwrap(), Some(42), "once retorno antes de terminar");
+
+                for round in 0..2 {
+                    log.lock().unwrap().push(format!("llega {} {}", round, worker));
+                    // hilos que llegan en distinto orden
+                    for _ in 0..worker {
+                        ctx_suspend(ThreadSignal::Yield);
+                    }
+                    let serial = my_barrier_wait(&barrier).unwrap();
+                    log.lock().unwrap().push(format!("pasa {} {}", round, serial));
+                }
+                my_waitgroup_done(&finished).unwrap();
+                ThreadSignal::Exit
+            }),
+        )
+        .unwrap();
+    }
+
+    // el hilo principal corre el runtime hasta que todos terminen
+    my_waitgroup_wait(&finished).unwrap();
+    assert_eq!(my_waitgroup_done(&finished).unwrap_err(), MyThreadError::InvalidArgument("done sin tareas pendientes"));
+
+    let log = log.lock().unwrap().clone();
+    println!("log: {:?}", log);
+    assert_eq!(init_runs.load(Ordering::Relaxed), 1);
+    for round in 0..2 {
+        let first_pass = log.iter().position(|e| e.starts_with(&format!("pasa {}", round))).unwrap();
+        let arrivals = log.iter().filter(|e| e.starts_with(&format!("llega {}", round))).count();
+        let early = log[..first_pass].iter().filter(|e| e.starts_with(&format!("llega {}", round))).count();
+        assert_eq!((arrivals, early), (WORKERS, WORKERS), "nadie pasa antes de que lleguen todos");
+        let serial = log.iter().filter(|e| **e == format!("pasa {} true", round)).count();
+        assert_eq!(serial, 1, "un solo serial thread por ronda");
+    }
+    // los que esperan estan bloqueados, no girando
+    assert!(runtime_stats_summary().unwrap().blocked_ms.values().sum::<u64>() > 0);
+
+    // desde el principal, sin hilos que puedan terminar el trabajo
+    let stuck = my_waitgroup_init().unwrap();
+    my_waitgroup_add(&stuck, 1).unwrap();
+    assert_eq!(my_waitgroup_wait(&stuck), Err(MyThreadError::Deadlock));
+    assert_eq!(my_call_once(&once, || unreachable!()), Ok(()));
+
+    println!("  Test pasado: las primitivas bloquean por el runtime!");
+}
+
+#[test]
+fn test_call_once_retries_after_panic() {
+    println!("\n=== TEST: call_once se puede reintentar si init entra en panico ===\n");
+
+    let once = MyOnce::new();
+    let failed = std::panic::catch_unwind(|| {
+        let _ = my_call_once(&once, || panic!("init fallo"));
+    });
+    assert!(failed.is_err());
+
+    // no quedo marcado como corriendo: el siguiente lo corre de nuevo
+    let runs = AtomicU32::new(0);
+    assert_eq!(my_call_once(&once, || { runs.fetch_add(1, Ordering::Relaxed); }), Ok(()));
+    assert_eq!(my_call_once(&once, || { runs.fetch_add(1, Ordering::Relaxed); }), Ok(()));
+    assert_eq!(runs.load(Ordering::Relaxed), 1);
+
+    println!("  Test pasado: un init que falla no deja el once trabado!");
+}
@@ -0,0 +1,81 @@
+//! tests del pool de tareas bloqueantes
+
+use mypthreads::mypthreads_api::my_spawn_blocking;
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::{SchedulerType, ThreadState};
+use mypthreads::MyThreadError;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_spawn_blocking_does_not_stall_runtime() {
+    println!("\n=== TEST: my_spawn_blocking no frena al runtime ===\n");
+
+    // desde el principal solo espera el resultado
+    assert_eq!(my_spawn_blocking(|| 6 * 7), Ok(42));
+    assert_eq!(my_spawn_blocking(|| -> u32 { panic!("tarea rota") }), Err(MyThreadError::Panicked));
+    assert_eq!(MyThreadError::Panicked.errno(), libc::EIO);
+
+    let mut rt = ThreadRuntimeV2::with_seed(5);
+    let finished = Arc::new(AtomicBool::new(false));
+    let ticks = Arc::new(AtomicU32::new(0));
+    let results = Arc::new(Mutex::new(Vec::new()));
+
+    let (sleeper_finished, sleeper_ticks, sleeper_results) = (finished.clone(), ticks.clone(), results.clone());
+    let sleeper = rt.spawn(
+        "sleeper",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            let value = my_spawn_blocking(|| {
+                std::thread::sleep(Duration::from_millis(50));
+                String::from("despierto")
+            });
+            let failed = my_spawn_blocking(|| -> u32 { panic!("tarea rota") });
+            let mut results = sleeper_results.lock().unwrap();
+            results.push(format!("{:?}", value));
+            results.push(format!("{:?}", failed));
+            results.push(format!("ticks durante la espera: {}", sleeper_ticks.load(Ordering::Relaxed) > 0));
+            sleeper_finished.store(true, Ordering::Relaxed);
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+
+    // sigue corriendo mientras el otro duerme en el pool
+    let (ticker_finished, ticker_ticks) = (finished.clone(), ticks.clone());
+    let ticker = rt.spawn(
+        "ticker",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            if ticker_finished.load(Ordering::Relaxed) {
+                return ThreadSignal::Exit;
+            }
+            ticker_ticks.fetch_add(1, Ordering::Relaxed);
+            ThreadSignal::Yield
+        }),
+        1,
+        None,
+    );
+
+    rt.run_once();
+    assert_eq!(rt.threads[&sleeper].state, ThreadState::Blocked, "espera en el reactor");
+
+    let start = Instant::now();
+    while rt.threads[&ticker].state != ThreadState::Terminated && start.elapsed() < Duration::from_secs(5) {
+        rt.run_once();
+    }
+    assert_eq!(rt.threads[&sleeper].state, ThreadState::Terminated);
+    assert_eq!(rt.threads[&ticker].state, ThreadState::Terminated);
+
+    let results = results.lock().unwrap().clone();
+    println!("resultados: {:?}", results);
+    assert_eq!(
+        results,
+        vec!["Ok(\"despierto\")", "Err(Panicked)", "ticks durante la espera: true"]
+    );
+
+    println!("  Test pasado: el trabajo bloqueante corre en el pool!");
+}
@@ -0,0 +1,59 @@
+//! tests de corrutinas y generadores
+
+use mypthreads::{Coroutine, CoroutineState};
+use std::panic::{self, AssertUnwindSafe};
+
+#[test]
+fn test_coroutines_and_generators() {
+    println!("\n=== TEST: Corrutinas con valores tipados y generadores ===\n");
+
+    // generador: se recorre como iterador, sin crear un hilo
+    let fibonacci: Coroutine<u64> = Coroutine::new(|yielder, ()| {
+        let (mut a, mut b) = (0, 1);
+        loop {
+            yielder.suspend(a);
+            (a, b) = (b, a + b);
+        }
+    });
+    let first: Vec<u64> = fibonacci.take(10).collect();
+    assert_eq!(first, vec![0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+
+    // corrutina que recibe numeros, entrega la suma parcial y termina con un resumen
+    let mut adder: Coroutine<i32, i32, String> = Coroutine::new(|yielder, mut next| {
+        let mut total = 0;
+        let mut count = 0;
+        while next != 0 {
+            total += next;
+            count += 1;
+            next = yielder.suspend(total);
+        }
+        format!("{} numeros, suma {}", count, total)
+    });
+    assert_eq!(adder.resume(5), CoroutineState::Yielded(5));
+    assert_eq!(adder.resume(10), CoroutineState::Yielded(15));
+    assert!(!adder.is_done());
+    assert_eq!(adder.resume(0), CoroutineState::Complete("2 numeros, suma 15".to_string()));
+    assert!(adder.is_done());
+
+    // un generador finito se acaba solo
+    let countdown: Coroutine<u32, (), &str> = Coroutine::new(|yielder, ()| {
+        for n in (1..=3).rev() {
+            yielder.suspend(n);
+        }
+        "despegue"
+    });
+    assert_eq!(countdown.collect::<Vec<_>>(), vec![3, 2, 1]);
+
+    // el panico del cuerpo sale por resume y la corrutina queda terminada
+    let mut broken: Coroutine<(), (), ()> = Coroutine::new(|yielder, ()| {
+        yielder.suspend(());
+        panic!("cuerpo roto");
+    });
+    assert_eq!(broken.resume(()), CoroutineState::Yielded(()));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| broken.resume(())));
+    assert!(result.is_err());
+    assert!(broken.is_done());
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| broken.resume(()))).is_err(), "ya termino");
+
+    println!("  Test pasado: las corrutinas intercambian valores tipados!");
+}
@@ -0,0 +1,96 @@
+//! tests de los errores de la API (MyThreadError y sus codigos errno)
+
+use mypthreads::channels::SimpleMutex;
+use mypthreads::error::{EBUSY, EDEADLK, EINVAL, EPERM, ESRCH};
+use mypthreads::mypthreads_api::{
+    my_currency_fund, my_mutex_destroy, my_mutex_init, my_mutex_lock, my_mutex_trylock, my_mutex_unlock,
+    my_thread_chsched, my_thread_create, my_thread_detach, my_thread_stats, my_thread_yield_early, run_simulation,
+    runtime_init, SchedulerParams,
+};
+use mypthreads::signals::ThreadSignal;
+use mypthreads::MyThreadError;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_api_returns_posix_like_errors() {
+    println!("\n=== TEST: Errores de la API ===\n");
+
+    // antes de runtime_init no hay panico, hay error
+    let early = my_thread_create("early", SchedulerParams::RoundRobin, Box::new(|_, _| ThreadSignal::Exit));
+    assert_eq!(early.unwrap_err(), MyThreadError::NotInitialized);
+    assert_eq!(MyThreadError::NotInitialized.errno(), EINVAL);
+    runtime_init();
+
+    // hilos inexistentes y parametros invalidos
+    assert_eq!(my_thread_detach(999), Err(MyThreadError::NoSuchThread(999)));
+    assert_eq!(my_thread_detach(999).unwrap_err().errno(), ESRCH);
+    assert_eq!(my_thread_chsched(999, SchedulerParams::RoundRobin).unwrap_err().errno(), ESRCH);
+    assert_eq!(my_thread_stats(999).unwrap_err().errno(), ESRCH);
+    assert_eq!(my_currency_fund(999, 10).unwrap_err().errno(), EINVAL);
+    assert_eq!(my_thread_yield_early(0).unwrap_err().errno(), EINVAL);
+    assert_eq!(my_thread_yield_early(3), Ok(ThreadSignal::YieldEarly(3)));
+
+    let zero_tickets = my_thread_create("t", SchedulerParams::Lottery { tickets: 0 }, Box::new(|_, _| ThreadSignal::Exit));
+    assert_eq!(zero_tickets.unwrap_err().errno(), EINVAL);
+
+    let tid = my_thread_create("worker", SchedulerParams::RoundRobin, Box::new(|_, _| ThreadSignal::Exit)).unwrap();
+    let bad_currency = SchedulerParams::LotteryFunded { currency: 42, tickets: 1 };
+    assert_eq!(my_thread_chsched(tid, bad_currency).unwrap_err().errno(), EINVAL);
+    assert_eq!(my_thread_detach(tid), Ok(()));
+    assert_eq!(my_thread_detach(tid).unwrap_err().errno(), EINVAL, "ya era detached");
+
+    // mutex desde el hilo principal
+    let mtx: &'static _ = Box::leak(Box::new(my_mutex_init().unwrap()));
+    assert_eq!(my_mutex_lock(mtx), Ok(ThreadSignal::Continue));
+    assert_eq!(my_mutex_lock(mtx).unwrap_err().errno(), EDEADLK, "volver a tomarlo");
+    assert_eq!(my_mutex_trylock(mtx).unwrap_err().errno(), EBUSY);
+
+    // un hilo que libera un mutex ajeno recibe EPERM; uno que lo pide se bloquea
+    let results: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let log = results.clone();
+    let mut step = 0;
+    my_thread_create(
+        "contender",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            step += 1;
+            match step {
+                1 => {
+                    assert_eq!(my_mutex_unlock(mtx).unwrap_err().errno(), EPERM);
+                    let signal = my_mutex_lock(mtx).unwrap();
+                    assert!(matches!(signal, ThreadSignal::MutexLock(_)), "debe bloquearse");
+                    signal
+                }
+                2 => {
+                    log.lock().unwrap().push("adquirido".into());
+                    my_mutex_unlock(mtx).unwrap()
+                }
+                _ => ThreadSignal::Exit,
+            }
+        }),
+    )
+    .unwrap();
+
+    run_simulation(5).unwrap();
+    assert!(results.lock().unwrap().is_empty(), "sigue esperando al hilo principal");
+    assert_eq!(my_mutex_unlock(mtx), Ok(ThreadSignal::Continue));
+    run_simulation(5).unwrap();
+    assert_eq!(*results.lock().unwrap(), vec!["adquirido".to_string()]);
+    assert_eq!(my_mutex_unlock(mtx).unwrap_err().errno(), EPERM, "ya no es el dueño");
+
+    // destruir un mutex tomado
+    let mut local = my_mutex_init().unwrap();
+    my_mutex_trylock(&local).unwrap();
+    assert_eq!(my_mutex_destroy(&mut local).unwrap_err().errno(), EBUSY);
+    my_mutex_unlock(&local).unwrap();
+    assert_eq!(my_mutex_destroy(&mut local), Ok(()));
+
+    // el mutex interno tampoco entra en panico
+    let raw = SimpleMutex::new();
+    assert!(raw.try_lock(1));
+    assert_eq!(raw.unlock(2), Err(MyThreadError::NotOwner));
+    assert_eq!(raw.unlock(1), Ok(None));
+
+    println!("  {}", MyThreadError::NoSuchThread(7));
+    println!("  Test pasado: errores de la API!");
+}
@@ -0,0 +1,173 @@
+//! tests del explorador de intercalaciones
+
+use mypthreads::channels::SimpleMutex;
+use mypthreads::explore::Explorer;
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::SchedulerType;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// dos hilos que leen el contador, ceden y escriben lectura + 1 (sin lock)
+fn lost_update(rt: &mut ThreadRuntimeV2) -> impl FnOnce(&ThreadRuntimeV2) -> Result<(), String> {
+    let counter = Arc::new(AtomicU32::new(0));
+    for name in ["A", "B"] {
+        let counter = counter.clone();
+        let mut read: Option<u32> = None;
+        rt.spawn(
+            name,
+            SchedulerType::RoundRobin,
+            Box::new(move |_, _| match read {
+                None => {
+                    read = Some(counter.load(Ordering::SeqCst));
+                    ThreadSignal::Yield
+                }
+                Some(value) => {
+                    counter.store(value + 1, Ordering::SeqCst);
+                    ThreadSignal::Exit
+                }
+            }),
+            1,
+            None,
+        );
+    }
+    move |_| match counter.load(Ordering::SeqCst) {
+        2 => Ok(()),
+        n => Err(format!("contador = {}, se esperaba 2", n)),
+    }
+}
+
+/// lo mismo pero la lectura y escritura van dentro de un mutex
+fn locked_update(rt: &mut ThreadRuntimeV2) -> impl FnOnce(&ThreadRuntimeV2) -> Result<(), String> {
+    let counter = Arc::new(AtomicU32::new(0));
+    let mutex = Arc::new(SimpleMutex::new());
+    let addr = Arc::as_ptr(&mutex) as usize;
+    for name in ["A", "B"] {
+        let counter = counter.clone();
+        let mutex = mutex.clone();
+        let mut step = 0;
+        let mut read = 0;
+        rt.spawn(
+            name,
+            SchedulerType::RoundRobin,
+            Box::new(move |_, _| {
+                let _keep_alive = &mutex;
+                step += 1;
+                match step {
+                    1 => ThreadSignal::MutexLock(addr),
+                    2 => {
+                        read = counter.load(Ordering::SeqCst);
+                        ThreadSignal::Yield
+                    }
+                    3 => {
+                        counter.store(read + 1, Ordering::SeqCst);
+                        ThreadSignal::MutexUnlock(addr)
+                    }
+                    _ => ThreadSignal::Exit,
+                }
+            }),
+            1,
+            None,
+        );
+    }
+    move |_| match counter.load(Ordering::SeqCst) {
+        2 => Ok(()),
+        n => Err(format!("contador = {}, se esperaba 2", n)),
+    }
+}
+
+#[test]
+fn test_exhaustive_finds_lost_update() {
+    println!("\n=== TEST: Búsqueda exhaustiva encuentra la actualización perdida ===\n");
+
+    let report = Explorer::exhaustive(100).run(lost_update);
+    let failure = report.failure.expect("debe encontrar la intercalación mala");
+    println!("{}", failure);
+
+    assert_eq!(failure.message, "contador = 1, se esperaba 2");
+    assert_eq!(failure.schedule.len(), 4);
+    assert_ne!(failure.schedule[0], failure.schedule[1], "ambos leen antes de escribir");
+    assert!(failure.to_string().contains("hilo 1 (A)"));
+
+    // el cronograma reportado se puede reproducir
+    let mut rt = ThreadRuntimeV2::with_seed(0);
+    let check = lost_update(&mut rt);
+    rt.start_replay(failure.recording.clone());
+    rt.run(10);
+    assert!(rt.replay.divergence().is_none());
+    assert!(check(&rt).is_err(), "la reproducción repite el fallo");
+
+    println!("  Test pasado: se encontró y reprodujo el cronograma!");
+}
+
+#[test]
+fn test_exhaustive_proves_mutex_exclusion() {
+    println!("\n=== TEST: Exclusión mutua en todas las intercalaciones ===\n");
+
+    let report = Explorer::exhaustive(1000).run(locked_update);
+    println!("  corridas: {}", report.runs);
+
+    assert!(report.failure.is_none(), "{}", report.failure.unwrap());
+    assert!(report.complete, "se cubrieron todas las intercalaciones");
+    assert!(report.runs > 1);
+
+    println!("  Test pasado: el mutex protege el contador!");
+}
+
+#[test]
+fn test_pct_finds_lost_update() {
+    println!("\n=== TEST: PCT encuentra la actualización perdida ===\n");
+
+    let report = Explorer::pct(50, 2, 7).max_steps(20).run(lost_update);
+    let failure = report.failure.expect("PCT debe encontrar el fallo");
+    println!("{}", failure);
+    assert!(report.runs <= 50);
+
+    // con el limite por defecto (1000 pasos) los puntos de cambio caen en
+    // los 4 pasos que mide la primera corrida, no en pasos que nunca llegan
+    let report = Explorer::pct(10, 2, 7).run(lost_update);
+    println!("  sin max_steps: fallo en la corrida {}", report.runs);
+    assert!(report.failure.is_some(), "PCT debe encontrar el fallo sin ajustar max_steps");
+
+    println!("  Test pasado: PCT encontró el cronograma!");
+}
+
+#[test]
+fn test_explorer_reports_deadlock() {
+    println!("\n=== TEST: Deadlock por orden de locks ===\n");
+
+    let report = Explorer::exhaustive(100).run(|rt: &mut ThreadRuntimeV2| {
+        let first = Arc::new(SimpleMutex::new());
+        let second = Arc::new(SimpleMutex::new());
+        let pairs = [
+            ("AB", first.clone(), second.clone()),
+            ("BA", second.clone(), first.clone()),
+        ];
+        for (name, a, b) in pairs {
+            let mut step = 0;
+            rt.spawn(
+                name,
+                SchedulerType::RoundRobin,
+                Box::new(move |_, _| {
+                    step += 1;
+                    match step {
+                        1 => ThreadSignal::MutexLock(Arc::as_ptr(&a) as usize),
+                        2 => ThreadSignal::MutexLock(Arc::as_ptr(&b) as usize),
+                        3 => ThreadSignal::MutexUnlock(Arc::as_ptr(&b) as usize),
+                        4 => ThreadSignal::MutexUnlock(Arc::as_ptr(&a) as usize),
+                        _ => ThreadSignal::Exit,
+                    }
+                }),
+                1,
+                None,
+            );
+        }
+        |_: &ThreadRuntimeV2| Ok(())
+    });
+
+    let failure = report.failure.expect("debe encontrar el deadlock");
+    println!("{}", failure);
+    assert!(failure.message.starts_with("deadlock"));
+
+    println!("  Test pasado: el deadlock se detecta!");
+}
@@ -0,0 +1,165 @@
+//! tests de la capa C (se llaman las funciones extern "C" desde Rust)
+//!
+//! Un solo test: todas las funciones usan el runtime global.
+
+use mypthreads::ffi::*;
+use mypthreads::mypthreads_api::{runtime_add_observer, runtime_snapshot};
+use mypthreads::observer::RuntimeEvent;
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+static mut COUNTER_MUTEX: my_pthread_mutex_t = my_pthread_mutex_t { inner: ptr::null_mut() };
+static mut COUNTER: u64 = 0;
+
+static mut QUEUE_MUTEX: my_pthread_mutex_t = my_pthread_mutex_t { inner: ptr::null_mut() };
+static mut QUEUE_COND: my_pthread_cond_t = my_pthread_cond_t { inner: ptr::null_mut() };
+static mut QUEUE_READY: bool = false;
+
+extern "C" fn double_arg(arg: *mut c_void) -> *mut c_void {
+    (arg as usize * 2) as *mut c_void
+}
+
+extern "C" fn increment(_arg: *mut c_void) -> *mut c_void {
+    for _ in 0..5 {
+        unsafe {
+            assert_eq!(my_pthread_mutex_lock(&raw mut COUNTER_MUTEX), 0);
+            let seen = COUNTER;
+            // cede con el mutex tomado: los demas deben bloquearse en lock
+            my_pthread_yield();
+            COUNTER = seen + 1;
+            assert_eq!(my_pthread_mutex_unlock(&raw mut COUNTER_MUTEX), 0);
+        }
+        my_pthread_yield();
+    }
+    ptr::null_mut()
+}
+
+extern "C" fn consumer(_arg: *mut c_void) -> *mut c_void {
+    unsafe {
+        my_pthread_mutex_lock(&raw mut QUEUE_MUTEX);
+        while !QUEUE_READY {
+            assert_eq!(my_pthread_cond_wait(&raw mut QUEUE_COND, &raw mut QUEUE_MUTEX), 0);
+        }
+        my_pthread_mutex_unlock(&raw mut QUEUE_MUTEX);
+    }
+    7 as *mut c_void
+}
+
+extern "C" fn producer(_arg: *mut c_void) -> *mut c_void {
+    // deja que el consumidor llegue primero a cond_wait
+    my_pthread_yield();
+    unsafe {
+        my_pthread_mutex_lock(&raw mut QUEUE_MUTEX);
+        QUEUE_READY = true;
+        my_pthread_cond_signal(&raw mut QUEUE_COND);
+        my_pthread_mutex_unlock(&raw mut QUEUE_MUTEX);
+    }
+    ptr::null_mut()
+}
+
+extern "C" fn self_join(_arg: *mut c_void) -> *mut c_void {
+    my_pthread_join(my_pthread_self(), ptr::null_mut()) as usize as *mut c_void
+}
+
+#[test]
+fn test_pthread_style_api() {
+    println!("\n=== TEST: API estilo pthreads ===\n");
+
+    my_pthread_runtime_init();
+    assert_eq!(my_pthread_runtime_seed(3), 0);
+    assert_eq!(my_pthread_self(), 0, "el hilo principal es 0");
+
+    // create + join con valor de retorno
+    let mut t: my_pthread_t = 0;
+    assert_eq!(my_pthread_create(&mut t, ptr::null(), Some(double_arg), 21 as *mut c_void), 0);
+    let mut ret: *mut c_void = ptr::null_mut();
+    assert_eq!(my_pthread_join(t, &mut ret), 0);
+    assert_eq!(ret as usize, 42);
+    assert_eq!(my_pthread_join(t, &mut ret), MY_ESRCH, "un hilo solo se puede unir una vez");
+    println!("  join devolvio {}", ret as usize);
+
+    // atributos invalidos
+    let bad = my_pthread_attr_t { policy: 9, tickets: 0, deadline: 0 };
+    assert_eq!(my_pthread_create(&mut t, &bad, Some(double_arg), ptr::null_mut()), MY_EINVAL);
+    assert_eq!(my_pthread_create(&mut t, ptr::null(), None, ptr::null_mut()), MY_EINVAL);
+
+    // mutex compartido entre hilos con distintas politicas
+    let lottery = my_pthread_attr_t { policy: MY_SCHED_LOTTERY, tickets: 5, deadline: 0 };
+    let mut workers = [0; 3];
+    let spawned = Arc::new(Mutex::new(Vec::new()));
+    let log = spawned.clone();
+    runtime_add_observer(move |_, event: &RuntimeEvent| {
+        if let RuntimeEvent::Spawn { tid, name } = event {
+            log.lock().unwrap().push((*tid, name.clone()));
+        }
+    })
+    .unwrap();
+    assert_eq!(my_pthread_create(&mut workers[0], ptr::null(), Some(increment), ptr::null_mut()), 0);
+    assert_eq!(my_pthread_create(&mut workers[1], &lottery, Some(increment), ptr::null_mut()), 0);
+    assert_eq!(my_pthread_create(&mut workers[2], ptr::null(), Some(increment), ptr::null_mut()), 0);
+    let snap = runtime_snapshot().unwrap();
+    let spawned = spawned.lock().unwrap().clone();
+    assert_eq!(spawned.len(), 3);
+    for (w, (tid, spawn_name)) in workers.into_iter().zip(spawned) {
+        let name = &snap.threads.iter().find(|t| t.id == w).unwrap().name;
+        assert_eq!(*name, format!("pthread-{}", w), "cada hilo de C lleva su tid en el nombre");
+        assert_eq!((tid, spawn_name), (w, name.clone()), "los observadores ven el mismo nombre");
+    }
+    for w in workers {
+        assert_eq!(my_pthread_join(w, ptr::null_mut()), 0);
+    }
+    assert_eq!(unsafe { COUNTER }, 15, "ningun incremento se pierde");
+    println!("  contador protegido = {}", unsafe { COUNTER });
+
+    // el hilo principal tambien puede usar el mutex
+    assert_eq!(my_pthread_mutex_lock(&raw mut COUNTER_MUTEX), 0);
+    assert_eq!(my_pthread_mutex_trylock(&raw mut COUNTER_MUTEX), MY_EBUSY);
+    assert_eq!(my_pthread_mutex_destroy(&raw mut COUNTER_MUTEX), MY_EBUSY);
+    assert_eq!(my_pthread_mutex_unlock(&raw mut COUNTER_MUTEX), 0);
+    assert_eq!(my_pthread_mutex_unlock(&raw mut COUNTER_MUTEX), MY_EPERM);
+    assert_eq!(my_pthread_mutex_destroy(&raw mut COUNTER_MUTEX), 0);
+
+    // variable de condicion
+    let mut c: my_pthread_t = 0;
+    let mut p: my_pthread_t = 0;
+    assert_eq!(my_pthread_create(&mut c, ptr::null(), Some(consumer), ptr::null_mut()), 0);
+    assert_eq!(my_pthread_create(&mut p, ptr::null(), Some(producer), ptr::null_mut()), 0);
+    assert_eq!(my_pthread_join(c, &mut ret), 0);
+    assert_eq!(ret as usize, 7);
+    assert_eq!(my_pthread_join(p, ptr::null_mut()), 0);
+    assert_eq!(my_pthread_cond_destroy(&raw mut QUEUE_COND), 0);
+    println!("  consumidor desperto por cond_signal");
+
+    // join a si mismo, detach
+    assert_eq!(my_pthread_create(&mut t, ptr::null(), Some(self_join), ptr::null_mut()), 0);
+    assert_eq!(my_pthread_join(t, &mut ret), 0);
+    assert_eq!(ret as usize as i32, MY_EDEADLK);
+
+    assert_eq!(my_pthread_create(&mut t, ptr::null(), Some(double_arg), ptr::null_mut()), 0);
+    assert_eq!(my_pthread_detach(t), 0);
+    assert_eq!(my_pthread_detach(t), MY_EINVAL);
+    assert_eq!(my_pthread_join(t, ptr::null_mut()), MY_EINVAL);
+    assert_eq!(my_pthread_runtime_run(100), 0, "no quedan hilos vivos");
+    assert_eq!(my_pthread_detach(t), MY_ESRCH, "un hilo detached se libera al terminar");
+
+    // tipos de mutex
+    let mut attr = my_pthread_mutexattr_t { kind: -1 };
+    let mut kind = -1;
+    assert_eq!(my_pthread_mutexattr_init(&mut attr), 0);
+    assert_eq!(my_pthread_mutexattr_gettype(&attr, &mut kind), 0);
+    assert_eq!(kind, MY_PTHREAD_MUTEX_ERRORCHECK);
+    assert_eq!(my_pthread_mutexattr_settype(&mut attr, 42), MY_EINVAL);
+    assert_eq!(my_pthread_mutexattr_settype(&mut attr, MY_PTHREAD_MUTEX_RECURSIVE), 0);
+    let mut recursive = my_pthread_mutex_t { inner: ptr::null_mut() };
+    assert_eq!(my_pthread_mutex_init(&mut recursive, &attr), 0);
+    assert_eq!(my_pthread_mutex_lock(&mut recursive), 0);
+    assert_eq!(my_pthread_mutex_lock(&mut recursive), 0, "el dueño lo vuelve a tomar");
+    assert_eq!(my_pthread_mutex_unlock(&mut recursive), 0);
+    assert_eq!(my_pthread_mutex_destroy(&mut recursive), MY_EBUSY, "falta un unlock");
+    assert_eq!(my_pthread_mutex_unlock(&mut recursive), 0);
+    assert_eq!(my_pthread_mutex_unlock(&mut recursive), MY_EPERM);
+    assert_eq!(my_pthread_mutex_destroy(&mut recursive), 0);
+
+    println!("  Test pasado: API estilo pthreads!");
+}
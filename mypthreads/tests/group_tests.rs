@@ -0,0 +1,262 @@
+//! tests de los grupos de hilos y la cancelacion
+
+use mypthreads::channels::{SimpleMutex, UNLOCKED};
+use mypthreads::mypthreads_api::{
+    my_group_cancel, my_group_chsched, my_group_create, my_group_join, my_group_members, my_group_stats,
+    my_thread_cancel, my_thread_create, my_thread_create_in_group, run_simulation, runtime_init,
+    SchedulerParams,
+};
+use mypthreads::MyThreadError;
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::{SchedulerType, ThreadState};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_group_membership_sched_and_stats() {
+    println!("\n=== TEST: Miembros, chsched y estadisticas por grupo ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let cars = rt.create_group("cars");
+    let boats = rt.create_group("boats");
+
+    let mut tids = Vec::new();
+    for i in 0..3 {
+        let mut steps = 0;
+        let tid = rt.spawn(
+            format!("car-{}", i),
+            SchedulerType::RoundRobin,
+            Box::new(move |_, _| {
+                steps += 1;
+                if steps == 2 {
+                    ThreadSignal::Exit
+                } else {
+                    ThreadSignal::Yield
+                }
+            }),
+            1,
+            None,
+        );
+        assert!(rt.set_group(tid, Some(cars)));
+        tids.push(tid);
+    }
+    let boat = rt.spawn("boat", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Yield), 1, None);
+    assert!(rt.set_group(boat, Some(boats)));
+    assert!(!rt.set_group(boat, Some(99)), "el grupo debe existir");
+
+    assert_eq!(rt.group_members(cars), tids);
+    assert_eq!(rt.group_members(boats), vec![boat]);
+    assert_eq!(rt.groups.get(cars).unwrap().name, "cars");
+
+    // un cambio de planificador para toda la clase
+    assert_eq!(rt.set_group_sched_params(cars, SchedulerType::Lottery, 20, None, None), 3);
+    for tid in &tids {
+        let t = &rt.threads[tid];
+        assert_eq!((t.sched_type, t.tickets), (SchedulerType::Lottery, 20));
+    }
+    assert_eq!(rt.threads[&boat].sched_type, SchedulerType::RoundRobin);
+
+    // uno se libera solo al terminar, pero sigue contando en su grupo
+    rt.threads.get_mut(&tids[0]).unwrap().detached = true;
+    rt.run(10);
+    assert!(!rt.threads.contains_key(&tids[0]));
+    // del liberado solo quedan sus contadores sumados
+    let exited = &rt.groups.get(cars).unwrap().exited;
+    assert_eq!((exited.threads, exited.dispatches), (1, 2));
+    assert!(rt.group_live_members(cars).is_empty(), "los carros terminaron");
+    assert_eq!(rt.group_members(cars), tids[1..], "los terminados sin liberar siguen listados");
+    assert_eq!(rt.set_group_sched_params(cars, SchedulerType::RoundRobin, 1, None, None), 0);
+
+    let car_stats = rt.group_stats(cars).unwrap();
+    let boat_stats = rt.group_stats(boats).unwrap();
+    println!("  carros: {} despachos, barcos: {}", car_stats.dispatches, boat_stats.dispatches);
+    assert_eq!(car_stats.threads, 3);
+    assert_eq!(car_stats.dispatches, 6, "dos pasos por carro");
+    // el resumen global tambien cuenta al carro liberado
+    assert_eq!(car_stats.dispatches + boat_stats.dispatches, rt.stats_summary().dispatches);
+    assert_eq!(rt.stats_summary().threads, 4);
+    assert!(rt.group_stats(99).is_none());
+
+    println!("  Test pasado: operaciones por grupo!");
+}
+
+#[test]
+fn test_cancel_ready_and_blocked_threads() {
+    println!("\n=== TEST: Cancelar hilos listos y bloqueados ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let group = rt.create_group("workers");
+    let mutex: &'static SimpleMutex = Box::leak(Box::new(SimpleMutex::new()));
+    let addr = mutex as *const SimpleMutex as usize;
+
+    // dueño del mutex: lo toma y luego cede para siempre
+    let mut owner_step = 0;
+    let owner = rt.spawn(
+        "owner",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            owner_step += 1;
+            match owner_step {
+                1 => ThreadSignal::MutexLock(addr),
+                2 => ThreadSignal::Yield,
+                3 => ThreadSignal::MutexUnlock(addr),
+                _ => ThreadSignal::Exit,
+            }
+        }),
+        1,
+        None,
+    );
+    // espera el mutex y sera cancelado antes de recibirlo
+    let waiter = rt.spawn(
+        "waiter",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| ThreadSignal::MutexLock(addr)),
+        1,
+        None,
+    );
+    // listo, nunca llega a correr
+    let idle = rt.spawn("idle", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Yield), 1, None);
+    // espera a `waiter` con join
+    let mut joined = false;
+    let joiner = rt.spawn(
+        "joiner",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            if joined {
+                ThreadSignal::Exit
+            } else {
+                joined = true;
+                ThreadSignal::Join(waiter)
+            }
+        }),
+        1,
+        None,
+    );
+    for tid in [waiter, idle] {
+        rt.set_group(tid, Some(group));
+    }
+
+    // owner toma el mutex, waiter se bloquea en el, joiner se bloquea esperando a waiter
+    for tid in [owner, waiter, joiner] {
+        assert!(rt.run_chosen(tid));
+    }
+    assert_eq!(rt.threads[&waiter].state, ThreadState::Blocked);
+    assert_eq!(rt.threads[&joiner].state, ThreadState::Blocked);
+
+    assert_eq!(rt.cancel_group(group), 2, "waiter e idle");
+    assert_eq!(rt.cancel_group(group), 0, "cancelar dos veces no hace nada");
+    assert_eq!(rt.threads[&waiter].state, ThreadState::Terminated);
+    assert!(!rt.blocked.contains(&waiter));
+    assert!(!rt.ready.contains(&idle));
+    assert_eq!(rt.threads[&joiner].state, ThreadState::Ready, "el join se resuelve al cancelar");
+
+    rt.run(10);
+    assert_eq!(rt.threads[&owner].state, ThreadState::Terminated);
+    assert_eq!(rt.threads[&joiner].state, ThreadState::Terminated);
+    assert_eq!(
+        mutex.owner.load(Ordering::Relaxed),
+        UNLOCKED,
+        "el mutex no se entrega al hilo cancelado"
+    );
+    assert_eq!(rt.threads[&idle].stats.dispatches, 0);
+
+    println!("  Test pasado: cancelacion!");
+}
+
+#[test]
+fn test_group_api_join_chsched_and_cancel() {
+    println!("\n=== TEST: API global de grupos ===\n");
+
+    runtime_init();
+    let trucks = my_group_create("trucks").unwrap();
+    let boats = my_group_create("boats").unwrap();
+    let finished: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+
+    for i in 0..3 {
+        let done = finished.clone();
+        let mut steps = 0;
+        my_thread_create_in_group(
+            trucks,
+            &format!("truck-{}", i),
+            SchedulerParams::RoundRobin,
+            Box::new(move |_, _| {
+                steps += 1;
+                if steps <= i + 1 {
+                    return ThreadSignal::Yield;
+                }
+                done.lock().unwrap().push(format!("truck-{}", i));
+                ThreadSignal::Exit
+            }),
+        )
+        .unwrap();
+    }
+    for i in 0..2 {
+        my_thread_create_in_group(
+            boats,
+            &format!("boat-{}", i),
+            SchedulerParams::RoundRobin,
+            Box::new(|_, _| ThreadSignal::Yield),
+        )
+        .unwrap();
+    }
+    let orphan = my_thread_create_in_group(99, "orphan", SchedulerParams::RoundRobin, Box::new(|_, _| ThreadSignal::Exit));
+    assert!(matches!(orphan, Err(MyThreadError::InvalidArgument(_))), "el grupo debe existir");
+
+    // un coordinador espera a todos los camiones y luego cancela los barcos
+    let done = finished.clone();
+    my_thread_create(
+        "coordinator",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            if let Some(signal) = my_group_join(trucks).unwrap() {
+                return signal;
+            }
+            assert_eq!(done.lock().unwrap().len(), 3, "join espera a todo el grupo");
+            assert_eq!(my_group_cancel(boats), Ok(2));
+            done.lock().unwrap().push("coordinator".into());
+            ThreadSignal::Exit
+        }),
+    )
+    .unwrap();
+
+    // se cancela a si mismo: termina al acabar el paso en curso
+    let doomed = my_group_create("doomed").unwrap();
+    let steps_run = Arc::new(AtomicU32::new(0));
+    let counter = steps_run.clone();
+    let doomed_tid = my_thread_create_in_group(
+        doomed,
+        "doomed",
+        SchedulerParams::RoundRobin,
+        Box::new(move |tid, _| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            my_thread_cancel(tid).unwrap();
+            ThreadSignal::Yield
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(my_group_members(trucks).unwrap().len(), 3);
+    assert_eq!(my_group_members(doomed), Ok(vec![doomed_tid]));
+    assert_eq!(
+        my_group_chsched(trucks, SchedulerParams::Lottery { tickets: 50 }),
+        Ok(3),
+        "chsched a toda la clase"
+    );
+    assert_eq!(my_group_join(trucks), Err(MyThreadError::NotInThread));
+
+    // las llamadas desde los hilos no chocan con el lock que toma run_simulation
+    run_simulation(100).unwrap();
+
+    let finished = finished.lock().unwrap().clone();
+    println!("  orden de terminacion: {:?}", finished);
+    assert_eq!(finished.last().map(String::as_str), Some("coordinator"));
+    let boat_stats = my_group_stats(boats).unwrap();
+    assert_eq!(boat_stats.threads, 2);
+    assert!(my_group_stats(trucks).unwrap().dispatches >= 9, "2 + 3 + 4 pasos");
+    assert_eq!(my_group_cancel(boats), Ok(0), "los barcos cancelados ya terminaron");
+    assert_eq!(my_thread_cancel(doomed_tid), Err(MyThreadError::NoSuchThread(doomed_tid)));
+    assert_eq!(steps_run.load(Ordering::Relaxed), 1, "no vuelve a correr despues de cancelarse");
+
+    println!("  Test pasado: API de grupos!");
+}
@@ -0,0 +1,182 @@
+//! tests de las reglas de join y detach
+
+use mypthreads::mypthreads_api::{
+    my_thread_create, my_thread_detach, my_thread_join, my_thread_last_error, my_thread_self, my_thread_stats,
+    run_simulation, runtime_init, SchedulerParams,
+};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::{SchedulerType, ThreadState};
+use mypthreads::MyThreadError;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_join_rules_self_and_detach() {
+    println!("\n=== TEST: Reglas de join, my_thread_self y detach ===\n");
+
+    runtime_init();
+    assert_eq!(my_thread_self(), Err(MyThreadError::NotInThread));
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let mut steps = 0;
+    let worker = my_thread_create(
+        "worker",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            steps += 1;
+            if steps == 3 {
+                ThreadSignal::Exit
+            } else {
+                ThreadSignal::Yield
+            }
+        }),
+    )
+    .unwrap();
+
+    let joiner_log = log.clone();
+    let mut first = true;
+    my_thread_create(
+        "joiner",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            let me = my_thread_self().unwrap();
+            if first {
+                first = false;
+                joiner_log.lock().unwrap().push(("self-join", my_thread_join(me).err()));
+            }
+            match my_thread_join(worker) {
+                Ok(Some(signal)) => signal,
+                result => {
+                    joiner_log.lock().unwrap().push(("join", result.err()));
+                    ThreadSignal::Exit
+                }
+            }
+        }),
+    )
+    .unwrap();
+
+    // corre despues del primero, cuando el worker ya tiene quien lo espere
+    let second_log = log.clone();
+    my_thread_create(
+        "second",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            second_log.lock().unwrap().push(("second", my_thread_join(worker).err()));
+            ThreadSignal::Exit
+        }),
+    )
+    .unwrap();
+
+    let detached = my_thread_create("detached", SchedulerParams::RoundRobin, Box::new(|_, _| ThreadSignal::Exit)).unwrap();
+    my_thread_detach(detached).unwrap();
+    assert!(matches!(my_thread_join(detached), Err(MyThreadError::InvalidArgument(_))));
+
+    let finished = my_thread_create("finished", SchedulerParams::RoundRobin, Box::new(|_, _| ThreadSignal::Exit)).unwrap();
+    let joined_from_main = my_thread_create("main-join", SchedulerParams::RoundRobin, Box::new(|_, _| ThreadSignal::Exit)).unwrap();
+    assert_eq!(my_thread_join(joined_from_main), Ok(Some(ThreadSignal::Join(joined_from_main))));
+
+    run_simulation(20).unwrap();
+
+    let log = log.lock().unwrap().clone();
+    println!("log: {:?}", log);
+    assert_eq!(
+        log,
+        vec![
+            ("self-join", Some(MyThreadError::Deadlock)),
+            ("second", Some(MyThreadError::InvalidArgument("otro hilo ya espera a este hilo"))),
+            ("join", None),
+        ]
+    );
+
+    // el join exitoso libera al worker y el detached se libero al terminar
+    assert_eq!(my_thread_stats(worker), Err(MyThreadError::NoSuchThread(worker)));
+    assert_eq!(my_thread_stats(detached), Err(MyThreadError::NoSuchThread(detached)));
+
+    // detach de un hilo ya terminado lo libera de una vez
+    assert!(my_thread_stats(finished).is_ok());
+    assert_eq!(my_thread_detach(finished), Ok(()));
+    assert_eq!(my_thread_stats(finished), Err(MyThreadError::NoSuchThread(finished)));
+
+    assert_eq!(my_thread_join(joined_from_main), Ok(None));
+    assert_eq!(my_thread_join(joined_from_main), Err(MyThreadError::NoSuchThread(joined_from_main)));
+
+    println!("  Test pasado: join y detach siguen las reglas de pthreads!");
+}
+
+#[test]
+fn test_runtime_refuses_invalid_joins() {
+    println!("\n=== TEST: El runtime rechaza joins invalidos ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let mut steps = 0;
+    let target = rt.spawn(
+        "target",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            steps += 1;
+            if steps == 4 {
+                ThreadSignal::Exit
+            } else {
+                ThreadSignal::Yield
+            }
+        }),
+        1,
+        None,
+    );
+    let mut waited = false;
+    let first = rt.spawn(
+        "first",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            if waited {
+                ThreadSignal::Exit
+            } else {
+                waited = true;
+                ThreadSignal::Join(target)
+            }
+        }),
+        1,
+        None,
+    );
+    let second = rt.spawn("second", SchedulerType::RoundRobin, Box::new(move |_, _| ThreadSignal::Join(target)), 1, None);
+    // el join a si mismo vuelve como error en el paso siguiente
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let selfish = rt.spawn(
+        "selfish",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            log.lock().unwrap().push(my_thread_last_error().unwrap());
+            ThreadSignal::Join(4)
+        }),
+        1,
+        None,
+    );
+
+    rt.run(4);
+
+    // solo el primero queda esperando; los demas siguen listos
+    assert_eq!(rt.threads[&target].joiners, vec![first]);
+    assert_eq!(rt.threads[&first].state, ThreadState::Blocked);
+    assert_eq!(rt.threads[&second].state, ThreadState::Ready);
+    assert_eq!(rt.threads[&selfish].state, ThreadState::Ready);
+    assert!(matches!(rt.check_join(second, target), Err(MyThreadError::InvalidArgument(_))));
+    assert_eq!(rt.check_join(target, first), Err(MyThreadError::Deadlock), "el objetivo ya lo espera");
+    assert_eq!(rt.check_join(selfish, selfish), Err(MyThreadError::Deadlock));
+    assert!(matches!(rt.threads[&second].last_error, Some(MyThreadError::InvalidArgument(_))));
+    assert_eq!(rt.threads[&first].last_error, None);
+
+    rt.run(12);
+    assert_eq!(seen.lock().unwrap()[..2], [None, Some(MyThreadError::Deadlock)]);
+
+    // al terminar se despierta al que esperaba y la lista queda vacia
+    assert_eq!(rt.threads[&target].state, ThreadState::Terminated);
+    assert!(rt.threads[&target].joiners.is_empty());
+    assert_eq!(rt.threads[&first].state, ThreadState::Terminated);
+    assert_eq!(rt.check_join(second, target), Ok(false));
+    assert!(rt.reap(target));
+    assert!(!rt.reap(second), "sigue vivo");
+
+    println!("  Test pasado: el runtime aplica las reglas de join!");
+}
@@ -0,0 +1,48 @@
+//! tests del lock del runtime global: solo el despachador entra sin tomarlo
+
+use mypthreads::mypthreads_api::{
+    my_thread_create, my_thread_stats, run_simulation, runtime_init, runtime_snapshot, runtime_stats_summary,
+    SchedulerParams,
+};
+use mypthreads::signals::ThreadSignal;
+use mypthreads::MyThreadError;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_other_os_threads_get_busy_while_runtime_runs() {
+    println!("\n=== TEST: Otro hilo del sistema recibe EBUSY mientras el runtime corre ===\n");
+
+    runtime_init();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let tid = my_thread_create(
+        "probe",
+        SchedulerParams::RoundRobin,
+        Box::new(move |tid, _| {
+            // el hilo verde entra aunque el despachador tiene el lock
+            let own = my_thread_stats(tid).map(|s| s.dispatches);
+            // un hilo del sistema cualquiera no puede tocar el runtime ahora
+            let other = std::thread::spawn(move || my_thread_stats(tid).map(|s| s.dispatches))
+                .join()
+                .unwrap();
+            log.lock().unwrap().push((own, other));
+            // la foto del runtime tambien pasa por el lock
+            assert!(runtime_snapshot().is_ok());
+            let snap = std::thread::spawn(|| runtime_snapshot().map(|_| ())).join().unwrap();
+            assert_eq!(snap, Err(MyThreadError::Busy));
+            ThreadSignal::Exit
+        }),
+    )
+    .unwrap();
+
+    run_simulation(5).unwrap();
+    let seen = seen.lock().unwrap().clone();
+    println!("visto desde el hilo {}: {:?}", tid, seen);
+    assert_eq!(seen, vec![(Ok(1), Err(MyThreadError::Busy))]);
+
+    // con el runtime quieto cualquiera lo puede consultar
+    let from_other = std::thread::spawn(|| runtime_stats_summary().map(|s| s.dispatches)).join().unwrap();
+    assert_eq!(from_other, Ok(1));
+
+    println!("  Test pasado: el lock del runtime solo deja pasar al despachador!");
+}
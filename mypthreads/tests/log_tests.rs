@@ -0,0 +1,59 @@
+//! tests de la fachada de logging
+//!
+//! La configuracion es global, por eso todo va en un solo test.
+
+use mypthreads::log::{self, Level};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::SchedulerType;
+
+fn run_two_lottery_threads() {
+    let mut rt = ThreadRuntimeV2::with_seed(3);
+    rt.spawn("A", SchedulerType::Lottery, Box::new(|_, _| ThreadSignal::Yield), 5, None);
+    rt.spawn("B", SchedulerType::Lottery, Box::new(|_, _| ThreadSignal::Yield), 5, None);
+    rt.run(4);
+}
+
+#[test]
+fn test_sched_log_levels_and_sinks() {
+    println!("\n=== TEST: Logging del scheduler ===\n");
+
+    // apagado por defecto
+    log::log_to_ring(16);
+    run_two_lottery_threads();
+    assert!(log::ring_records().is_empty());
+    assert!(!log::enabled(Level::Error, "sched"));
+
+    // solo el target "sched" en debug
+    log::set_target_level("sched", Some(Level::Debug));
+    mypthreads::mp_log!(Info, "runtime", "no se registra");
+    run_two_lottery_threads();
+    let records = log::ring_records();
+    assert_eq!(records.len(), 4);
+    assert!(records.iter().all(|r| r.target == "sched" && r.level == Level::Debug));
+    assert!(records[0].message.starts_with("SORTEO: Seleccionado hilo"));
+    assert!(records[0].to_string().starts_with("[DEBUG sched] SORTEO"));
+
+    // el buffer solo guarda los ultimos mensajes
+    log::log_to_ring(2);
+    run_two_lottery_threads();
+    assert_eq!(log::ring_records().len(), 2);
+
+    // un nivel menos detallado descarta las decisiones
+    log::log_to_ring(16);
+    log::set_target_level("sched", Some(Level::Warn));
+    run_two_lottery_threads();
+    assert!(log::ring_records().is_empty());
+
+    // salida a archivo
+    let path = std::env::temp_dir().join(format!("mypthreads_log_{}.txt", std::process::id()));
+    log::set_level(Some(Level::Trace));
+    log::log_to_file(&path).unwrap();
+    mypthreads::mp_log!(Info, "runtime", "hola {}", 42);
+    let text = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(text, "[INFO runtime] hola 42\n");
+
+    log::reset();
+    println!("  Test pasado: niveles, targets y salidas funcionan!");
+}
@@ -0,0 +1,130 @@
+//! tests de monedas de tiquetes, transferencias y compensacion
+
+use mypthreads::lottery::LotteryLedger;
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::{MyThread, SchedulerType, ThreadId, ThreadState};
+use std::collections::HashMap;
+
+fn lottery_thread(tid: ThreadId, tickets: u32) -> Box<MyThread> {
+    Box::new(MyThread::new(
+        tid,
+        format!("L-{}", tid),
+        SchedulerType::Lottery,
+        tickets,
+        None,
+        Box::new(|_, _| unreachable!()),
+    ))
+}
+
+#[test]
+fn test_currency_shares_funding() {
+    println!("\n=== TEST: Moneda reparte su respaldo ===\n");
+
+    let mut ledger = LotteryLedger::new();
+    let emergency = ledger.create_currency("emergency", 900);
+
+    let mut threads: HashMap<ThreadId, Box<MyThread>> = HashMap::new();
+    for tid in 1..=3 {
+        let mut t = lottery_thread(tid, 1);
+        t.currency = Some(emergency);
+        threads.insert(tid, t);
+    }
+    threads.insert(4, lottery_thread(4, 10));
+
+    ledger.refresh(&mut threads);
+
+    for tid in 1..=3 {
+        assert_eq!(threads[&tid].effective_tickets, 300, "cada hilo recibe un tercio del fondo");
+    }
+    assert_eq!(threads[&4].effective_tickets, 10, "tiquetes base no cambian");
+
+    // al terminar uno, los demas se reparten el fondo completo
+    threads.get_mut(&3).unwrap().state = ThreadState::Terminated;
+    ledger.refresh(&mut threads);
+    assert_eq!(threads[&1].effective_tickets, 450);
+    assert_eq!(threads[&2].effective_tickets, 450);
+
+    println!("  Test pasado: las monedas reparten su respaldo!");
+}
+
+#[test]
+fn test_blocked_member_does_not_dilute_currency() {
+    println!("\n=== TEST: Un miembro bloqueado no diluye su moneda ===\n");
+
+    let mut ledger = LotteryLedger::new();
+    let emergency = ledger.create_currency("emergency", 900);
+    let mut threads: HashMap<ThreadId, Box<MyThread>> = HashMap::new();
+    for tid in 1..=3 {
+        let mut t = lottery_thread(tid, 1);
+        t.currency = Some(emergency);
+        threads.insert(tid, t);
+    }
+    threads.insert(4, lottery_thread(4, 10));
+
+    // 3 se bloquea sin prestar: 1 y 2 se reparten todo el fondo
+    threads.get_mut(&3).unwrap().state = ThreadState::Blocked;
+    ledger.refresh(&mut threads);
+    assert_eq!(threads[&1].effective_tickets, 450);
+    assert_eq!(threads[&2].effective_tickets, 450);
+    assert_eq!(threads[&3].effective_tickets, 0, "sus tiquetes estan inactivos");
+
+    // si espera un lock de 4, su parte sigue activa y se la presta
+    threads.get_mut(&3).unwrap().lending_to = Some(4);
+    ledger.refresh(&mut threads);
+    assert_eq!(threads[&1].effective_tickets, 300);
+    assert_eq!(threads[&4].effective_tickets, 310);
+
+    println!("  Test pasado: solo los tiquetes activos reparten el fondo!");
+}
+
+#[test]
+fn test_blocked_thread_lends_tickets_to_holder() {
+    println!("\n=== TEST: Transferencia de tiquetes al dueño del lock ===\n");
+
+    let ledger = LotteryLedger::new();
+    let mut threads: HashMap<ThreadId, Box<MyThread>> = HashMap::new();
+    threads.insert(1, lottery_thread(1, 5));
+    threads.insert(2, lottery_thread(2, 20));
+    threads.insert(3, lottery_thread(3, 30));
+
+    // 3 espera a 2, que a su vez espera a 1: todo termina en 1
+    let t2 = threads.get_mut(&2).unwrap();
+    t2.state = ThreadState::Blocked;
+    t2.lending_to = Some(1);
+    let t3 = threads.get_mut(&3).unwrap();
+    t3.state = ThreadState::Blocked;
+    t3.lending_to = Some(2);
+
+    ledger.refresh(&mut threads);
+
+    assert_eq!(threads[&1].effective_tickets, 55);
+    assert_eq!(threads[&2].effective_tickets, 0);
+    assert_eq!(threads[&3].effective_tickets, 0);
+
+    println!("  Test pasado: los tiquetes siguen la cadena de locks!");
+}
+
+#[test]
+fn test_early_yield_gets_compensation() {
+    println!("\n=== TEST: Tiquetes de compensación ===\n");
+
+    let mut rt = ThreadRuntimeV2::new();
+    let tid = rt.spawn(
+        "Early",
+        SchedulerType::Lottery,
+        Box::new(|_, _| ThreadSignal::YieldEarly(2)),
+        10,
+        None,
+    );
+
+    rt.run_once();
+
+    // uso 2 de 10 ms: sus 10 tiquetes valen 50 (10 propios + 40 de compensacion)
+    assert_eq!(rt.threads[&tid].compensation_tickets, 40);
+
+    rt.lottery.refresh(&mut rt.threads);
+    assert_eq!(rt.threads[&tid].effective_tickets, 50);
+
+    println!("  Test pasado: ceder temprano da compensación!");
+}
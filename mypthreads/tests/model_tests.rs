@@ -0,0 +1,135 @@
+//! tests del simulador del scheduler (sin hilos)
+
+use mypthreads::thread::SchedulerType;
+use mypthreads::{GanttSlice, TaskSet, TaskSpec};
+
+fn slice(task: &str, job: u32, start_ms: u64, end_ms: u64) -> GanttSlice {
+    GanttSlice {
+        job: Some(job),
+        task: Some(task.to_string()),
+        start_ms,
+        end_ms,
+    }
+}
+
+#[test]
+fn test_round_robin_model_gantt_and_times() {
+    println!("\n=== TEST: Modelo round robin: Gantt, espera y turnaround ===\n");
+
+    let set = TaskSet::new(vec![
+        TaskSpec::new("A", SchedulerType::RoundRobin, 0, 20),
+        TaskSpec::new("B", SchedulerType::RoundRobin, 0, 15),
+        TaskSpec::new("C", SchedulerType::RoundRobin, 60, 5),
+    ]);
+    let report = set.simulate(1, 1000);
+    println!("{}", report.to_text());
+
+    assert_eq!(
+        report.slices,
+        vec![
+            slice("A", 1, 0, 10),
+            slice("B", 2, 10, 20),
+            slice("A", 1, 20, 30),
+            slice("B", 2, 30, 35),
+            GanttSlice {
+                job: None,
+                task: None,
+                start_ms: 35,
+                end_ms: 60
+            },
+            slice("C", 3, 60, 65),
+        ]
+    );
+    assert_eq!(report.end_ms, 65);
+    let times: Vec<_> = report.jobs.iter().map(|j| (j.waiting_ms(), j.turnaround_ms())).collect();
+    assert_eq!(times, vec![(Some(10), Some(30)), (Some(20), Some(35)), (Some(0), Some(5))]);
+
+    let gantt = report.gantt(5);
+    assert!(gantt.contains("A    |##..##"), "{}", gantt);
+    assert!(gantt.contains("idle |       -----"), "{}", gantt);
+
+    println!("  Test pasado: el modelo reparte como el round robin del runtime!");
+}
+
+#[test]
+fn test_real_time_model_reports_deadline_misses() {
+    println!("\n=== TEST: Modelo de tiempo real con deadlines perdidos ===\n");
+
+    // 25 ms de trabajo cada 20 ms: alguna llegada tiene que perder su deadline
+    let set = TaskSet::parse(
+        "# mypthreads task set v1\n\
+         task fast realtime arrival=0 burst=10 period=20 deadline=15\n\
+         task slow realtime arrival=0 burst=15 period=20 deadline=20\n\
+         task batch lottery arrival=0 burst=10 tickets=3\n",
+    )
+    .unwrap();
+    assert_eq!(TaskSet::parse(&set.to_text()).unwrap(), set);
+
+    let report = set.simulate(7, 100);
+    println!("{}", report.to_text());
+
+    // el deadline mas cercano va primero y el sorteo espera a que no haya tiempo real
+    assert_eq!(report.slices[0], slice("fast", 1, 0, 10));
+    assert_eq!(report.slices[1].task.as_deref(), Some("slow"));
+    assert!(report.deadline_misses() > 0);
+    let fast = &report.summaries()[0];
+    assert_eq!(fast.jobs, 5);
+    assert!(report.jobs.iter().filter(|j| j.task == "batch").all(|j| j.finish_ms.is_none()));
+
+    let json = report.to_json();
+    assert!(json.starts_with("{\"seed\":7,"));
+    assert!(json.contains(&format!("\"deadline_misses\":{}", report.deadline_misses())));
+    assert!(json.contains("\"missed_deadline\":true"));
+
+    println!("  Test pasado: el modelo predice los deadlines perdidos!");
+}
+
+#[test]
+fn test_lottery_model_is_repeatable_and_parse_errors() {
+    println!("\n=== TEST: Modelo de sorteo repetible y errores de formato ===\n");
+
+    let set = TaskSet::new(vec![
+        TaskSpec {
+            tickets: 9,
+            ..TaskSpec::new("rich", SchedulerType::Lottery, 0, 100)
+        },
+        TaskSpec::new("poor", SchedulerType::Lottery, 0, 100),
+    ]);
+    let first = set.simulate(42, 1000);
+    assert_eq!(first, set.simulate(42, 1000), "misma semilla, mismo reparto");
+
+    // con 9 a 1 el rico termina mucho antes
+    let finish = |name: &str| first.jobs.iter().find(|j| j.task == name).unwrap().finish_ms.unwrap();
+    println!("rico termina en {} ms, pobre en {} ms", finish("rich"), finish("poor"));
+    assert!(finish("rich") < finish("poor"));
+
+    for bad in [
+        "tarea x rr burst=5",
+        "task x fifo burst=5",
+        "task x rr burst=0",
+        "task x rr burst=5 deadline=3",
+        "task x lottery burst=5 tickets=0",
+        "task x rr burst=cinco",
+    ] {
+        let err = TaskSet::parse(bad).unwrap_err();
+        println!("{}", err);
+        assert!(err.starts_with("linea 1:"));
+    }
+    // el reporte identifica las tareas por nombre
+    let err = TaskSet::parse("task x rr burst=5\n\ntask x lottery burst=3\n").unwrap_err();
+    println!("{}", err);
+    assert!(err.starts_with("linea 3:") && err.contains("nombre"), "{}", err);
+
+    println!("  Test pasado: los sorteos del modelo se repiten con la semilla!");
+}
+
+#[test]
+fn test_lottery_model_with_huge_ticket_totals() {
+    println!("\n=== TEST: Sorteo con mas de u32::MAX tiquetes en total ===\n");
+
+    let set = TaskSet::parse("task a lottery burst=20 tickets=4000000000\ntask b lottery burst=20 tickets=4000000000\n").unwrap();
+    let report = set.simulate(3, 1000);
+    assert!(report.jobs.iter().all(|j| j.finish_ms.is_some()), "las dos terminan: {:?}", report.jobs);
+
+    println!("  Test pasado: la suma de tiquetes no se desborda!");
+}
@@ -0,0 +1,144 @@
+//! tests de los tipos de mutex (normal, errorcheck y recursivo)
+
+use mypthreads::channels::{MutexKind, SimpleMutex};
+use mypthreads::mypthreads_api::{
+    my_mutex_destroy, my_mutex_init, my_mutex_init_kind, my_mutex_lock, my_mutex_trylock, my_mutex_unlock,
+    my_thread_create, my_thread_last_error, run_simulation, runtime_init, runtime_snapshot, SchedulerParams,
+};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::snapshot::WaitReason;
+use mypthreads::thread::{SchedulerType, ThreadState};
+use mypthreads::MyThreadError;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_mutex_kinds() {
+    println!("\n=== TEST: Mutex normal, errorcheck y recursivo ===\n");
+
+    runtime_init();
+    assert_eq!(my_mutex_init().unwrap().kind(), MutexKind::ErrorCheck);
+
+    // recursivo: se suelta con el ultimo unlock
+    let mut recursive = my_mutex_init_kind(MutexKind::Recursive).unwrap();
+    for _ in 0..3 {
+        assert_eq!(my_mutex_lock(&recursive), Ok(ThreadSignal::Continue));
+    }
+    assert_eq!(my_mutex_trylock(&recursive), Ok(()));
+    for _ in 0..3 {
+        my_mutex_unlock(&recursive).unwrap();
+        assert_eq!(my_mutex_destroy(&mut recursive), Err(MyThreadError::Busy));
+    }
+    my_mutex_unlock(&recursive).unwrap();
+    assert_eq!(my_mutex_unlock(&recursive), Err(MyThreadError::NotOwner));
+    assert_eq!(my_mutex_destroy(&mut recursive), Ok(()));
+
+    // errorcheck y normal desde hilos
+    let errorcheck: &'static _ = Box::leak(Box::new(my_mutex_init_kind(MutexKind::ErrorCheck).unwrap()));
+    let normal: &'static _ = Box::leak(Box::new(my_mutex_init_kind(MutexKind::Normal).unwrap()));
+    let results = Arc::new(Mutex::new(Vec::new()));
+
+    let owner_results = results.clone();
+    my_thread_create(
+        "owner",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            let mut results = owner_results.lock().unwrap();
+            results.push(("lock", my_mutex_lock(errorcheck)));
+            results.push(("relock", my_mutex_lock(errorcheck)));
+            ThreadSignal::Exit
+        }),
+    )
+    .unwrap();
+    let other_results = results.clone();
+    my_thread_create(
+        "other",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            other_results.lock().unwrap().push(("unlock ajeno", my_mutex_unlock(errorcheck)));
+            ThreadSignal::Exit
+        }),
+    )
+    .unwrap();
+
+    let mut locked = false;
+    let stuck = my_thread_create(
+        "stuck",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            if !locked {
+                locked = true;
+                assert_eq!(my_mutex_lock(normal), Ok(ThreadSignal::Continue));
+                return ThreadSignal::Yield;
+            }
+            // un normal no revisa al dueño: la señal lo deja esperandose a si mismo
+            my_mutex_lock(normal).unwrap()
+        }),
+    )
+    .unwrap();
+
+    run_simulation(10).unwrap();
+
+    assert_eq!(
+        *results.lock().unwrap(),
+        vec![
+            ("lock", Ok(ThreadSignal::Continue)),
+            ("relock", Err(MyThreadError::Deadlock)),
+            ("unlock ajeno", Err(MyThreadError::NotOwner)),
+        ]
+    );
+    let snap = runtime_snapshot().unwrap();
+    let stuck = snap.threads.iter().find(|t| t.id == stuck).unwrap();
+    assert_eq!(stuck.state, ThreadState::Blocked);
+    assert!(matches!(stuck.wait_reason, Some(WaitReason::Mutex { .. })));
+    // el hilo principal no puede quedarse esperando
+    let from_main = my_mutex_init_kind(MutexKind::Normal).unwrap();
+    assert_eq!(my_mutex_lock(&from_main), Ok(ThreadSignal::Continue));
+    assert_eq!(my_mutex_lock(&from_main), Err(MyThreadError::Deadlock));
+
+    println!("  Test pasado: cada tipo de mutex se comporta como en pthreads!");
+}
+
+#[test]
+fn test_runtime_does_not_queue_errorcheck_owner() {
+    println!("\n=== TEST: El dueño de un errorcheck no queda en su propia cola ===\n");
+
+    let mutex: &'static SimpleMutex = Box::leak(Box::new(SimpleMutex::new()));
+    assert_eq!(mutex.lock(1), Ok(false));
+    assert_eq!(mutex.lock(1), Err(MyThreadError::Deadlock));
+
+    let recursive = SimpleMutex::with_kind(MutexKind::Recursive);
+    assert_eq!(recursive.lock(1), Ok(false));
+    assert_eq!(recursive.lock(1), Ok(false));
+    assert_eq!(recursive.recursion_depth(), 1);
+    assert_eq!(recursive.lock(2), Ok(true), "otro hilo se encola");
+    assert_eq!(recursive.unlock(1), Ok(None));
+    assert_eq!(recursive.unlock(1), Ok(Some(2)));
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let mutex_addr = mutex as *const SimpleMutex as usize;
+    // el paso devuelve la señal directo, sin pasar por `my_mutex_lock`
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let tid = rt.spawn(
+        "relock",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            log.lock().unwrap().push(my_thread_last_error().unwrap());
+            ThreadSignal::MutexLock(mutex_addr)
+        }),
+        1,
+        None,
+    );
+    mutex.force_unlock();
+    rt.run(3);
+
+    // el EDEADLK le llega al hilo en su siguiente paso
+    assert_eq!(*seen.lock().unwrap(), vec![None, None, Some(MyThreadError::Deadlock)]);
+
+    assert_eq!(rt.threads[&tid].state, ThreadState::Ready);
+    assert_eq!(rt.threads[&tid].stats.mutex_acquisitions, 1);
+    assert!(unsafe { &*mutex.wait_queue.get() }.is_empty());
+
+    println!("  Test pasado: el runtime rechaza el relock de un errorcheck!");
+}
@@ -0,0 +1,94 @@
+//! tests de los observadores de eventos del runtime
+
+use mypthreads::observer::RuntimeEvent;
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::stats::BlockReason;
+use mypthreads::thread::SchedulerType;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_observer_sees_thread_lifecycle() {
+    println!("\n=== TEST: Observador del ciclo de vida ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let seen: Arc<Mutex<Vec<(u64, RuntimeEvent)>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    rt.add_observer(move |now_ms, event: &RuntimeEvent| {
+        sink.lock().unwrap().push((now_ms, event.clone()));
+    });
+
+    let mut joined = false;
+    let a = rt.spawn(
+        "A",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            if joined {
+                ThreadSignal::Exit
+            } else {
+                joined = true;
+                ThreadSignal::Join(2)
+            }
+        }),
+        1,
+        None,
+    );
+    let b = rt.spawn("B", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Exit), 1, None);
+    rt.set_sched_params(a, SchedulerType::Lottery, 5, None, None);
+
+    rt.run(3);
+
+    let events: Vec<RuntimeEvent> = seen.lock().unwrap().iter().map(|(_, e)| e.clone()).collect();
+    for e in &events {
+        println!("  {:?}", e);
+    }
+    assert_eq!(
+        events,
+        vec![
+            RuntimeEvent::Spawn { tid: a, name: "A".into() },
+            RuntimeEvent::Spawn { tid: b, name: "B".into() },
+            RuntimeEvent::SchedChange {
+                tid: a,
+                sched_type: SchedulerType::Lottery,
+                tickets: 5,
+                deadline: None,
+            },
+            RuntimeEvent::Dispatch { tid: a },
+            RuntimeEvent::Block { tid: a, reason: BlockReason::Join },
+            RuntimeEvent::Dispatch { tid: b },
+            RuntimeEvent::Exit { tid: b },
+            RuntimeEvent::Unblock { tid: a },
+            RuntimeEvent::Dispatch { tid: a },
+            RuntimeEvent::Exit { tid: a },
+        ]
+    );
+    assert_eq!(seen.lock().unwrap()[3].0, 10, "el despacho ocurre en el primer quantum");
+
+    println!("  Test pasado: el observador recibe todos los eventos!");
+}
+
+#[test]
+fn test_observer_deadline_miss_and_removal() {
+    println!("\n=== TEST: Deadline perdido y quitar observador ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let misses = Arc::new(Mutex::new(Vec::new()));
+    let sink = misses.clone();
+    let id = rt.add_observer(move |now_ms, event: &RuntimeEvent| {
+        if let RuntimeEvent::DeadlineMiss { tid, deadline } = event {
+            sink.lock().unwrap().push((*tid, *deadline, now_ms));
+        }
+    });
+
+    let tid = rt.spawn("RT", SchedulerType::RealTime, Box::new(|_, _| ThreadSignal::Yield), 1, Some(5));
+    rt.run(2);
+    assert_eq!(*misses.lock().unwrap(), vec![(tid, 5, 10)]);
+
+    assert!(rt.remove_observer(id));
+    assert!(!rt.remove_observer(id));
+    rt.set_sched_params(tid, SchedulerType::RealTime, 0, Some(15), None);
+    rt.run(2);
+    assert_eq!(misses.lock().unwrap().len(), 1, "ya no recibe eventos");
+
+    println!("  Test pasado: deadline perdido notificado!");
+}
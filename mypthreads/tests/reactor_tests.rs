@@ -0,0 +1,198 @@
+//! tests del reactor de E/S con pipes y sockets Unix
+
+use mypthreads::mypthreads_api::{
+    my_accept, my_read, my_thread_create, my_write, run_simulation, runtime_init, SchedulerParams,
+};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::{SchedulerType, ThreadState};
+use mypthreads::MyThreadError;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn nonblocking_pipe() -> (OwnedFd, OwnedFd) {
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) }, 0);
+    unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) }
+}
+
+/// lee de `fd` hasta fin de archivo
+fn read_to_end(fd: RawFd) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 16];
+    loop {
+        match my_read(fd, &mut buf) {
+            Ok(0) => return data,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+            Err(e) => panic!("lectura fallida: {}", e),
+        }
+    }
+}
+
+#[test]
+fn test_reactor_parks_readers_on_pipes() {
+    println!("\n=== TEST: El reactor estaciona lectores de un pipe ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(3);
+    let (read_end, write_end) = nonblocking_pipe();
+    let read_fd = read_end.as_raw_fd();
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let reader_received = received.clone();
+    let reader = rt.spawn(
+        "reader",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            *reader_received.lock().unwrap() = read_to_end(read_fd);
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+
+    // escribe un trozo por paso y al final cierra su extremo
+    let mut write_end = Some(write_end);
+    let mut chunks = vec!["uno ", "dos ", "tres"].into_iter();
+    let writer = rt.spawn(
+        "writer",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| match chunks.next() {
+            Some(chunk) => {
+                let fd = write_end.as_ref().unwrap().as_raw_fd();
+                assert_eq!(my_write(fd, chunk.as_bytes()), Ok(chunk.len()));
+                ThreadSignal::Yield
+            }
+            None => {
+                write_end = None;
+                ThreadSignal::Exit
+            }
+        }),
+        1,
+        None,
+    );
+
+    rt.run(2);
+    assert_eq!(rt.threads[&reader].state, ThreadState::Blocked, "el pipe esta vacio");
+    assert!(rt.has_io_waiters());
+
+    rt.run(20);
+    assert_eq!(rt.threads[&writer].state, ThreadState::Terminated);
+    assert_eq!(rt.threads[&reader].state, ThreadState::Terminated);
+    assert_eq!(&*received.lock().unwrap(), b"uno dos tres");
+    assert!(!rt.has_io_waiters());
+
+    // sin hilos listos el scheduler espera la E/S que llega de afuera
+    let (read_end, write_end) = nonblocking_pipe();
+    let read_fd = read_end.as_raw_fd();
+    let late = rt.spawn(
+        "late",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            assert_eq!(read_to_end(read_fd), b"tarde");
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+    let outside = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        let fd = write_end.as_raw_fd();
+        assert_eq!(unsafe { libc::write(fd, b"tarde".as_ptr().cast(), 5) }, 5);
+    });
+    for _ in 0..100 {
+        if rt.threads[&late].state == ThreadState::Terminated {
+            break;
+        }
+        rt.run_once();
+    }
+    outside.join().unwrap();
+    assert_eq!(rt.threads[&late].state, ThreadState::Terminated);
+
+    // cancelar a un hilo estacionado lo saca del reactor
+    let (read_end, _write_end) = nonblocking_pipe();
+    let read_fd = read_end.as_raw_fd();
+    let stuck = rt.spawn(
+        "stuck",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            read_to_end(read_fd);
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+    rt.run_once();
+    assert!(rt.has_io_waiters());
+    assert!(rt.cancel_thread(stuck));
+    assert!(!rt.has_io_waiters());
+
+    println!("  Test pasado: los lectores esperan al reactor sin frenar al runtime!");
+}
+
+#[test]
+fn test_unix_socket_echo_server() {
+    println!("\n=== TEST: Servidor de eco sobre un socket Unix ===\n");
+
+    runtime_init();
+
+    // desde el principal no hay a quien estacionar: se comporta como el fd
+    let (read_end, _write_end) = nonblocking_pipe();
+    let mut buf = [0u8; 8];
+    assert_eq!(my_read(read_end.as_raw_fd(), &mut buf), Err(MyThreadError::Io(libc::EAGAIN)));
+    assert_eq!(my_read(-1, &mut buf), Err(MyThreadError::Io(libc::EBADF)));
+    assert_eq!(MyThreadError::Io(libc::EBADF).errno(), libc::EBADF);
+
+    let path = std::env::temp_dir().join(format!("mypthreads-reactor-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let listen_fd = listener.as_raw_fd();
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    // el servidor corre antes que el cliente y se estaciona en accept
+    let server_log = log.clone();
+    my_thread_create(
+        "server",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            let conn = unsafe { OwnedFd::from_raw_fd(my_accept(listen_fd).unwrap()) };
+            server_log.lock().unwrap().push("accept".to_string());
+            let mut buf = [0u8; 32];
+            let n = my_read(conn.as_raw_fd(), &mut buf).unwrap();
+            let mut reply = b"eco: ".to_vec();
+            reply.extend_from_slice(&buf[..n]);
+            assert_eq!(my_write(conn.as_raw_fd(), &reply), Ok(reply.len()));
+            ThreadSignal::Exit
+        }),
+    )
+    .unwrap();
+
+    let client_log = log.clone();
+    let client_path = path.clone();
+    my_thread_create(
+        "client",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            let stream = UnixStream::connect(&client_path).unwrap();
+            stream.set_nonblocking(true).unwrap();
+            client_log.lock().unwrap().push("connect".to_string());
+            my_write(stream.as_raw_fd(), b"hola").unwrap();
+            let mut buf = [0u8; 32];
+            let n = my_read(stream.as_raw_fd(), &mut buf).unwrap();
+            client_log.lock().unwrap().push(String::from_utf8_lossy(&buf[..n]).into_owned());
+            ThreadSignal::Exit
+        }),
+    )
+    .unwrap();
+
+    run_simulation(50).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let log = log.lock().unwrap().clone();
+    println!("log: {:?}", log);
+    assert_eq!(log, vec!["connect", "accept", "eco: hola"]);
+
+    println!("  Test pasado: accept, read y write estacionan a los hilos verdes!");
+}
@@ -0,0 +1,113 @@
+//! tests del RuntimeHandle: hablarle al runtime desde otros hilos del sistema
+
+use mypthreads::mypthreads_api::{
+    my_remote_recv, my_remote_try_recv, my_thread_create, run_simulation, runtime_handle, runtime_init,
+    SchedulerParams,
+};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::SchedulerType;
+use mypthreads::MyThreadError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_handle_queues_commands_until_next_cycle() {
+    println!("\n=== TEST: Los pedidos remotos se aplican en el siguiente ciclo ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(2);
+    let local = rt.spawn("local", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Yield), 1, None);
+    let handle = rt.handle();
+
+    let remote = std::thread::spawn(move || {
+        let tid = handle
+            .spawn_remote("remote", SchedulerParams::RoundRobin, Box::new(|_, _| ThreadSignal::Yield))
+            .unwrap();
+        assert!(matches!(
+            handle.spawn_remote("broke", SchedulerParams::Lottery { tickets: 0 }, Box::new(|_, _| ThreadSignal::Exit)),
+            Err(MyThreadError::InvalidArgument(_))
+        ));
+        handle.chsched_remote(tid, SchedulerParams::Lottery { tickets: 7 }).unwrap();
+        tid
+    })
+    .join()
+    .unwrap();
+
+    // el tid ya esta reservado pero el hilo todavia no existe
+    assert_ne!(remote, local);
+    assert!(!rt.threads.contains_key(&remote));
+    let next_local = rt.spawn("after", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Exit), 1, None);
+    assert_eq!(next_local, remote + 1, "los tids locales y remotos no chocan");
+
+    rt.run_once();
+    let thread = &rt.threads[&remote];
+    assert_eq!(thread.name, "remote");
+    assert_eq!(thread.sched_type, SchedulerType::Lottery);
+    assert_eq!(thread.tickets, 7);
+    assert!(rt.ready.contains(&remote));
+
+    println!("  Test pasado: el runtime aplica lo que encolan los handles!");
+}
+
+#[test]
+fn test_remote_spawn_and_messages_while_running() {
+    println!("\n=== TEST: Hilos y mensajes desde otro hilo del sistema ===\n");
+
+    runtime_init();
+    assert!(matches!(my_remote_recv(), Err(MyThreadError::NotInThread)));
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let (listener_log, listener_done) = (log.clone(), done.clone());
+    let listener = my_thread_create(
+        "listener",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            assert!(my_remote_try_recv().unwrap().is_none());
+            loop {
+                let message = my_remote_recv().unwrap();
+                let text = *message.downcast::<&str>().unwrap();
+                listener_log.lock().unwrap().push(format!("recibio {}", text));
+                if text == "fin" {
+                    listener_done.store(true, Ordering::Relaxed);
+                    return ThreadSignal::Exit;
+                }
+            }
+        }),
+    )
+    .unwrap();
+
+    // la "UI": crea un hilo y le habla al listener mientras el runtime corre
+    let ui_log = log.clone();
+    let ui = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(10));
+        // se pide con el runtime corriendo: no necesita su lock
+        let handle = runtime_handle().unwrap();
+        handle
+            .spawn_remote(
+                "remote-worker",
+                SchedulerParams::RoundRobin,
+                Box::new(move |_, _| {
+                    ui_log.lock().unwrap().push("worker remoto".to_string());
+                    ThreadSignal::Exit
+                }),
+            )
+            .unwrap();
+        handle.send(listener, "hola");
+        std::thread::sleep(Duration::from_millis(10));
+        handle.send(listener, "fin");
+    });
+
+    let start = Instant::now();
+    while !done.load(Ordering::Relaxed) && start.elapsed() < Duration::from_secs(5) {
+        run_simulation(1).unwrap();
+    }
+    ui.join().unwrap();
+
+    let log = log.lock().unwrap().clone();
+    println!("log: {:?}", log);
+    assert_eq!(log, vec!["worker remoto", "recibio hola", "recibio fin"]);
+
+    println!("  Test pasado: el handle crea hilos y entrega mensajes sin tocar RUNTIME!");
+}
@@ -0,0 +1,89 @@
+//! tests de grabacion y reproduccion de decisiones del scheduler
+
+use mypthreads::replay::{SchedDecision, SchedRecording};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::{SchedulerType, ThreadId};
+use mypthreads::trace::TraceEventKind;
+
+/// tres hilos de sorteo; devuelve el orden de despacho segun la traza
+fn run_lottery(rt: &mut ThreadRuntimeV2, cycles: usize) -> Vec<ThreadId> {
+    rt.tracer.enable();
+    for tickets in [1, 5, 20] {
+        rt.spawn(
+            format!("L{}", tickets),
+            SchedulerType::Lottery,
+            Box::new(|_, _| ThreadSignal::Yield),
+            tickets,
+            None,
+        );
+    }
+    rt.run(cycles);
+    rt.tracer
+        .events()
+        .iter()
+        .filter(|e| e.kind == TraceEventKind::Dispatch)
+        .map(|e| e.tid)
+        .collect()
+}
+
+#[test]
+fn test_replay_forces_recorded_choices() {
+    println!("\n=== TEST: Reproducir decisiones grabadas ===\n");
+
+    let mut original = ThreadRuntimeV2::with_seed(11);
+    original.start_recording();
+    let recorded_order = run_lottery(&mut original, 30);
+    let recording = original.replay.stop_recording().unwrap();
+    assert_eq!(recording.seed, Some(11));
+    assert_eq!(recording.decisions.len(), 30);
+
+    // ida y vuelta por el formato de texto
+    let text = recording.to_text();
+    println!("{}", &text[..text.len().min(120)]);
+    let parsed = SchedRecording::parse(&text).unwrap();
+    assert_eq!(parsed, recording);
+
+    // otra semilla sin reproduccion elige distinto...
+    let mut other = ThreadRuntimeV2::with_seed(99);
+    other.start_recording();
+    let other_order = run_lottery(&mut other, 30);
+    assert_ne!(other_order, recorded_order);
+
+    // ...pero reproduciendo la grabacion repite exactamente el orden
+    let mut replayed = ThreadRuntimeV2::with_seed(99);
+    replayed.start_replay(parsed);
+    let replayed_order = run_lottery(&mut replayed, 30);
+    assert_eq!(replayed_order, recorded_order);
+    assert!(replayed.replay.divergence().is_none());
+    assert_eq!(replayed.replay.remaining(), 0);
+
+    println!("  Test pasado: la reproducción repite las decisiones!");
+}
+
+#[test]
+fn test_replay_reports_divergence() {
+    println!("\n=== TEST: Divergencia de la reproducción ===\n");
+
+    let recording = SchedRecording::parse("seed 1\ndispatch 2\nwake 7\ndispatch 1\n").unwrap();
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    rt.start_replay(recording);
+    rt.spawn("A", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Yield), 1, None);
+    rt.spawn("B", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Yield), 1, None);
+
+    rt.run_once();
+    assert!(rt.replay.is_replaying(), "el primer despacho coincide");
+    assert_eq!(rt.ready.front(), Some(&1), "se forzo B antes que A");
+
+    // la grabacion esperaba que despertara el hilo 7
+    rt.run_once();
+    let div = rt.replay.divergence().expect("debe divergir");
+    assert_eq!(div.index, 1);
+    assert_eq!(div.expected, SchedDecision::Wake(7));
+    assert!(!rt.replay.is_replaying(), "vuelve a decidir el scheduler");
+
+    assert!(SchedRecording::parse("dispatch x").is_err());
+
+    println!("  Test pasado: la divergencia se detecta!");
+}
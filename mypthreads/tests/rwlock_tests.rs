@@ -0,0 +1,89 @@
+//! tests del lock de lectores/escritores
+
+use mypthreads::api_context::ctx_suspend;
+use mypthreads::mypthreads_api::{
+    my_thread_create, my_waitgroup_add, my_waitgroup_done, my_waitgroup_init, my_waitgroup_wait, runtime_init,
+    SchedulerParams,
+};
+use mypthreads::signals::ThreadSignal;
+use mypthreads::sync::{MyRwLock, RwPreference};
+use mypthreads::MyThreadError;
+use std::sync::{Arc, Mutex};
+
+type Body = Box<dyn Fn(&MyRwLock<u32>) -> u32 + Send>;
+
+/// Un lector largo, luego un escritor y luego otro lector; retorna el orden en que entraron
+fn contend(preference: RwPreference) -> Vec<&'static str> {
+    let lock = Arc::new(MyRwLock::with_preference(0u32, preference));
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let finished = Arc::new(my_waitgroup_init().unwrap());
+    my_waitgroup_add(&finished, 3).unwrap();
+
+    let spawn = |name: &'static str, body: Body| {
+        let (lock, log, finished) = (lock.clone(), log.clone(), finished.clone());
+        my_thread_create(
+            name,
+            SchedulerParams::RoundRobin,
+            Box::new(move |_, _| {
+                let value = body(&lock);
+                log.lock().unwrap().push((name, value));
+                my_waitgroup_done(&finished).unwrap();
+                ThreadSignal::Exit
+            }),
+        )
+        .unwrap();
+    };
+
+    spawn(
+        "lector-largo",
+        Box::new(|lock| {
+            let guard = lock.read().unwrap();
+            assert!(lock.try_write().is_none());
+            // sostiene la lectura varios despachos
+            for _ in 0..3 {
+                ctx_suspend(ThreadSignal::Yield);
+            }
+            *guard
+        }),
+    );
+    spawn(
+        "escritor",
+        Box::new(|lock| {
+            let mut guard = lock.write().unwrap();
+            assert_eq!(lock.read().err(), Some(MyThreadError::Deadlock));
+            *guard += 1;
+            *guard
+        }),
+    );
+    spawn("lector", Box::new(|lock| *lock.read().unwrap()));
+
+    my_waitgroup_wait(&finished).unwrap();
+    let log = log.lock().unwrap().clone();
+    println!("{:?}: {:?}", preference, log);
+    log.into_iter().map(|(name, _)| name).collect()
+}
+
+#[test]
+fn test_rwlock_preferences() {
+    println!("\n=== TEST: Lock de lectores/escritores ===\n");
+
+    runtime_init();
+
+    // sin hilos: el principal también puede tomarlo
+    let lock = MyRwLock::new(String::from("ciudad"));
+    {
+        let a = lock.read().unwrap();
+        let b = lock.try_read().expect("varios lectores a la vez");
+        assert_eq!(a.len() + b.len(), 12);
+        assert!(lock.try_write().is_none());
+    }
+    lock.write().unwrap().push('!');
+    assert_eq!(*lock.read().unwrap(), "ciudad!");
+
+    // con escritores primero, el lector que llega despues del escritor espera
+    assert_eq!(contend(RwPreference::Writers), vec!["lector-largo", "escritor", "lector"]);
+    // con lectores primero, entra junto al lector largo y el escritor queda de ultimo
+    assert_eq!(contend(RwPreference::Readers), vec!["lector", "lector-largo", "escritor"]);
+
+    println!("  Test pasado: el rwlock respeta la preferencia!");
+}
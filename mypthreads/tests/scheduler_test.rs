@@ -3,6 +3,8 @@
 use mypthreads::sched::select_next_thread;
 // --- CORRECCIÓN 1: Importar ThreadId desde mypthreads ---
 use mypthreads::thread::{MyThread, SchedulerType, ThreadId};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::collections::{HashMap, VecDeque};
 
 // Nota: Para que esto funcione, la struct MyThread y su constructor `new`
@@ -42,7 +44,8 @@ fn unit_test_real_time_is_always_chosen() {
     ready_queue.push_back(2);
 
     // 2. Ejecución (llamar a la función que quieres probar)
-    let selected_id = select_next_thread(&ready_queue, &threads, 0);
+    let mut rng = StdRng::seed_from_u64(0);
+    let selected_id = select_next_thread(&ready_queue, &threads, 0, &mut rng);
 
     // 3. Verificación (comprobar que el resultado es el esperado)
     assert_eq!(
@@ -50,4 +53,33 @@ fn unit_test_real_time_is_always_chosen() {
         Some(2),
         "El scheduler de Tiempo Real debería haber sido elegido."
     );
-}
\ No newline at end of file
+}
+#[test]
+fn unit_test_same_seed_same_lottery() {
+    let mut threads: HashMap<ThreadId, Box<MyThread>> = HashMap::new();
+    let mut ready_queue = VecDeque::new();
+    for tid in 1..=4 {
+        let thread = MyThread::new(
+            tid,
+            format!("L-{}", tid),
+            SchedulerType::Lottery,
+            tid * 10,
+            None,
+            Box::new(|_, _| unreachable!()),
+        );
+        threads.insert(tid, Box::new(thread));
+        ready_queue.push_back(tid);
+    }
+
+    // Dos generadores con la misma semilla deben producir los mismos ganadores
+    let mut rng_a = StdRng::seed_from_u64(42);
+    let mut rng_b = StdRng::seed_from_u64(42);
+    let picks_a: Vec<_> = (0..50)
+        .map(|_| select_next_thread(&ready_queue, &threads, 0, &mut rng_a))
+        .collect();
+    let picks_b: Vec<_> = (0..50)
+        .map(|_| select_next_thread(&ready_queue, &threads, 0, &mut rng_b))
+        .collect();
+
+    assert_eq!(picks_a, picks_b, "La misma semilla debería repetir los sorteos.");
+}
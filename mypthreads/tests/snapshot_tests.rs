@@ -0,0 +1,60 @@
+//! tests de la captura del estado del runtime
+
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::snapshot::WaitReason;
+use mypthreads::thread::{SchedulerType, ThreadState};
+
+#[test]
+fn test_snapshot_reports_threads_and_queues() {
+    println!("\n=== TEST: Captura del estado del runtime ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let mut joined = false;
+    let joiner = rt.spawn(
+        "Joiner",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            if joined {
+                ThreadSignal::Exit
+            } else {
+                joined = true;
+                ThreadSignal::Join(2)
+            }
+        }),
+        1,
+        None,
+    );
+    let target = rt.spawn("Target", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Yield), 1, None);
+    let sleeper = rt.spawn("Sleeper", SchedulerType::RealTime, Box::new(|_, _| ThreadSignal::Block), 1, Some(500));
+
+    rt.run(3);
+
+    let snap = rt.snapshot();
+    println!("{}", snap.to_json());
+
+    assert_eq!(snap.now_ms, 30);
+    assert_eq!(snap.threads.len(), 3);
+    assert_eq!(snap.ready, vec![target]);
+    assert_eq!(snap.blocked.len(), 2);
+
+    let j = snap.thread(joiner).unwrap();
+    assert_eq!(j.state, ThreadState::Blocked);
+    assert_eq!(j.wait_reason, Some(WaitReason::Join(target)));
+
+    let t = snap.thread(target).unwrap();
+    assert_eq!(t.joiners, vec![joiner]);
+    assert_eq!(t.wait_reason, None);
+
+    let s = snap.thread(sleeper).unwrap();
+    assert_eq!(s.sched_type, SchedulerType::RealTime);
+    assert_eq!(s.deadline, Some(500));
+    assert_eq!(s.wait_reason, Some(WaitReason::Explicit));
+
+    let json = snap.to_json();
+    assert!(json.starts_with(r#"{"now_ms":30,"ready":[2],"#));
+    assert!(json.contains(r#""wait_reason":{"kind":"join","target":2}"#));
+    assert!(json.contains(r#""name":"Sleeper","state":"Blocked","sched_type":"RealTime""#));
+
+    println!("  Test pasado: la captura refleja el runtime!");
+}
@@ -0,0 +1,340 @@
+//! tests de las estadisticas por hilo del runtime
+
+use mypthreads::channels::SimpleMutex;
+use mypthreads::mypthreads_api::{my_mutex_init, my_mutex_lock, my_mutex_unlock, MyMutex};
+use mypthreads::runtime::{ThreadRuntimeV2, QUANTUM_MS};
+use mypthreads::signals::ThreadSignal;
+use mypthreads::stats::{BlockReason, OTHER_STACK_CLASS};
+use mypthreads::thread::{SchedulerType, ThreadState};
+use mypthreads::trace::TraceEventKind;
+
+#[test]
+fn test_stats_count_dispatches_and_waits() {
+    println!("\n=== TEST: Despachos, ejecucion y espera en cola ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let a = rt.spawn("A", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Yield), 1, None);
+    let b = rt.spawn("B", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::YieldEarly(3)), 1, None);
+
+    rt.run(4);
+
+    let sa = rt.thread_stats(a).unwrap();
+    let sb = rt.thread_stats(b).unwrap();
+    println!("  A: {:?}\n  B: {:?}", sa, sb);
+
+    assert_eq!(sa.dispatches, 2);
+    assert_eq!(sa.run_ms, 2 * QUANTUM_MS);
+    assert_eq!(sa.ready_ms, 30, "espera el primer quantum y luego el de B");
+    assert_eq!(sb.dispatches, 2);
+    assert_eq!(sb.run_ms, 6, "un yield temprano cuenta solo lo usado");
+    assert_eq!(sb.ready_ms, 40);
+
+    let summary = rt.stats_summary();
+    assert_eq!(summary.threads, 2);
+    assert_eq!(summary.dispatches, 4);
+    assert_eq!(summary.run_ms, sa.run_ms + sb.run_ms);
+    assert_eq!(summary.now_ms, 40);
+
+    println!("  Test pasado: los contadores de despacho son correctos!");
+}
+
+#[test]
+fn test_stats_blocked_time_by_reason() {
+    println!("\n=== TEST: Tiempo bloqueado por join ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    // el hilo B se crea despues, su tid es el siguiente
+    let mut joined = false;
+    let a = rt.spawn(
+        "Joiner",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            if joined {
+                ThreadSignal::Exit
+            } else {
+                joined = true;
+                ThreadSignal::Join(2)
+            }
+        }),
+        1,
+        None,
+    );
+    let mut steps = 0;
+    let b = rt.spawn(
+        "Target",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            steps += 1;
+            if steps < 3 {
+                ThreadSignal::Yield
+            } else {
+                ThreadSignal::Exit
+            }
+        }),
+        1,
+        None,
+    );
+    assert_eq!(b, 2);
+
+    rt.run(5);
+
+    let sa = rt.thread_stats(a).unwrap();
+    println!("  Joiner: {:?}", sa);
+    assert_eq!(sa.blocked_ms.get(&BlockReason::Join), Some(&30));
+    assert_eq!(sa.total_blocked_ms(), 30);
+    assert_eq!(sa.dispatches, 2);
+
+    println!("  Test pasado: el bloqueo se atribuye al join!");
+}
+
+#[test]
+fn test_stats_mutex_contention() {
+    println!("\n=== TEST: Contencion de mutex ===\n");
+
+    let mutex: &'static SimpleMutex = Box::leak(Box::new(SimpleMutex::new()));
+    let addr = mutex as *const SimpleMutex as usize;
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let mut step_a = 0;
+    let a = rt.spawn(
+        "Holder",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            step_a += 1;
+            match step_a {
+                1 => ThreadSignal::MutexLock(addr),
+                2 => ThreadSignal::Yield,
+                3 => ThreadSignal::MutexUnlock(addr),
+                _ => ThreadSignal::Exit,
+            }
+        }),
+        1,
+        None,
+    );
+    let mut step_b = 0;
+    let b = rt.spawn(
+        "Waiter",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            step_b += 1;
+            match step_b {
+                1 => ThreadSignal::MutexLock(addr),
+                2 => ThreadSignal::MutexUnlock(addr),
+                _ => ThreadSignal::Exit,
+            }
+        }),
+        1,
+        None,
+    );
+
+    rt.run(8);
+
+    let sa = rt.thread_stats(a).unwrap();
+    let sb = rt.thread_stats(b).unwrap();
+    println!("  Holder: {:?}\n  Waiter: {:?}", sa, sb);
+
+    assert_eq!(sa.mutex_acquisitions, 1);
+    assert_eq!(sa.mutex_contentions, 0);
+    assert_eq!(sb.mutex_acquisitions, 1, "lo recibe por traspaso al liberarse");
+    assert_eq!(sb.mutex_contentions, 1);
+    assert_eq!(sb.blocked_ms.get(&BlockReason::Mutex), Some(&20));
+
+    let summary = rt.stats_summary();
+    assert_eq!(summary.mutex_contentions, 1);
+    assert_eq!(summary.mutex_acquisitions, 2);
+
+    println!("  Test pasado: la contencion de mutex se contabiliza!");
+}
+
+#[test]
+fn test_stats_mutex_api_handoff() {
+    println!("\n=== TEST: my_mutex_lock/unlock llevan la misma cuenta que las señales ===\n");
+
+    let mutex: &'static MyMutex = Box::leak(Box::new(my_mutex_init().unwrap()));
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    rt.tracer.enable();
+    let mut step_h = 0;
+    let holder = rt.spawn(
+        "Holder",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            step_h += 1;
+            match step_h {
+                1 => my_mutex_lock(mutex).unwrap(),
+                2 => ThreadSignal::Yield,
+                3 => my_mutex_unlock(mutex).unwrap(),
+                _ => ThreadSignal::Exit,
+            }
+        }),
+        1,
+        None,
+    );
+    let waiter = |first: bool| {
+        let mut step = 0;
+        Box::new(move |_, _| {
+            step += 1;
+            match step {
+                1 => my_mutex_lock(mutex).unwrap(),
+                2 if first => ThreadSignal::Yield,
+                _ => {
+                    my_mutex_unlock(mutex).unwrap();
+                    ThreadSignal::Exit
+                }
+            }
+        })
+    };
+    let first = rt.spawn("First", SchedulerType::RoundRobin, waiter(true), 1, None);
+    let second = rt.spawn("Second", SchedulerType::RoundRobin, waiter(false), 1, None);
+
+    // Holder toma el lock, los dos esperan y Holder lo suelta en su tercer paso
+    rt.run(5);
+    assert_eq!(rt.threads[&first].state, ThreadState::Ready);
+    assert_eq!(rt.threads[&second].lending_to, Some(first), "presta al nuevo dueño");
+
+    rt.run(10);
+
+    for tid in [holder, first, second] {
+        let stats = rt.thread_stats(tid).unwrap();
+        assert_eq!(stats.mutex_acquisitions, 1, "hilo {}", tid);
+    }
+    assert_eq!(rt.stats_summary().mutex_contentions, 2);
+    let handoffs: Vec<_> = rt
+        .tracer
+        .events()
+        .iter()
+        .filter_map(|e| match e.kind {
+            TraceEventKind::MutexHandoff { from } => Some((from, e.tid)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(handoffs, vec![(holder, first), (first, second)]);
+
+    println!("  Test pasado: el traspaso por la API se contabiliza!");
+}
+
+#[test]
+fn test_stats_summary_counts_reaped_and_open_blocks() {
+    println!("\n=== TEST: El resumen cuenta los liberados y los bloqueos en curso ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let gone = rt.spawn("gone", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Exit), 1, None);
+    rt.threads.get_mut(&gone).unwrap().detached = true;
+    let sleeper = rt.spawn("sleeper", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Block), 1, None);
+    rt.spawn("busy", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Yield), 1, None);
+
+    rt.run(6);
+    assert!(!rt.threads.contains_key(&gone), "el detached se libero");
+
+    let summary = rt.stats_summary();
+    println!("  {:?}", summary);
+    assert_eq!(summary.threads, 3);
+    let dispatched: u64 = rt.threads.values().map(|t| t.stats.dispatches).sum();
+    assert_eq!(summary.dispatches, dispatched + 1, "el paso del liberado sigue contando");
+    // el sleeper se bloqueo a los 20 ms y sigue asi: su espera cuenta hasta ahora
+    assert_eq!(rt.thread_stats(sleeper).unwrap().total_blocked_ms(), 0);
+    assert_eq!(summary.now_ms, 60);
+    assert_eq!(summary.blocked_ms.get(&BlockReason::Explicit), Some(&40));
+
+    println!("  Test pasado: el resumen no pierde hilos ni esperas abiertas!");
+}
+
+#[test]
+fn test_stats_deadline_miss_counted_once() {
+    println!("\n=== TEST: Deadlines perdidos ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let rt_tid = rt.spawn("RT", SchedulerType::RealTime, Box::new(|_, _| ThreadSignal::Yield), 1, Some(5));
+
+    rt.run(3);
+
+    let stats = rt.thread_stats(rt_tid).unwrap();
+    assert_eq!(stats.dispatches, 3);
+    assert_eq!(stats.deadline_misses, 1, "el mismo deadline vencido cuenta una vez");
+
+    println!("  Test pasado: los deadlines perdidos se cuentan!");
+}
+
+/// usa unos cuantos KB de pila antes de terminar
+fn dig(depth: u32) -> u64 {
+    let buf = std::hint::black_box([depth as u8; 256]);
+    if depth == 0 {
+        buf[0] as u64
+    } else {
+        dig(depth - 1) + buf[255] as u64
+    }
+}
+
+#[test]
+fn test_stats_stack_high_water_mark() {
+    println!("\n=== TEST: Pico de uso de pila por hilo y por nombre ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let walkers: Vec<_> = (0..2)
+        .map(|_| rt.spawn("walker", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Exit), 1, None))
+        .collect();
+    let digger = rt.spawn(
+        "digger",
+        SchedulerType::RoundRobin,
+        Box::new(|_, _| {
+            std::hint::black_box(dig(8));
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+    let idle = rt.spawn("idle", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Block), 1, None);
+
+    rt.run(10);
+
+    let stack_bytes = rt.thread_stats(digger).unwrap().stack_bytes;
+    assert!(stack_bytes >= 8192, "al menos lo pedido: {}", stack_bytes);
+    for tid in walkers.iter().copied().chain([digger, idle]) {
+        let stats = rt.thread_stats(tid).unwrap();
+        assert!(stats.stack_peak_bytes <= stats.stack_bytes);
+    }
+
+    // el terminado se sigue contando aunque se libere
+    assert!(rt.reap(walkers[0]));
+    let usage = rt.stack_usage();
+    println!("  uso de pila: {:?}", usage);
+    assert_eq!(usage["walker"].threads, 2);
+    assert_eq!(usage["digger"].threads, 1);
+    assert_eq!(usage["idle"].threads, 1, "los vivos se miden al pedirlo");
+    assert_eq!(usage["digger"].stack_bytes, stack_bytes);
+    assert!(usage["digger"].peak_bytes >= usage["walker"].peak_bytes);
+
+    let summary = rt.stats_summary();
+    let highest = usage.values().map(|u| u.peak_bytes).max().unwrap();
+    assert!(summary.stack_peak_bytes <= highest);
+
+    println!("  Test pasado: se mide el pico de pila de cada hilo!");
+}
+
+#[test]
+fn test_stack_usage_by_group_is_capped() {
+    println!("\n=== TEST: Uso de pila por grupo, con un tope de clases ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    rt.max_stack_classes = 2;
+    let cars = rt.create_group("cars");
+    // nombres unicos, como los agentes de threadcity: cuentan en su grupo
+    for i in 0..3 {
+        let tid = rt.spawn(format!("Car-{}", i), SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Exit), 1, None);
+        rt.set_group(tid, Some(cars));
+    }
+    // sin grupo cuenta su nombre, hasta llenar las clases
+    for name in ["solo", "extra-1", "extra-2"] {
+        rt.spawn(name, SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Exit), 1, None);
+    }
+
+    rt.run(10);
+
+    let usage = rt.stack_usage();
+    println!("  uso de pila: {:?}", usage);
+    let classes: Vec<_> = usage.keys().map(String::as_str).collect();
+    assert_eq!(classes, vec![OTHER_STACK_CLASS, "cars", "solo"]);
+    assert_eq!(usage["cars"].threads, 3);
+    assert_eq!(usage[OTHER_STACK_CLASS].threads, 2);
+
+    println!("  Test pasado: las clases de pila no crecen con los nombres!");
+}
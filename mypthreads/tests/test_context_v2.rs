@@ -2,7 +2,9 @@
 //! dos hilos hacen ping-pong usando yield
 
 use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::channels::ThreadChannels;
 use mypthreads::signals::ThreadSignal;
+use mypthreads::thread_data::ThreadGlobalContext;
 use mypthreads::thread::{ContextThreadEntry, SchedulerType};
 use std::sync::{Arc, Mutex};
 
@@ -227,4 +229,34 @@ fn test_context_state_preservation() {
     assert_eq!(final_count, 4, "El contador debería llegar a 4");
 
     println!("  Test pasado: El estado se preserva entre cambios de contexto!");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_global_context_is_restored_after_each_step() {
+    println!("\n=== TEST: El contexto global del despachador sobrevive a sus hilos ===\n");
+
+    // el despachador tiene su propio contexto, como un hilo verde que corre otro runtime
+    let outer = ThreadGlobalContext::init(77, ThreadChannels::new());
+    assert!(outer.is_none());
+
+    let mut rt = ThreadRuntimeV2::new();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let tid = rt.spawn(
+        "inner",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            log.lock().unwrap().push(ThreadGlobalContext::with(|ctx| ctx.tid));
+            ThreadSignal::Yield
+        }),
+        1,
+        None,
+    );
+    rt.run(3);
+
+    assert_eq!(*seen.lock().unwrap(), vec![tid; 3]);
+    assert_eq!(ThreadGlobalContext::with(|ctx| ctx.tid), 77, "no lo piso el hilo interno");
+
+    ThreadGlobalContext::restore(None);
+    println!("  Test pasado: cada paso repone el contexto global que habia!");
+}
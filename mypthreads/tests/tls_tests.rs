@@ -0,0 +1,116 @@
+//! tests del almacenamiento local por hilo verde
+
+use mypthreads::api_context::{current_tid, try_current_tid};
+use mypthreads::mypthreads_api::{my_getspecific, my_key_create, my_key_delete, my_setspecific};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::{SchedulerType, ThreadState};
+use mypthreads::MyThreadError;
+use std::any::Any;
+use std::sync::Mutex;
+
+static DESTROYED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn record_destroyed(value: Box<dyn Any + Send>) {
+    if let Ok(name) = value.downcast::<String>() {
+        DESTROYED.lock().unwrap().push(*name);
+    }
+}
+
+#[test]
+fn test_keys_are_per_green_thread() {
+    println!("\n=== TEST: Llaves locales por hilo verde ===\n");
+
+    let key = my_key_create(Some(record_destroyed)).unwrap();
+    let checks: &'static Mutex<u32> = Box::leak(Box::new(Mutex::new(0)));
+
+    // el hilo principal tiene su propio valor
+    assert_eq!(my_setspecific(key, String::from("main")), Ok(()));
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let mut tids = Vec::new();
+    for i in 0..3 {
+        let name = format!("worker-{}", i);
+        let mut step = 0;
+        tids.push(rt.spawn(
+            name.clone(),
+            SchedulerType::RoundRobin,
+            Box::new(move |tid, _| {
+                step += 1;
+                assert_eq!(current_tid(), tid, "current_tid corresponde al hilo que corre");
+                match step {
+                    1 => {
+                        assert_eq!(my_getspecific::<String>(key), Ok(None), "cada hilo empieza vacío");
+                        my_setspecific(key, name.clone()).unwrap();
+                        ThreadSignal::Yield
+                    }
+                    2 => {
+                        assert_eq!(my_getspecific::<String>(key), Ok(Some(name.clone())));
+                        *checks.lock().unwrap() += 1;
+                        ThreadSignal::Yield
+                    }
+                    _ => ThreadSignal::Exit,
+                }
+            }),
+            1,
+            None,
+        ));
+    }
+
+    rt.run(9);
+
+    assert_eq!(*checks.lock().unwrap(), 3, "los tres hilos vieron su propio valor");
+    assert_eq!(
+        *DESTROYED.lock().unwrap(),
+        vec!["worker-0", "worker-1", "worker-2"],
+        "el destructor corre al terminar cada hilo"
+    );
+    assert_eq!(my_getspecific::<String>(key), Ok(Some(String::from("main"))));
+    assert_eq!(try_current_tid(), None, "fuera de los hilos no hay tid activo");
+
+    assert_eq!(my_key_delete(key), Ok(()));
+    let deleted = Err(MyThreadError::InvalidArgument("llave inexistente"));
+    assert_eq!(my_key_delete(key), deleted);
+    assert_eq!(my_setspecific(key, 1u32), deleted);
+    assert_eq!(my_getspecific::<String>(key), deleted.map(|()| None));
+
+    println!("  Test pasado: cada hilo ve sus propios valores!");
+}
+
+static CANCELLED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn record_cancelled(value: Box<dyn Any + Send>) {
+    if let Ok(name) = value.downcast::<String>() {
+        CANCELLED.lock().unwrap().push(*name);
+    }
+}
+
+#[test]
+fn test_cancel_runs_key_destructors() {
+    println!("\n=== TEST: Cancelar un hilo bloqueado corre sus destructores ===\n");
+
+    let key = my_key_create(Some(record_cancelled)).unwrap();
+    my_setspecific(key, String::from("main")).unwrap();
+
+    let mut rt = ThreadRuntimeV2::with_seed(1);
+    let victim = rt.spawn(
+        "victim",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            my_setspecific(key, String::from("victim")).unwrap();
+            ThreadSignal::Block
+        }),
+        1,
+        None,
+    );
+    rt.run(2);
+    assert_eq!(rt.threads[&victim].state, ThreadState::Blocked);
+    assert!(CANCELLED.lock().unwrap().is_empty());
+
+    assert!(rt.cancel_thread(victim));
+    assert_eq!(*CANCELLED.lock().unwrap(), vec!["victim"]);
+    assert!(rt.threads[&victim].locals.is_empty());
+    assert_eq!(my_getspecific::<String>(key), Ok(Some(String::from("main"))), "el llamador conserva lo suyo");
+
+    println!("  Test pasado: el hilo cancelado suelta sus llaves!");
+}
@@ -0,0 +1,62 @@
+//! tests de la traza del scheduler en formato Chrome Trace Event
+
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::SchedulerType;
+use mypthreads::trace::TraceEventKind;
+
+#[test]
+fn test_trace_records_thread_lifecycle() {
+    println!("\n=== TEST: Traza de despacho, yield y exit ===\n");
+
+    let mut rt = ThreadRuntimeV2::new();
+    rt.tracer.enable();
+
+    let mut steps = 0;
+    let tid = rt.spawn(
+        "Worker \"1\"",
+        SchedulerType::RoundRobin,
+        Box::new(move |_, _| {
+            steps += 1;
+            if steps < 2 {
+                ThreadSignal::Yield
+            } else {
+                ThreadSignal::Exit
+            }
+        }),
+        1,
+        None,
+    );
+
+    rt.run(5);
+
+    let kinds: Vec<_> = rt.tracer.events().iter().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TraceEventKind::Dispatch,
+            TraceEventKind::Yield,
+            TraceEventKind::Dispatch,
+            TraceEventKind::Exit,
+        ]
+    );
+    assert!(rt.tracer.events().iter().all(|e| e.tid == tid));
+    assert_eq!(rt.tracer.events()[0].now_ms, 10);
+
+    let json = rt.tracer.to_chrome_json();
+    println!("{}", json);
+    assert!(json.starts_with("{\"displayTimeUnit\":\"ms\",\"traceEvents\":["));
+    assert!(json.contains(r#""args":{"name":"Worker \"1\""}"#), "la pista lleva el nombre del hilo");
+    assert_eq!(json.matches(r#""ph":"B""#).count(), 2);
+    assert_eq!(json.matches(r#""ph":"E""#).count(), 2);
+
+    println!("  Test pasado: la traza se exporta correctamente!");
+}
+
+#[test]
+fn test_trace_disabled_by_default() {
+    let mut rt = ThreadRuntimeV2::new();
+    rt.spawn("Quick", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Exit), 1, None);
+    rt.run(2);
+    assert!(rt.tracer.events().is_empty());
+}
@@ -0,0 +1,124 @@
+//! tests del watchdog: despachos largos y hilos en livelock
+
+use mypthreads::mypthreads_api::{
+    my_thread_create, my_thread_progress, run_simulation, runtime_init, runtime_unblock_all, runtime_watchdog_config,
+    runtime_watchdog_flags, SchedulerParams,
+};
+use mypthreads::runtime::ThreadRuntimeV2;
+use mypthreads::signals::ThreadSignal;
+use mypthreads::thread::SchedulerType;
+use mypthreads::{ctx_progress, MyThreadError, RuntimeEvent, WatchdogFlag};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[test]
+fn test_watchdog_flags_slow_and_livelocked_threads() {
+    println!("\n=== TEST: El watchdog marca despachos largos y livelocks ===\n");
+
+    let mut rt = ThreadRuntimeV2::with_seed(4);
+    rt.watchdog.slow_dispatch = Some(Duration::from_millis(10));
+    rt.watchdog.livelock_wakeups = Some(5);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    rt.add_observer(move |_now: u64, event: &RuntimeEvent| {
+        if let RuntimeEvent::Watchdog { tid, flag } = event {
+            sink.lock().unwrap().push((*tid, *flag));
+        }
+    });
+
+    // no cede hasta que pasa el umbral
+    let slow = rt.spawn(
+        "slow",
+        SchedulerType::RoundRobin,
+        Box::new(|_, _| {
+            std::thread::sleep(Duration::from_millis(30));
+            ThreadSignal::Exit
+        }),
+        1,
+        None,
+    );
+    // como un agente que falla `try_enter` en cada ronda
+    let spinner = rt.spawn("spinner", SchedulerType::RoundRobin, Box::new(|_, _| ThreadSignal::Block), 1, None);
+    // tambien se bloquea en cada ronda, pero avisa que avanzo
+    let worker = rt.spawn(
+        "worker",
+        SchedulerType::RoundRobin,
+        Box::new(|_, _| {
+            ctx_progress();
+            ThreadSignal::Block
+        }),
+        1,
+        None,
+    );
+
+    rt.run(10);
+    for _ in 0..4 {
+        rt.unblock_all_threads();
+        rt.run(10);
+    }
+    assert!(!rt.watchdog.is_flagged(spinner), "cuatro rondas todavia no alcanzan");
+
+    rt.unblock_all_threads();
+    rt.run(10);
+    let flagged = rt.watchdog.flagged().clone();
+    println!("marcados: {:?}", flagged);
+    assert!(matches!(flagged[&slow], WatchdogFlag::SlowDispatch { elapsed_ms } if elapsed_ms >= 30));
+    assert_eq!(flagged[&spinner], WatchdogFlag::Livelock { wakeups: 5 });
+    assert!(!rt.watchdog.is_flagged(worker));
+
+    assert_eq!(rt.threads[&slow].stats.slow_dispatches, 1);
+    assert!(rt.threads[&slow].stats.max_dispatch_us >= 30_000);
+    assert_eq!(rt.threads[&spinner].stats.livelocks, 1);
+    assert_eq!(rt.stats_summary().livelocks, 1);
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![(slow, flagged[&slow]), (spinner, flagged[&spinner])]
+    );
+
+    // despertar a un hilo directamente no cuenta como despertar inutil
+    for _ in 0..10 {
+        rt.unblock_thread(spinner);
+        rt.run(1);
+    }
+    assert_eq!(rt.threads[&spinner].stats.livelocks, 1);
+
+    println!("  Test pasado: el watchdog avisa sin frenar a los demas hilos!");
+}
+
+#[test]
+fn test_watchdog_through_global_api() {
+    println!("\n=== TEST: Watchdog desde la API global ===\n");
+
+    runtime_init();
+    runtime_watchdog_config(None, Some(3)).unwrap();
+    assert!(matches!(my_thread_progress(), Err(MyThreadError::NotInThread)));
+
+    let mut round = 0;
+    let busy = my_thread_create(
+        "busy",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            // avanza en las rondas pares
+            round += 1;
+            if round % 2 == 0 {
+                my_thread_progress().unwrap();
+            }
+            ThreadSignal::Block
+        }),
+    )
+    .unwrap();
+    let stuck = my_thread_create("stuck", SchedulerParams::RoundRobin, Box::new(|_, _| ThreadSignal::Block)).unwrap();
+
+    run_simulation(5).unwrap();
+    for _ in 0..6 {
+        runtime_unblock_all().unwrap();
+        run_simulation(5).unwrap();
+    }
+
+    let flags = runtime_watchdog_flags().unwrap();
+    println!("marcados: {:?}", flags);
+    assert!(!flags.contains_key(&busy));
+    assert_eq!(flags.get(&stuck), Some(&WatchdogFlag::Livelock { wakeups: 3 }));
+
+    println!("  Test pasado: la API global configura y consulta el watchdog!");
+}
@@ -28,8 +28,16 @@ impl Default for CityLayout {
 
 /// Crea una ciudad configurada según los requerimientos
 pub fn create_city() -> (City, CityLayout) {
-    let layout = CityLayout::default();
-    let mut city = City::new(layout.grid_rows, layout.grid_cols);
+    build_city(CityLayout::default(), City::new)
+}
+
+/// Igual que `create_city`, pero con el spawner sembrado para repetir la corrida
+pub fn create_city_with_seed(seed: u64) -> (City, CityLayout) {
+    build_city(CityLayout::default(), |rows, cols| City::with_seed(rows, cols, seed))
+}
+
+fn build_city(layout: CityLayout, new_city: impl FnOnce(u32, u32) -> City) -> (City, CityLayout) {
+    let mut city = new_city(layout.grid_rows, layout.grid_cols);
     
     tc_log!("\n╔════════════════════════════════════════════════════════════╗");
     tc_log!("║              Creando ThreadCity                           ║");
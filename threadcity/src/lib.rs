@@ -4,10 +4,11 @@ pub mod sim;
 pub mod config;
 pub mod log;
 pub mod runner;        
+pub mod services;
 
 pub use model::*;
 pub use agents::*;
 pub use sim::*;
 pub use config::*;
 pub use log::*;
-pub use runner::run_simulation;  
+pub use runner::{run_simulation, run_simulation_with_seed};
@@ -2,6 +2,9 @@
 // Se puede redirigir con set_logger(fn(&str)) antes de correr la simulación
 
 use core::sync::atomic::{AtomicPtr, Ordering};
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::sync::{Mutex, MutexGuard};
 
 type LogFn = fn(&str);
 
@@ -13,6 +16,19 @@ fn default_log(s: &str) {
 // Almacena un puntero a función, se asume set_logger() se llama antes de uso concurrente
 static LOGGER_PTR: AtomicPtr<()> = AtomicPtr::new(default_log as *mut ());
 
+/// líneas que esperan a los sinks del log (ver `services.rs`); None si no hay sinks
+struct SinkQueue {
+    lines: Vec<String>,
+    // avisa al hilo del sink, que espera el otro extremo en el reactor
+    notify: UnixStream,
+}
+
+static SINK_QUEUE: Mutex<Option<SinkQueue>> = Mutex::new(None);
+
+fn sink_queue() -> MutexGuard<'static, Option<SinkQueue>> {
+    SINK_QUEUE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 #[inline]
 pub fn set_logger(f: LogFn) {
     LOGGER_PTR.store(f as *mut (), Ordering::Relaxed);
@@ -23,6 +39,39 @@ pub fn log_str(s: &str) {
     let p = LOGGER_PTR.load(Ordering::Relaxed);
     let f: LogFn = unsafe { core::mem::transmute(p) };
     f(s);
+    if let Some(queue) = sink_queue().as_mut() {
+        queue.lines.push(s.to_string());
+        // no bloqueante: si el aviso anterior no se leyó, con ese alcanza
+        let _ = (&queue.notify).write(&[1]);
+    }
+}
+
+/// Empieza a guardar las líneas para los sinks; `notify` debe ser no bloqueante
+pub(crate) fn open_sink_queue(notify: UnixStream) {
+    *sink_queue() = Some(SinkQueue {
+        lines: Vec::new(),
+        notify,
+    });
+}
+
+/// Deja de guardar líneas para los sinks
+pub(crate) fn close_sink_queue() {
+    *sink_queue() = None;
+}
+
+/// Despierta al sink aunque no haya líneas nuevas (p. ej. para cerrarlo)
+pub(crate) fn notify_sink() {
+    if let Some(queue) = sink_queue().as_ref() {
+        let _ = (&queue.notify).write(&[1]);
+    }
+}
+
+/// Saca las líneas pendientes para los sinks
+pub(crate) fn take_sink_lines() -> Vec<String> {
+    sink_queue()
+        .as_mut()
+        .map(|queue| std::mem::take(&mut queue.lines))
+        .unwrap_or_default()
 }
 
 #[macro_export]
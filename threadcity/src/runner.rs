@@ -1,17 +1,23 @@
+use crate::services::Services;
 use crate::tc_log;
 use crate::{
-    create_city, create_shared_city, nearest_bridge, AgentInfo, AgentState, AgentType, Ambulance,
+    create_city_with_seed, create_shared_city, nearest_bridge, AgentInfo, AgentState, AgentType, Ambulance,
     Boat, CargoTruck, CityLayout, Coord, PlantStatus, SharedCity, SupplyKind, TrafficDirection,
     Vehicle,
 };
 use mypthreads::{
     mypthreads_api::{
-        my_thread_chsched, my_thread_create, runtime_run_cycles, runtime_unblock_all,
-        SchedulerParams,
+        my_currency_create, my_group_chsched, my_group_create, my_group_stats,
+        my_thread_create_in_group, my_thread_progress, my_waitgroup_add, my_waitgroup_done, my_waitgroup_init,
+        my_waitgroup_wait,
+        runtime_record_start, runtime_record_write, runtime_replay_divergence, runtime_replay_start,
+        runtime_stats_summary,
+        runtime_set_seed, runtime_trace_enable, runtime_trace_write, runtime_unblock_all,
+        MyWaitGroup, SchedulerParams,
     },
-    ThreadId, ThreadSignal,
+    GroupId, MyResult, SchedRecording, ThreadId, ThreadSignal,
 };
-use rand::rng;
+use rand::rngs::StdRng;
 use rand::{prelude::*, Rng};
 use std::cmp::{max, min};
 use std::sync::atomic::{AtomicU32, Ordering};
@@ -23,13 +29,217 @@ fn get_next_agent_id() -> u32 {
     NEXT_AGENT_ID.fetch_add(1, Ordering::Relaxed)
 }
 
+/// Variable de entorno para fijar la semilla de la simulación
+pub const SEED_ENV_VAR: &str = "THREADCITY_SEED";
+
+/// Variable de entorno con la ruta donde escribir la traza del scheduler
+pub const TRACE_ENV_VAR: &str = "THREADCITY_TRACE";
+
+/// Variable de entorno con la ruta donde escribir las decisiones del scheduler
+pub const SCHED_LOG_ENV_VAR: &str = "THREADCITY_SCHED_LOG";
+
+/// Variable de entorno con la ruta donde grabar las decisiones del scheduler para reproducirlas
+pub const RECORD_ENV_VAR: &str = "THREADCITY_RECORD";
+
+/// Variable de entorno con una grabación a reproducir (usa también su semilla)
+pub const REPLAY_ENV_VAR: &str = "THREADCITY_REPLAY";
+
+/// Semilla de la corrida: la de `THREADCITY_SEED` si existe, si no una aleatoria
+pub fn simulation_seed() -> u64 {
+    std::env::var(SEED_ENV_VAR)
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or_else(rand::random)
+}
+
+/// Grabación indicada en `THREADCITY_REPLAY`, si hay una
+fn load_replay() -> Option<SchedRecording> {
+    let path = std::env::var(REPLAY_ENV_VAR).ok()?;
+    match SchedRecording::load(&path) {
+        Ok(rec) => Some(rec),
+        Err(e) => {
+            tc_log!("❌ No se pudo leer la grabación {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Un grupo del runtime por clase de agente; los camiones van agrupados por insumo
+/// para poder promover de una vez a los que lleva una emergencia
+struct AgentGroups {
+    cars: GroupId,
+    ambulances: GroupId,
+    boats: GroupId,
+    radioactive_trucks: GroupId,
+    water_trucks: GroupId,
+}
+
+impl AgentGroups {
+    fn create() -> Self {
+        let group = |name| my_group_create(name).expect("no se pudo crear el grupo de agentes");
+        Self {
+            cars: group("cars"),
+            ambulances: group("ambulances"),
+            boats: group("boats"),
+            radioactive_trucks: group("trucks-radioactive"),
+            water_trucks: group("trucks-water"),
+        }
+    }
+
+    fn of(&self, agent_type: AgentType) -> GroupId {
+        match agent_type {
+            AgentType::Car => self.cars,
+            AgentType::Ambulance => self.ambulances,
+            AgentType::Boat => self.boats,
+            AgentType::CargoTruck(SupplyKind::Radioactive) => self.radioactive_trucks,
+            AgentType::CargoTruck(SupplyKind::Water) => self.water_trucks,
+        }
+    }
+}
+
+/// Sincroniza cada paso de la ciudad con los agentes: cada agente vivo corre
+/// un paso por ronda y el hilo principal espera a que todos terminen antes de
+/// avanzar `City::update`
+#[derive(Clone)]
+struct StepSync {
+    round: std::sync::Arc<AtomicU32>,
+    live: std::sync::Arc<AtomicU32>,
+    pending: std::sync::Arc<MyWaitGroup>,
+}
+
+impl StepSync {
+    fn new() -> Self {
+        Self {
+            round: std::sync::Arc::new(AtomicU32::new(0)),
+            live: std::sync::Arc::new(AtomicU32::new(0)),
+            pending: std::sync::Arc::new(my_waitgroup_init().expect("no se pudo crear el waitgroup de pasos")),
+        }
+    }
+
+    /// registra un agente nuevo; hace su primer paso en la siguiente ronda
+    fn agent(&self) -> AgentStep {
+        self.live.fetch_add(1, Ordering::Relaxed);
+        AgentStep {
+            sync: self.clone(),
+            last_round: None,
+        }
+    }
+
+    /// resumen para el servidor de control
+    fn status(&self) -> crate::services::StatusFn {
+        let (round, live) = (self.round.clone(), self.live.clone());
+        std::sync::Arc::new(move || {
+            format!(
+                "ronda {}, agentes vivos {}",
+                round.load(Ordering::Relaxed),
+                live.load(Ordering::Relaxed)
+            )
+        })
+    }
+
+    /// abre una ronda y corre el runtime hasta que todos los agentes vivos hagan su paso
+    fn run_round(&self) -> MyResult<()> {
+        self.round.fetch_add(1, Ordering::Relaxed);
+        my_waitgroup_add(&self.pending, self.live.load(Ordering::Relaxed) as usize)?;
+        runtime_unblock_all()?;
+        let result = my_waitgroup_wait(&self.pending);
+        if result.is_err() {
+            // los que no alcanzaron a correr no arrastran la cuenta a la siguiente ronda
+            while my_waitgroup_done(&self.pending).is_ok() {}
+        }
+        result
+    }
+}
+
+/// Lado del agente de `StepSync`
+struct AgentStep {
+    sync: StepSync,
+    last_round: Option<u32>,
+}
+
+impl AgentStep {
+    /// corre la lógica del agente una vez por ronda; después queda bloqueado hasta la siguiente
+    fn run(&mut self, logic: impl FnOnce() -> ThreadSignal) -> ThreadSignal {
+        let round = self.sync.round.load(Ordering::Relaxed);
+        if self.last_round == Some(round) {
+            return ThreadSignal::Block;
+        }
+        self.last_round = Some(round);
+
+        let signal = logic();
+        if signal == ThreadSignal::Block {
+            // no pudo hacer su paso (p. ej. no tomo la ciudad): lo reintenta en
+            // esta misma ronda, despues de los demas, sin contar como avance
+            self.last_round = None;
+            return ThreadSignal::Yield;
+        }
+        let _ = my_thread_progress();
+        if signal == ThreadSignal::Exit {
+            self.sync.live.fetch_sub(1, Ordering::Relaxed);
+        }
+        // el paso termino: recien ahora cuenta para la ronda
+        let _ = my_waitgroup_done(&self.sync.pending);
+        match signal {
+            ThreadSignal::Yield => ThreadSignal::Block,
+            other => other,
+        }
+    }
+}
+
 pub fn run_simulation() {
+    // al reproducir, la semilla grabada manda sobre THREADCITY_SEED
+    let replay = load_replay();
+    let seed = replay.as_ref().and_then(|rec| rec.seed).unwrap_or_else(simulation_seed);
+    run_simulation_inner(seed, replay);
+}
+
+/// Corre la simulación con una semilla fija: misma semilla, mismo log
+pub fn run_simulation_with_seed(seed: u64) {
+    run_simulation_inner(seed, load_replay());
+}
+
+fn run_simulation_inner(seed: u64, replay: Option<SchedRecording>) {
     tc_log!("\n╔════════════════════════════════════════════════════════════╗");
     tc_log!("║           ThreadCity - Simulación                           ║");
     tc_log!("╚════════════════════════════════════════════════════════════╝\n");
+    tc_log!("🎲 Semilla: {} (repetir con {}={})", seed, SEED_ENV_VAR, seed);
+
+    // Una sola semilla alimenta al scheduler, al spawner de la ciudad y a los agentes
+    runtime_set_seed(seed).expect("no se pudo sembrar el scheduler");
+    let trace_path = std::env::var(TRACE_ENV_VAR).ok();
+    if trace_path.is_some() {
+        runtime_trace_enable().expect("no se pudo activar la traza");
+    }
+    let mut record_path = std::env::var(RECORD_ENV_VAR).ok();
+    if let Some(rec) = replay {
+        // el runtime tiene un solo modo: grabar reemplazaria la reproducción
+        if let Some(path) = record_path.take() {
+            tc_log!(
+                "❌ {} y {} no se pueden usar juntos: se reproduce sin grabar {}",
+                REPLAY_ENV_VAR,
+                RECORD_ENV_VAR,
+                path
+            );
+        }
+        tc_log!("⏪ Reproduciendo {} decisiones del scheduler", rec.decisions.len());
+        runtime_replay_start(rec).expect("no se pudo empezar la reproducción");
+    }
+    if record_path.is_some() {
+        runtime_record_start().expect("no se pudo empezar la grabación");
+    }
+    if let Ok(path) = std::env::var(SCHED_LOG_ENV_VAR) {
+        match mypthreads::log::log_to_file(&path) {
+            Ok(()) => mypthreads::log::set_target_level("sched", Some(mypthreads::log::Level::Debug)),
+            Err(e) => tc_log!("❌ No se pudo abrir el log del scheduler {}: {}", path, e),
+        }
+    }
+    let mut rng = StdRng::seed_from_u64(seed ^ 0x9E37_79B9_7F4A_7C15);
+    let groups = AgentGroups::create();
+    let steps = StepSync::new();
+    let services = Services::start(steps.status());
 
     // --- CREACIÓN DE LA CIUDAD ---
-    let (city, layout) = create_city();
+    let (city, layout) = create_city_with_seed(seed);
     let shared_city = create_shared_city(city);
 
     // --- CONTADORES TOTALES ---
@@ -43,42 +253,60 @@ pub fn run_simulation() {
     // --- CREACIÓN INICIAL DE AGENTES ---
     for i in 0..5 {
         spawn_car(
+            &mut rng,
             i + 1,
             &layout,
             &shared_city,
+            &groups,
+            &steps,
             std::sync::Arc::clone(&total_cars),
         );
     }
     for i in 0..2 {
         spawn_ambulance(
+            &mut rng,
             i + 100,
             &layout,
             &shared_city,
+            &groups,
+            &steps,
             std::sync::Arc::clone(&total_ambulances),
         );
     }
     tc_log!("Creando camiones de carga aleatorios...");
     for i in 0..4 {
         spawn_cargo_truck(
+            &mut rng,
             200 + i,
             &layout,
             &shared_city,
+            &groups,
+            &steps,
             std::sync::Arc::clone(&total_trucks),
         );
     }
     spawn_boat(
+        &mut rng,
         300,
         &layout,
         &shared_city,
+        &groups,
+        &steps,
         std::sync::Arc::clone(&total_boats),
     );
 
     tc_log!("Agentes iniciales creados.");
 
+    // --- MONEDA DE EMERGENCIA ---
+    // Los camiones en emergencia se reparten este fondo en lugar de recibir tiquetes fijos.
+    const PLANT_EMERGENCY_FUNDING: u32 = 1000;
+    let emergency_currency = my_currency_create("plant emergency", PLANT_EMERGENCY_FUNDING)
+        .expect("no se pudo crear la moneda de emergencia");
+
     // --- PARÁMETROS DE SIMULACIÓN ---
     const SIMULATION_STEPS: u32 = 100;
     const TIME_PER_STEP_MS: u64 = 500;
-    const SCHEDULER_CYCLES_PER_STEP: usize = 20;
+    const UI_STEP_PACING: Duration = Duration::from_millis(50);
     tc_log!(
         "Iniciando simulación... Pasos: {}, Tiempo/Paso: {}ms\n",
         SIMULATION_STEPS,
@@ -88,12 +316,7 @@ pub fn run_simulation() {
     // --- BUCLE PRINCIPAL DE SIMULACIÓN ---
     for step in 0..SIMULATION_STEPS {
         let new_agents = {
-            let mut city_lock = loop {
-                if let Some(lock) = shared_city.try_enter() {
-                    break lock;
-                }
-                thread::sleep(Duration::from_micros(100));
-            };
+            let mut city_lock = shared_city.write().expect("no se pudo tomar la ciudad");
 
             city_lock.update(TIME_PER_STEP_MS);
             city_lock.check_plant_deadlines();
@@ -104,7 +327,6 @@ pub fn run_simulation() {
             );
             let agents = city_lock.update_spawner();
             drop(city_lock);
-            shared_city.force_unlock_for_main();
             agents
         };
 
@@ -112,21 +334,30 @@ pub fn run_simulation() {
             let new_id = get_next_agent_id();
             match agent_type {
                 AgentType::Car => spawn_car(
+                    &mut rng,
                     new_id,
                     &layout,
                     &shared_city,
+                    &groups,
+                    &steps,
                     std::sync::Arc::clone(&total_cars),
                 ),
                 AgentType::Ambulance => spawn_ambulance(
+                    &mut rng,
                     new_id,
                     &layout,
                     &shared_city,
+                    &groups,
+                    &steps,
                     std::sync::Arc::clone(&total_ambulances),
                 ),
                 AgentType::Boat => spawn_boat(
+                    &mut rng,
                     new_id,
                     &layout,
                     &shared_city,
+                    &groups,
+                    &steps,
                     std::sync::Arc::clone(&total_boats),
                 ),
                 AgentType::CargoTruck(_) => {}
@@ -134,47 +365,48 @@ pub fn run_simulation() {
         }
 
         {
-            let tids_to_promote: Vec<u32> = {
-                let city_lock = loop {
-                    if let Some(lock) = shared_city.try_enter() {
-                        break lock;
-                    }
-                    thread::sleep(Duration::from_micros(100));
-                };
+            let supplies_at_risk: Vec<SupplyKind> = {
+                let city_lock = shared_city.read().expect("no se pudo tomar la ciudad");
 
-                let mut tids = Vec::new();
+                let mut kinds = Vec::new();
                 for plant in &city_lock.plants {
                     if plant.status == PlantStatus::AtRisk {
                         if let Some(needed_supply) =
                             plant.active_risk_kind(city_lock.current_time())
                         {
-                            for agent_info in city_lock.agents.values() {
-                                if let AgentType::CargoTruck(cargo) = agent_info.agent_type {
-                                    if cargo == needed_supply {
-                                        tids.push(agent_info.vehicle.tid);
-                                    }
-                                }
+                            if !kinds.contains(&needed_supply) {
+                                kinds.push(needed_supply);
                             }
                         }
                     }
                 }
                 drop(city_lock);
-                shared_city.force_unlock_for_main();
-                tids
+                kinds
             };
-            for tid in tids_to_promote {
-                println!(
-                    "📢 ¡Activando protocolo de emergencia para el Hilo {}!",
-                    tid
-                );
-                my_thread_chsched(tid, SchedulerParams::Lottery { tickets: 1000 });
+            // toda la clase de camiones que lleva el insumo pasa a la moneda de emergencia
+            for supply in supplies_at_risk {
+                match my_group_chsched(
+                    groups.of(AgentType::CargoTruck(supply)),
+                    SchedulerParams::LotteryFunded {
+                        currency: emergency_currency,
+                        tickets: 1,
+                    },
+                ) {
+                    Ok(promoted) => println!(
+                        "📢 ¡Activando protocolo de emergencia para los camiones de {:?} ({} hilos)!",
+                        supply, promoted
+                    ),
+                    Err(e) => tc_log!("❌ No se pudo activar la emergencia para {:?}: {}", supply, e),
+                }
             }
         }
 
-        runtime_unblock_all();
-        runtime_run_cycles(SCHEDULER_CYCLES_PER_STEP);
+        if let Err(e) = steps.run_round() {
+            tc_log!("❌ La ronda del paso {} no terminó: {}", step, e);
+        }
 
-        thread::sleep(Duration::from_millis(50));
+        // solo para que la interfaz alcance a mostrar el paso
+        thread::sleep(UI_STEP_PACING);
     }
 
     tc_log!("\n╔════════════════════════════════════════════════════════════╗");
@@ -197,134 +429,206 @@ pub fn run_simulation() {
         total_boats.load(Ordering::Relaxed)
     );
     tc_log!("╚════════════════════════════════════════════════════════════╝\n");
+
+    log_scheduler_report(&groups);
+
+    if let Some(path) = trace_path {
+        match runtime_trace_write(&path) {
+            Ok(()) => tc_log!("🧵 Traza del scheduler escrita en {}", path),
+            Err(e) => tc_log!("❌ No se pudo escribir la traza en {}: {}", path, e),
+        }
+    }
+    if let Some(path) = record_path {
+        match runtime_record_write(&path) {
+            Ok(()) => tc_log!("⏺️ Decisiones del scheduler grabadas en {}", path),
+            Err(e) => tc_log!("❌ No se pudo escribir la grabación en {}: {}", path, e),
+        }
+    }
+    if let Ok(Some(div)) = runtime_replay_divergence() {
+        tc_log!(
+            "⚠️ La reproducción divergió en la decisión {}: se esperaba '{}', había {:?}",
+            div.index, div.expected, div.found
+        );
+    }
+    services.shutdown();
+}
+
+/// Reporte de equidad: cuánto CPU recibió cada clase de agente según las estadísticas del runtime
+fn log_scheduler_report(groups: &AgentGroups) {
+    // por clase: (hilos, despachos, ms compitiendo por CPU, ms en cola de listos)
+    let classes = ["Carros", "Ambulancias", "Camiones", "Barcos"];
+    let class_groups = [
+        vec![groups.cars],
+        vec![groups.ambulances],
+        vec![groups.radioactive_trucks, groups.water_trucks],
+        vec![groups.boats],
+    ];
+    let mut rows = [(0usize, 0u64, 0u64, 0u64); 4];
+    for (row, class) in rows.iter_mut().zip(&class_groups) {
+        for stats in class.iter().filter_map(|&g| my_group_stats(g).ok()) {
+            row.0 += stats.threads;
+            row.1 += stats.dispatches;
+            row.2 += stats.ready_ms + stats.run_ms;
+            row.3 += stats.ready_ms;
+        }
+    }
+
+    tc_log!("╔════════════════════════════════════════════════════════════╗");
+    tc_log!("║              Equidad del Scheduler                         ║");
+    tc_log!("╠════════════════════════════════════════════════════════════╣");
+    tc_log!(
+        "║ {:<12} {:>6} {:>10} {:>12} {:>14} ║",
+        "Clase", "Hilos", "Despachos", "Espera/desp", "Desp/s activo"
+    );
+    for (name, (threads, dispatches, active_ms, ready_ms)) in classes.iter().zip(rows) {
+        let wait_per_dispatch = ready_ms as f64 / dispatches.max(1) as f64;
+        let dispatch_rate = dispatches as f64 * 1000.0 / active_ms.max(1) as f64;
+        tc_log!(
+            "║ {:<12} {:>6} {:>10} {:>10.1}ms {:>14.2} ║",
+            name, threads, dispatches, wait_per_dispatch, dispatch_rate
+        );
+    }
+    let summary = runtime_stats_summary().unwrap_or_default();
+    tc_log!("╠════════════════════════════════════════════════════════════╣");
+    tc_log!("║ Despachos totales: {:>39} ║", summary.dispatches);
+    tc_log!("║ Deadlines perdidos: {:>38} ║", summary.deadline_misses);
+    tc_log!("╚════════════════════════════════════════════════════════════╝\n");
 }
 
 // --- FUNCIONES SPAWN ---
-fn spawn_car(id: u32, layout: &CityLayout, city: &SharedCity, counter: std::sync::Arc<AtomicU32>) {
+fn spawn_car(
+    rng: &mut StdRng,
+    id: u32,
+    layout: &CityLayout,
+    city: &SharedCity,
+    groups: &AgentGroups,
+    steps: &StepSync,
+    counter: std::sync::Arc<AtomicU32>,
+) {
     counter.fetch_add(1, Ordering::Relaxed);
-    let mut rng = rng();
-    let origin = random_position(&mut rng, layout);
-    let dest = random_destination(&mut rng, layout, origin);
+    let origin = random_position(rng, layout);
+    let dest = random_destination(rng, layout, origin);
     let city_clone = city.clone();
     let layout_clone = layout.clone();
     let mut pos = origin;
     let mut state = AgentState::Traveling;
     let mut crossing_steps = 0u32;
+    let mut agent_step = steps.agent();
 
     tc_log!("🚗 Carro-{} creado: {:?} -> {:?}", id, origin, dest);
 
-    let tid = my_thread_create(
+    let tid = my_thread_create_in_group(
+        groups.of(AgentType::Car),
         &format!("Car-{}", id),
         SchedulerParams::Lottery { tickets: 10 },
         Box::new(move |tid_interno, current_tickets| {
-            vehicle_logic(
-                tid_interno,
-                id,
-                AgentType::Car,
-                current_tickets,
-                &mut pos,
-                dest,
-                &mut state,
-                &mut crossing_steps,
-                &city_clone,
-                &layout_clone,
-            )
+            agent_step.run(|| {
+                vehicle_logic(
+                    tid_interno,
+                    id,
+                    AgentType::Car,
+                    current_tickets,
+                    &mut pos,
+                    dest,
+                    &mut state,
+                    &mut crossing_steps,
+                    &city_clone,
+                    &layout_clone,
+                )
+            })
         }),
-    );
+    )
+    .expect("no se pudo crear el hilo del agente");
     let agent_info = AgentInfo {
         vehicle: Vehicle::new(id, tid, origin, dest),
         agent_type: AgentType::Car,
     };
 
-    loop {
-        if let Some(mut city_lock) = city.try_enter() {
-            city_lock.agents.insert(tid, agent_info);
-            drop(city_lock);
-            city.force_unlock_for_main();
-            break;
-        }
-        thread::sleep(Duration::from_micros(50));
-    }
+    city
+        .write()
+        .expect("no se pudo tomar la ciudad")
+        .agents
+        .insert(tid, agent_info);
 }
 
 fn spawn_ambulance(
+    rng: &mut StdRng,
     id: u32,
     layout: &CityLayout,
     city: &SharedCity,
+    groups: &AgentGroups,
+    steps: &StepSync,
     counter: std::sync::Arc<AtomicU32>,
 ) {
     counter.fetch_add(1, Ordering::Relaxed);
-    let mut rng = rng();
-    let origin = random_position(&mut rng, layout);
-    let dest = random_destination(&mut rng, layout, origin);
+    let origin = random_position(rng, layout);
+    let dest = random_destination(rng, layout, origin);
     let city_clone = city.clone();
     let layout_clone = layout.clone();
     let mut pos = origin;
     let mut state = AgentState::Traveling;
     let mut crossing_steps = 0u32;
+    let mut agent_step = steps.agent();
 
     tc_log!("🚑 Ambulancia-{} creada: {:?} -> {:?}", id, origin, dest);
 
-    let tid = my_thread_create(
+    let tid = my_thread_create_in_group(
+        groups.of(AgentType::Ambulance),
         &format!("Ambulance-{}", id),
         SchedulerParams::Lottery { tickets: 100 },
         Box::new(move |tid_interno, current_tickets| {
-            vehicle_logic(
-                tid_interno,
-                id,
-                AgentType::Ambulance,
-                current_tickets,
-                &mut pos,
-                dest,
-                &mut state,
-                &mut crossing_steps,
-                &city_clone,
-                &layout_clone,
-            )
+            agent_step.run(|| {
+                vehicle_logic(
+                    tid_interno,
+                    id,
+                    AgentType::Ambulance,
+                    current_tickets,
+                    &mut pos,
+                    dest,
+                    &mut state,
+                    &mut crossing_steps,
+                    &city_clone,
+                    &layout_clone,
+                )
+            })
         }),
-    );
+    )
+    .expect("no se pudo crear el hilo del agente");
     let ambulance = Ambulance::new(id, tid, (origin.x, origin.y), (dest.x, dest.y));
     let agent_info = AgentInfo {
         vehicle: ambulance.inner,
         agent_type: AgentType::Ambulance,
     };
 
-    loop {
-        if let Some(mut city_lock) = city.try_enter() {
-            city_lock.agents.insert(tid, agent_info);
-            drop(city_lock);
-            city.force_unlock_for_main();
-            break;
-        }
-        thread::sleep(Duration::from_micros(50));
-    }
+    city
+        .write()
+        .expect("no se pudo tomar la ciudad")
+        .agents
+        .insert(tid, agent_info);
 }
 
 /// Spawn un camión de carga
 fn spawn_cargo_truck(
+    rng: &mut StdRng,
     id: u32,
     layout: &CityLayout,
     city: &SharedCity,
+    groups: &AgentGroups,
+    steps: &StepSync,
     counter: std::sync::Arc<AtomicU32>,
 ) {
     counter.fetch_add(1, Ordering::Relaxed);
-    let mut rng = rng();
-    let origin = random_position(&mut rng, layout);
-    let cargo = random_supply_kind(&mut rng);
+    let origin = random_position(rng, layout);
+    let cargo = random_supply_kind(rng);
     let destination: Coord;
     let deadline: u64;
 
     {
-        let city_lock = loop {
-            if let Some(lock) = city.try_enter() {
-                break lock;
-            }
-            thread::sleep(Duration::from_micros(100));
-        };
+        let city_lock = city.read().expect("no se pudo tomar la ciudad");
 
         let plant = city_lock
             .plants
-            .choose(&mut rng)
+            .choose(rng)
             .expect("No hay plantas")
             .clone();
         destination = plant.loc;
@@ -336,7 +640,6 @@ fn spawn_cargo_truck(
         deadline = city_lock.current_time() + supply_spec.deadline_ms;
 
         drop(city_lock);
-        city.force_unlock_for_main();
     }
 
     /// Lógica del camión de carga
@@ -345,6 +648,7 @@ fn spawn_cargo_truck(
     let mut pos = origin;
     let mut state = AgentState::Traveling;
     let mut crossing_steps = 0u32;
+    let mut agent_step = steps.agent();
     let cargo_for_thread = cargo;
 
     tc_log!(
@@ -356,24 +660,28 @@ fn spawn_cargo_truck(
         deadline
     );
 
-    let tid = my_thread_create(
+    let tid = my_thread_create_in_group(
+        groups.of(AgentType::CargoTruck(cargo)),
         &format!("Truck-{}", id),
         SchedulerParams::RealTime { deadline },
         Box::new(move |tid_interno, current_tickets| {
-            cargo_truck_logic(
-                tid_interno,
-                id,
-                cargo_for_thread,
-                current_tickets,
-                &mut pos,
-                destination,
-                &mut state,
-                &mut crossing_steps,
-                &city_clone,
-                &layout_clone,
-            )
+            agent_step.run(|| {
+                cargo_truck_logic(
+                    tid_interno,
+                    id,
+                    cargo_for_thread,
+                    current_tickets,
+                    &mut pos,
+                    destination,
+                    &mut state,
+                    &mut crossing_steps,
+                    &city_clone,
+                    &layout_clone,
+                )
+            })
         }),
-    );
+    )
+    .expect("no se pudo crear el hilo del agente");
     let truck = CargoTruck::new(
         id,
         tid,
@@ -387,23 +695,26 @@ fn spawn_cargo_truck(
     };
 
     /// Registrar el camión en la ciudad
-    loop {
-        if let Some(mut city_lock) = city.try_enter() {
-            city_lock.agents.insert(tid, agent_info);
-            drop(city_lock);
-            city.force_unlock_for_main();
-            break;
-        }
-        thread::sleep(Duration::from_micros(50));
-    }
+    city
+        .write()
+        .expect("no se pudo tomar la ciudad")
+        .agents
+        .insert(tid, agent_info);
 }
 
 /// Spawn un barco
-fn spawn_boat(id: u32, layout: &CityLayout, city: &SharedCity, counter: std::sync::Arc<AtomicU32>) {
+fn spawn_boat(
+    rng: &mut StdRng,
+    id: u32,
+    layout: &CityLayout,
+    city: &SharedCity,
+    groups: &AgentGroups,
+    steps: &StepSync,
+    counter: std::sync::Arc<AtomicU32>,
+) {
     counter.fetch_add(1, Ordering::Relaxed);
     let city_clone = city.clone();
     let layout_clone = layout.clone();
-    let mut rng = rand::rng();
 
     // El origen del barco es desde la parte inferior de la pantalla, en la columna del río.
     let origin = Coord::new(layout.grid_rows - 1, layout.river_column);
@@ -427,41 +738,42 @@ fn spawn_boat(id: u32, layout: &CityLayout, city: &SharedCity, counter: std::syn
     let mut pos = origin;
     let mut state = AgentState::Traveling;
     let mut crossing_steps = 0u32;
+    let mut agent_step = steps.agent();
 
     tc_log!("⛵ Barco-{} creado: {:?} -> {:?}", id, origin, dest);
 
-    let tid = my_thread_create(
+    let tid = my_thread_create_in_group(
+        groups.of(AgentType::Boat),
         &format!("Boat-{}", id),
         SchedulerParams::RoundRobin,
         Box::new(move |tid_interno, current_tickets| {
-            boat_logic(
-                tid_interno,
-                id,
-                current_tickets,
-                &mut pos,
-                dest,
-                &mut state,
-                &mut crossing_steps,
-                &city_clone,
-                &layout_clone,
-            )
+            agent_step.run(|| {
+                boat_logic(
+                    tid_interno,
+                    id,
+                    current_tickets,
+                    &mut pos,
+                    dest,
+                    &mut state,
+                    &mut crossing_steps,
+                    &city_clone,
+                    &layout_clone,
+                )
+            })
         }),
-    );
+    )
+    .expect("no se pudo crear el hilo del agente");
     let boat = Boat::new(id, tid, (origin.x, origin.y), (dest.x, dest.y));
     let agent_info = AgentInfo {
         vehicle: boat.inner,
         agent_type: AgentType::Boat,
     };
 
-    loop {
-        if let Some(mut city_lock) = city.try_enter() {
-            city_lock.agents.insert(tid, agent_info);
-            drop(city_lock);
-            city.force_unlock_for_main();
-            break;
-        }
-        thread::sleep(Duration::from_micros(50));
-    }
+    city
+        .write()
+        .expect("no se pudo tomar la ciudad")
+        .agents
+        .insert(tid, agent_info);
 }
 
 // --- LÓGICA DE AGENTES Y HELPERS ---
@@ -499,9 +811,9 @@ fn vehicle_logic(
             ThreadSignal::Yield
         }
         AgentState::WaitingForBridge => {
-            let city_lock = match city.try_enter() {
-                Some(lock) => lock,
-                None => return ThreadSignal::Block,
+            let city_lock = match city.read() {
+                Ok(lock) => lock,
+                Err(_) => return ThreadSignal::Block,
             };
 
             let bridge_id = nearest_bridge(layout, pos.x);
@@ -519,7 +831,7 @@ fn vehicle_logic(
                 tc_log!("[{}] 🚑 AMBULANCIA pasando directamente", id);
                 can_cross = true;
             } else {
-                let final_priority = current_tickets as u8;
+                let final_priority = current_tickets.min(u8::MAX as u32) as u8;
                 if bridge.try_cross(tid, final_priority, direction) {
                     tc_log!("[{}] Comenzó a cruzar puente {}", id, bridge_id);
                     can_cross = true;
@@ -532,7 +844,6 @@ fn vehicle_logic(
             }
 
             drop(city_lock);
-            city.force_unlock_for_main();
             return ThreadSignal::Yield;
         }
         AgentState::CrossingBridge => {
@@ -547,9 +858,9 @@ fn vehicle_logic(
                 tc_log!("[{}] Cruzó el puente, pos: {:?}", id, pos);
                 *state = AgentState::Traveling;
 
-                let city_lock = match city.try_enter() {
-                    Some(lock) => lock,
-                    None => return ThreadSignal::Yield,
+                let city_lock = match city.read() {
+                    Ok(lock) => lock,
+                    Err(_) => return ThreadSignal::Yield,
                 };
 
                 let bridge_id = nearest_bridge(layout, pos.x);
@@ -561,7 +872,6 @@ fn vehicle_logic(
                 }
 
                 drop(city_lock);
-                city.force_unlock_for_main();
                 return ThreadSignal::Yield;
             }
             ThreadSignal::Yield
@@ -584,9 +894,9 @@ fn cargo_truck_logic(
     layout: &CityLayout,
 ) -> ThreadSignal {
     if *state != AgentState::Arrived && pos.x == dest.x && pos.y == dest.y {
-        let mut city_lock = match city.try_enter() {
-            Some(lock) => lock,
-            None => return ThreadSignal::Block,
+        let mut city_lock = match city.write() {
+            Ok(lock) => lock,
+            Err(_) => return ThreadSignal::Block,
         };
 
         let current_time = city_lock.current_time();
@@ -609,7 +919,6 @@ fn cargo_truck_logic(
         *state = AgentState::Arrived;
 
         drop(city_lock);
-        city.force_unlock_for_main();
         // El camión termina su ejecución al llegar.
         return ThreadSignal::Exit;
     }
@@ -661,9 +970,9 @@ fn boat_logic(
             ThreadSignal::Yield
         }
         AgentState::WaitingForBridge => {
-            let city_lock = match city.try_enter() {
-                Some(lock) => lock,
-                None => return ThreadSignal::Block,
+            let city_lock = match city.read() {
+                Ok(lock) => lock,
+                Err(_) => return ThreadSignal::Block,
             };
 
             let bridge = city_lock.get_bridge(3).expect("Puente 3 no encontrado");
@@ -676,7 +985,6 @@ fn boat_logic(
             }
 
             drop(city_lock);
-            city.force_unlock_for_main();
             return ThreadSignal::Yield;
         }
         AgentState::CrossingBridge => {
@@ -686,16 +994,15 @@ fn boat_logic(
                 tc_log!("[Boat-{}] ⛵ Cruzó el puente, pos: {:?}", id, pos);
                 *state = AgentState::Traveling;
 
-                let city_lock = match city.try_enter() {
-                    Some(lock) => lock,
-                    None => return ThreadSignal::Yield,
+                let city_lock = match city.read() {
+                    Ok(lock) => lock,
+                    Err(_) => return ThreadSignal::Yield,
                 };
 
                 let bridge = city_lock.get_bridge(3).expect("Puente 3 no encontrado");
                 bridge.boat_exit();
 
                 drop(city_lock);
-                city.force_unlock_for_main();
                 return ThreadSignal::Yield;
             }
             ThreadSignal::Yield
@@ -0,0 +1,250 @@
+//! Servidor de control y sinks del log de la simulación, como hilos verdes.
+//!
+//! Esperan en el reactor de mypthreads (`my_accept`, `my_read`, `my_write`)
+//! en lugar de bloquear al runtime. Un archivo no se puede esperar con epoll,
+//! así que el sink de archivo escribe con `my_spawn_blocking`. Se encienden
+//! solo con su variable de entorno, así una corrida normal reparte el CPU
+//! igual que siempre.
+
+use crate::log;
+use crate::tc_log;
+use mypthreads::mypthreads_api::{
+    my_accept, my_read, my_spawn_blocking, my_thread_cancel, my_thread_create, my_thread_detach, my_thread_progress,
+    my_waitgroup_add, my_waitgroup_done, my_waitgroup_init, my_waitgroup_wait, my_write, runtime_stats_summary,
+    MyWaitGroup, SchedulerParams,
+};
+use mypthreads::{MyResult, ThreadId, ThreadSignal};
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Variable de entorno con la ruta del socket Unix del servidor de control
+pub const CONTROL_ENV_VAR: &str = "THREADCITY_CONTROL";
+
+/// Variable de entorno con un archivo donde copiar el log de la simulación
+pub const LOG_FILE_ENV_VAR: &str = "THREADCITY_LOG_FILE";
+
+/// Lo que el servidor contesta a `status`
+pub type StatusFn = Arc<dyn Fn() -> String + Send + Sync>;
+
+/// conexiones que pidieron `tail`: reciben cada línea del log
+#[derive(Clone, Default)]
+struct Subscribers(Arc<Mutex<Vec<OwnedFd>>>);
+
+impl Subscribers {
+    fn lock(&self) -> MutexGuard<'_, Vec<OwnedFd>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Hilos de servicio de una corrida
+pub struct Services {
+    sink: Option<LogSink>,
+    control: Option<(ThreadId, PathBuf)>,
+}
+
+/// lado del hilo principal del sink del log
+struct LogSink {
+    closing: Arc<AtomicBool>,
+    finished: Arc<MyWaitGroup>,
+}
+
+impl Services {
+    /// Arranca los servicios que piden las variables de entorno
+    pub fn start(status: StatusFn) -> Self {
+        let mut services = Services {
+            sink: None,
+            control: None,
+        };
+        let subscribers = Subscribers::default();
+        if let Ok(path) = std::env::var(CONTROL_ENV_VAR) {
+            match start_control(&path, status, subscribers.clone()) {
+                Ok(tid) => {
+                    tc_log!("🛰️ Servidor de control escuchando en {}", path);
+                    services.control = Some((tid, path.into()));
+                }
+                Err(e) => tc_log!("❌ No se pudo abrir el servidor de control en {}: {}", path, e),
+            }
+        }
+        let log_file = std::env::var(LOG_FILE_ENV_VAR).ok().and_then(|path| {
+            File::create(&path)
+                .inspect_err(|e| tc_log!("❌ No se pudo abrir el log {}: {}", path, e))
+                .ok()
+        });
+        // sin servidor ni archivo no hay a quién copiarle el log
+        if services.control.is_none() && log_file.is_none() {
+            return services;
+        }
+        match start_sink(subscribers, log_file) {
+            Ok(sink) => services.sink = Some(sink),
+            Err(e) => tc_log!("❌ No se pudo arrancar el sink del log: {}", e),
+        }
+        services
+    }
+
+    /// Vacía lo que queda del log en los sinks y cierra el servidor de control
+    pub fn shutdown(self) {
+        if let Some(sink) = self.sink {
+            sink.closing.store(true, Ordering::Release);
+            log::notify_sink();
+            if let Err(e) = my_waitgroup_wait(&sink.finished) {
+                tc_log!("❌ El sink del log no terminó: {}", e);
+            }
+            log::close_sink_queue();
+        }
+        if let Some((tid, path)) = self.control {
+            let _ = my_thread_cancel(tid);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// escribe todo `buf`, esperando en el reactor si el otro lado va lento
+fn write_all(fd: RawFd, mut buf: &[u8]) -> MyResult<()> {
+    while !buf.is_empty() {
+        let n = my_write(fd, buf)?;
+        buf = &buf[n..];
+    }
+    Ok(())
+}
+
+fn start_control(path: &str, status: StatusFn, subscribers: Subscribers) -> io::Result<ThreadId> {
+    // el socket que dejó otra corrida no deja hacer bind; cualquier otro archivo se respeta
+    if std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    listener.set_nonblocking(true)?;
+
+    my_thread_create(
+        "control",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| loop {
+            match my_accept(listener.as_raw_fd()) {
+                Ok(conn) => {
+                    let conn = unsafe { OwnedFd::from_raw_fd(conn) };
+                    if let Err(e) = serve_connection(conn, status.clone(), subscribers.clone()) {
+                        tc_log!("❌ No se pudo atender una conexión de control: {}", e);
+                    }
+                }
+                Err(e) => {
+                    tc_log!("❌ El servidor de control se detuvo: {}", e);
+                    return ThreadSignal::Exit;
+                }
+            }
+        }),
+    )
+    .map_err(io::Error::other)
+}
+
+/// un hilo por conexión: lee comandos por línea y contesta cada uno
+fn serve_connection(conn: OwnedFd, status: StatusFn, subscribers: Subscribers) -> MyResult<ThreadId> {
+    let mut conn = Some(conn);
+    let mut pending = Vec::new();
+    let tid = my_thread_create(
+        "control-conn",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            let mut buf = [0u8; 256];
+            'session: while let Some(fd) = conn.as_ref().map(|c| c.as_raw_fd()) {
+                let n = match my_read(fd, &mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                pending.extend_from_slice(&buf[..n]);
+                while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    let reply = match String::from_utf8_lossy(&line).trim() {
+                        "" => continue,
+                        "quit" => break 'session,
+                        "tail" => {
+                            // de aquí en adelante la conexión es del sink
+                            let _ = write_all(fd, "siguiendo el log\n".as_bytes());
+                            subscribers.lock().extend(conn.take());
+                            break 'session;
+                        }
+                        command => answer(command, &status),
+                    };
+                    if write_all(fd, reply.as_bytes()).is_err() {
+                        break 'session;
+                    }
+                }
+            }
+            conn = None;
+            ThreadSignal::Exit
+        }),
+    )?;
+    my_thread_detach(tid)?;
+    Ok(tid)
+}
+
+fn answer(command: &str, status: &StatusFn) -> String {
+    match command {
+        "status" => format!("{}\n", status()),
+        "stats" => match runtime_stats_summary() {
+            Ok(s) => format!(
+                "despachos {} deadlines perdidos {}\n",
+                s.dispatches, s.deadline_misses
+            ),
+            Err(e) => format!("error: {}\n", e),
+        },
+        "help" => "comandos: status, stats, tail, quit\n".to_string(),
+        other => format!("comando desconocido: {}\n", other),
+    }
+}
+
+/// El sink espera en el reactor el aviso de `log_str` y reparte las líneas
+/// nuevas; al cerrar vacía lo pendiente antes de terminar.
+fn start_sink(subscribers: Subscribers, file: Option<File>) -> io::Result<LogSink> {
+    let mut file = file.map(|f| Arc::new(Mutex::new(f)));
+    let (notify, wake) = UnixStream::pair()?;
+    notify.set_nonblocking(true)?;
+    wake.set_nonblocking(true)?;
+    let closing = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(my_waitgroup_init().map_err(io::Error::other)?);
+    my_waitgroup_add(&finished, 1).map_err(io::Error::other)?;
+
+    let (sink_closing, sink_finished) = (closing.clone(), finished.clone());
+    let tid = my_thread_create(
+        "log-sink",
+        SchedulerParams::RoundRobin,
+        Box::new(move |_, _| {
+            let mut buf = [0u8; 64];
+            loop {
+                let lines = log::take_sink_lines();
+                if !lines.is_empty() {
+                    let _ = my_thread_progress();
+                    let text = lines.join("\n") + "\n";
+                    if let Some(shared) = file.clone() {
+                        let batch = text.clone();
+                        let written = my_spawn_blocking(move || {
+                            shared.lock().unwrap_or_else(|e| e.into_inner()).write_all(batch.as_bytes())
+                        });
+                        if let Err(e) = written.map_err(io::Error::other).and_then(|r| r) {
+                            file = None;
+                            tc_log!("❌ Se dejó de copiar el log al archivo: {}", e);
+                        }
+                    }
+                    // se sacan de la lista: escribir puede suspender al hilo
+                    let mut alive = std::mem::take(&mut *subscribers.lock());
+                    alive.retain(|fd| write_all(fd.as_raw_fd(), text.as_bytes()).is_ok());
+                    subscribers.lock().append(&mut alive);
+                } else if sink_closing.load(Ordering::Acquire) || !matches!(my_read(wake.as_raw_fd(), &mut buf), Ok(1..)) {
+                    // cerrando, o sin el aviso ya no hay forma de esperar
+                    subscribers.lock().clear();
+                    let _ = my_waitgroup_done(&sink_finished);
+                    return ThreadSignal::Exit;
+                }
+            }
+        }),
+    )
+    .map_err(io::Error::other)?;
+    my_thread_detach(tid).map_err(io::Error::other)?;
+    log::open_sink_queue(notify);
+    Ok(LogSink { closing, finished })
+}
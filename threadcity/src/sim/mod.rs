@@ -1,9 +1,10 @@
 use crate::model::*;
 use crate::tc_log;
 use crate::{AgentInfo, AgentType};
-use mypthreads::sync::Shared;
+use mypthreads::sync::SharedRw;
 use mypthreads::thread::ThreadId;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::collections::HashMap; 
 
 #[derive(Debug, Clone)]
@@ -21,10 +22,21 @@ pub struct City {
     pub plants: Vec<NuclearPlant>,
     pub spawner: Spawner,
     pub agents: HashMap<ThreadId, AgentInfo>,
+    /// generador del spawner, sembrado para poder repetir una corrida
+    rng: StdRng,
 }
 
 impl City {
     pub fn new(rows: u32, cols: u32) -> Self {
+        Self::with_rng(rows, cols, StdRng::from_os_rng())
+    }
+
+    /// crea una ciudad cuyo spawner es reproducible con la misma semilla
+    pub fn with_seed(rows: u32, cols: u32, seed: u64) -> Self {
+        Self::with_rng(rows, cols, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rows: u32, cols: u32, rng: StdRng) -> Self {
         Self {
             time_ms: 0,
             grid: Grid::new(rows, cols),
@@ -37,6 +49,7 @@ impl City {
                 next_boat_spawn_ms: 5000,
             },
             agents: HashMap::new(),
+            rng,
         }
     }
 
@@ -121,7 +134,7 @@ impl City {
 
     pub fn update_spawner(&mut self) -> Vec<AgentType> {
         let mut new_agents = Vec::new();
-        let mut rng = rand::rng(); 
+        let rng = &mut self.rng;
 
         // Lógica para vehículos corregida
         if self.time_ms >= self.spawner.next_vehicle_spawn_ms {
@@ -158,9 +171,9 @@ impl City {
     }
 }
 
-pub type SharedCity = Shared<City>;
+pub type SharedCity = SharedRw<City>;
 
-/// Crea una ciudad compartida usando nuestro mutex
+/// Crea una ciudad compartida sobre el lock de lectores/escritores
 pub fn create_shared_city(city: City) -> SharedCity {
-    mypthreads::sync::shared(city)
+    mypthreads::sync::shared_rw(city)
 }